@@ -122,6 +122,25 @@ pub struct ClickStreamItem {
     pub workspace_id: Option<String>,
     pub created: DateTime<Utc>,
     pub dest: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub referer: Option<String>,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub switch: Option<String>,
+    /// Name of the conversion, for the events of a click.
+    #[serde(default)]
+    pub event: Option<String>,
+    #[serde(default)]
+    pub click_id: Option<String>,
+    #[serde(default)]
+    pub value: Option<f64>,
+    #[serde(default)]
+    pub currency: Option<String>,
     pub ip: Option<String>,
     pub continent: Option<String>,
     pub country: Option<String>,
@@ -0,0 +1,53 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use expression_lang::{parse, print};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    adapters::api::error_presenter::ErrorReponse,
+    model::{condition::Condition, error::ApiError},
+};
+
+pub fn api_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(parse_condition).service(format_condition);
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConditionText {
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ParsedCondition {
+    pub text: String,
+    pub condition: Condition,
+}
+
+#[post("/parse")]
+async fn parse_condition(body: web::Json<ConditionText>) -> impl Responder {
+    let text = body.into_inner().text;
+
+    let result = parse(&text)
+        .map_err(|error| ApiError {
+            code: 400,
+            message: error.render(&text),
+            error: Some(Box::new(error)),
+        })
+        .map_err(ErrorReponse::map_io_error)
+        .map(|condition| {
+            HttpResponse::Ok().json(ParsedCondition {
+                text: print(&condition),
+                condition,
+            })
+        });
+
+    result
+}
+
+#[post("/format")]
+async fn format_condition(body: web::Json<Condition>) -> impl Responder {
+    let condition = body.into_inner();
+
+    HttpResponse::Ok().json(ConditionText {
+        text: print(&condition),
+    })
+}
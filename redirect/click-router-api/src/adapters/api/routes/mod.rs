@@ -1,3 +1,4 @@
+pub mod conditions_controller;
 pub mod crypto_controller;
 pub mod routes_controller;
 pub mod user_settings_controller;
@@ -28,6 +28,10 @@ impl DynamoCryptoStore {
         model.item.map_or(None, |item| {
             let mut result = Keycert::new();
 
+            if let Some(hostname) = item.get("hostname") {
+                result = result.hostname(hostname.as_s().unwrap());
+            }
+
             if let Some(key) = item.get("key") {
                 result = result.key(key.as_s().unwrap().as_bytes());
             }
@@ -2,13 +2,14 @@ use std::collections::HashMap;
 
 use anyhow::{Error, Result};
 use aws_sdk_dynamodb::{operation::get_item::GetItemOutput, types::AttributeValue};
+use chrono::{DateTime, Utc};
 use serde_dynamo::from_attribute_value;
 use serde_json::Value;
 
 use crate::model::{
     route::{
-        BlockedReason, ChallengeRouting, ConditionalRouting, DestinationFormat, FileRouting,
-        RouteProperties, RouteStatus, RoutingPolicy, RoutingTerminal,
+        BlockedReason, ChallengeRouting, ClickIdParam, ConditionalRouting, DestinationFormat,
+        FileRouting, RouteProperties, RouteStatus, RoutingPolicy, RoutingTerminal,
     },
     Route,
 };
@@ -209,6 +210,11 @@ pub fn to_entity(model: GetItemOutput) -> Result<Option<Route>> {
             .get("workspace.id")
             .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
 
+        let created = item
+            .get("created")
+            .and_then(|d| DateTime::parse_from_rfc3339(d.as_s().unwrap()).ok())
+            .map(|created| created.with_timezone(&Utc));
+
         let scripts = item
             .get("script.ids")
             .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
@@ -231,18 +237,43 @@ pub fn to_entity(model: GetItemOutput) -> Result<Option<Route>> {
             .map_or(None, |p| Some(from_attribute_value(p.to_owned()).unwrap()));
 
         let opengraph = item.get("blocked").map_or(false, |d| *d.as_bool().unwrap());
+
+        let time_zone = item
+            .get("time_zone")
+            .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
+
+        let methods = item
+            .get("methods")
+            .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
+
+        let cors_origins = item
+            .get("cors.origins")
+            .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
+
+        let click_id = item.get("click_id.name").map(|d| ClickIdParam {
+            name: String::from(d.as_s().unwrap()),
+            cookie: item
+                .get("click_id.cookie")
+                .is_some_and(|d| *d.as_bool().unwrap()),
+        });
+
         let properties = RouteProperties {
             creator_id: creator_id,
             owner_id: owner_id,
             domain_id: domain_id,
             route_id: route_id,
             workspace_id: workspace_id,
+            created: created,
             scripts: scripts,
             tags: tags,
             custom: custom,
             native: native,
             bundling: bundling,
             opengraph: opengraph,
+            time_zone: time_zone,
+            methods: methods,
+            cors_origins: cors_origins,
+            click_id: click_id,
         };
 
         //policy
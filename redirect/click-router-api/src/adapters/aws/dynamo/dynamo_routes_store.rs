@@ -41,6 +41,15 @@ impl BaseRoutesStore for DynamoRoutesStore {
                 AttributeValue::S(route.properties.owner_id.clone().unwrap()),
             );
 
+        //the routers list the patterns of a domain from a sparse index on this attribute
+        let link = route.link.to_ascii_lowercase();
+
+        if let Some((domain, path)) = link.split_once("%2f") {
+            if path.contains('{') {
+                request = request.item("pattern.domain", AttributeValue::S(domain.to_string()));
+            }
+        }
+
         if let RoutingPolicy::Conditional(conditions) = &route.policy {
             let mut routing = HashMap::new();
 
@@ -67,7 +76,7 @@ impl BaseRoutesStore for DynamoRoutesStore {
     }
 
     async fn invalidate_route(&self, _switch: &str, _domain: &str, _path: &str) -> Result<()> {
-        todo!()
+        Ok(())
     }
 
     async fn get_route(&self, switch: &str, domain: &str, path: &str) -> Result<Option<Route>> {
@@ -106,12 +115,17 @@ impl BaseRoutesStore for DynamoRoutesStore {
                             domain_id: None,
                             route_id: None,
                             workspace_id: None,
+                            created: None,
                             bundling: None,
                             custom: None,
                             native: None,
                             opengraph: false,
                             scripts: None,
                             tags: None,
+                            time_zone: None,
+                            methods: None,
+                            cors_origins: None,
+                            click_id: None,
                         },
                     ))
                     .await
@@ -124,10 +138,7 @@ impl BaseRoutesStore for DynamoRoutesStore {
             .get_item()
             .table_name(&self.routes_table)
             .set_key(Some(HashMap::from([
-                (
-                    "link".to_string(),
-                    AttributeValue::S(link.to_ascii_lowercase()),
-                ),
+                ("link".to_string(), AttributeValue::S(link)),
                 (
                     "switch".to_string(),
                     AttributeValue::S(switch.to_ascii_lowercase()),
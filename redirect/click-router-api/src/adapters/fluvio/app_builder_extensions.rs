@@ -0,0 +1,21 @@
+use tracing::info;
+
+use crate::app_builder::AppBuilder;
+
+use super::fluvio_change_publisher::FluvioChangePublisher;
+
+impl AppBuilder {
+    pub async fn with_fluvio(&mut self) -> &mut Self {
+        info!("{}", "WITH FLUVIO CHANGE PUBLISHER");
+
+        let settings = self
+            .settings
+            .fluvio
+            .clone()
+            .expect("No fluvio settings specified.");
+
+        let publisher = FluvioChangePublisher::new(&settings.change_stream).await;
+
+        self.with_change_publisher(Box::new(publisher))
+    }
+}
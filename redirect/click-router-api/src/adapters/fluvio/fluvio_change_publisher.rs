@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use fluvio::{spu::SpuSocketPool, Fluvio, FluvioClusterConfig, RecordKey, TopicProducer};
+
+use crate::{core::BaseChangePublisher, model::ChangeEvent};
+
+use super::fluvio_settings::ChangeStreamConfig;
+
+#[derive(Clone)]
+pub struct FluvioChangePublisher {
+    producer: Arc<TopicProducer<SpuSocketPool>>,
+}
+
+impl FluvioChangePublisher {
+    pub async fn new(settings: &ChangeStreamConfig) -> Self {
+        let fluvio = Fluvio::connect_with_config(&FluvioClusterConfig::new(&settings.host))
+            .await
+            .expect("Can not connect to fluvio cluster.");
+
+        let producer = fluvio
+            .topic_producer(&settings.topic)
+            .await
+            .expect("Can not build change publisher topic producer.");
+
+        Self {
+            producer: Arc::new(producer),
+        }
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseChangePublisher for FluvioChangePublisher {
+    async fn publish(&self, event: &ChangeEvent) -> Result<()> {
+        self.producer
+            .send(RecordKey::NULL, serde_json::to_vec(event)?)
+            .await?;
+
+        //changes are rare and expected live right away, nothing is batched
+        self.producer.flush().await?;
+
+        Ok(())
+    }
+}
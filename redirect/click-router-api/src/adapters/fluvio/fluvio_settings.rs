@@ -0,0 +1,14 @@
+use serde_derive::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct ChangeStreamConfig {
+    pub host: String,
+    pub topic: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct Fluvio {
+    pub change_stream: ChangeStreamConfig,
+}
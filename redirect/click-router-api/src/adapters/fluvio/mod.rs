@@ -0,0 +1,3 @@
+pub mod app_builder_extensions;
+pub mod fluvio_change_publisher;
+pub mod fluvio_settings;
@@ -0,0 +1,19 @@
+use tracing::info;
+
+use crate::app_builder::AppBuilder;
+
+use super::kafka_change_publisher::KafkaChangePublisher;
+
+impl AppBuilder {
+    pub fn with_kafka(&mut self) -> &mut Self {
+        info!("{}", "WITH KAFKA CHANGE PUBLISHER");
+
+        let settings = self
+            .settings
+            .kafka
+            .clone()
+            .expect("No kafka settings specified.");
+
+        self.with_change_publisher(Box::new(KafkaChangePublisher::new(settings.change_stream)))
+    }
+}
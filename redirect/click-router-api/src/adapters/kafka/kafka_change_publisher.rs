@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use rdkafka::{
+    producer::{FutureProducer, FutureRecord},
+    ClientConfig,
+};
+
+use crate::{core::BaseChangePublisher, model::ChangeEvent};
+
+use super::kafka_settings::ChangeStreamConfig;
+
+#[derive(Clone)]
+pub struct KafkaChangePublisher {
+    producer: FutureProducer,
+    settings: ChangeStreamConfig,
+}
+
+impl KafkaChangePublisher {
+    pub fn new(settings: ChangeStreamConfig) -> Self {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", settings.hosts.join(","))
+            .set(
+                "message.timeout.ms",
+                (settings.ack_timeout_secs * 1000).to_string(),
+            )
+            .create()
+            .expect("Change publisher producer creation error");
+
+        Self { producer, settings }
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseChangePublisher for KafkaChangePublisher {
+    async fn publish(&self, event: &ChangeEvent) -> Result<()> {
+        let payload = serde_json::to_vec(event)?;
+
+        self.producer
+            .send(
+                FutureRecord::<(), _>::to(&self.settings.topic).payload(&payload),
+                Duration::from_secs(0),
+            )
+            .await
+            .map_err(|(error, _)| error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+use serde_derive::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct ChangeStreamConfig {
+    pub hosts: Vec<String>,
+    pub topic: String,
+    pub ack_timeout_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct Kafka {
+    pub change_stream: ChangeStreamConfig,
+}
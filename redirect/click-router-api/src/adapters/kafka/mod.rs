@@ -0,0 +1,3 @@
+pub mod app_builder_extensions;
+pub mod kafka_change_publisher;
+pub mod kafka_settings;
@@ -1,2 +1,5 @@
 pub mod api;
-pub mod aws;
\ No newline at end of file
+pub mod aws;
+pub mod fluvio;
+pub mod kafka;
+pub mod sqlite;
\ No newline at end of file
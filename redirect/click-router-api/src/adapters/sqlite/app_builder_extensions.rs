@@ -0,0 +1,30 @@
+use tracing::info;
+
+use crate::app_builder::AppBuilder;
+
+use super::{
+    sqlite_crypto_store::SqliteCryptoStore, sqlite_database::connect,
+    sqlite_routes_store::SqliteRoutesStore, sqlite_user_settings_store::SqliteUserSettingsStore,
+};
+
+impl AppBuilder {
+    pub async fn with_sqlite(&mut self) -> &mut Self {
+        info!("{}", "WITH SQLITE STORES");
+
+        let settings = self
+            .settings
+            .sqlite
+            .clone()
+            .expect("No sqlite settings specified.");
+
+        let pool = connect(&settings)
+            .await
+            .expect("Can not open the sqlite database.");
+
+        self.routes_store = Some(Box::new(SqliteRoutesStore::new(pool.clone())) as Box<_>);
+        self.crypto_store = Some(Box::new(SqliteCryptoStore::new(pool.clone())) as Box<_>);
+        self.user_settings_store = Some(Box::new(SqliteUserSettingsStore::new(pool)) as Box<_>);
+
+        self
+    }
+}
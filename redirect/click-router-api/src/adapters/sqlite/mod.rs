@@ -0,0 +1,7 @@
+pub mod app_builder_extensions;
+pub mod sqlite_crypto_store;
+pub mod sqlite_database;
+mod sqlite_routes_mapper;
+pub mod sqlite_routes_store;
+pub mod sqlite_settings;
+pub mod sqlite_user_settings_store;
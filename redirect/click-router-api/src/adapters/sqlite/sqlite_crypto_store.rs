@@ -0,0 +1,88 @@
+use anyhow::{Error, Result};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::core::BaseCryptoStore;
+use crate::model::Keycert;
+
+const SELECT_CERTIFICATE: &'static str =
+    "SELECT hostname, key, cert FROM certificates WHERE hostname = ?";
+
+const INSERT_CERTIFICATE: &'static str = r#"
+    INSERT INTO certificates (key, cert, hostname) VALUES (?, ?, ?)
+    ON CONFLICT (hostname) DO UPDATE SET key = excluded.key, cert = excluded.cert"#;
+
+const UPDATE_CERTIFICATE: &'static str =
+    "UPDATE certificates SET key = ?, cert = ? WHERE hostname = ?";
+
+const DELETE_CERTIFICATE: &'static str = "DELETE FROM certificates WHERE hostname = ?";
+
+#[derive(Clone, Debug)]
+pub struct SqliteCryptoStore {
+    pool: SqlitePool,
+}
+
+impl SqliteCryptoStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn to_entity(&self, row: &SqliteRow) -> Result<Keycert> {
+        Ok(Keycert::new()
+            .hostname(row.try_get::<String, _>("hostname")?)
+            .key(row.try_get::<Vec<u8>, _>("key")?)
+            .cert(row.try_get::<Vec<u8>, _>("cert")?))
+    }
+
+    async fn save(&self, statement: &str, certificate: &Keycert) -> Result<u64> {
+        let result = sqlx::query(statement)
+            .bind(&certificate.key)
+            .bind(&certificate.cert)
+            .bind(certificate.hostname.to_ascii_lowercase())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseCryptoStore for SqliteCryptoStore {
+    async fn store_certificate(&self, certificate: &Keycert) -> Result<()> {
+        self.save(INSERT_CERTIFICATE, certificate).await?;
+
+        Ok(())
+    }
+
+    async fn update_certificate(&self, certificate: &Keycert) -> Result<()> {
+        if self.save(UPDATE_CERTIFICATE, certificate).await? == 0 {
+            return Err(Error::msg(format!(
+                "Could not find certificate of '{}'.",
+                certificate.hostname
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_certificate(&self, certificate: &Keycert) -> Result<()> {
+        sqlx::query(DELETE_CERTIFICATE)
+            .bind(certificate.hostname.to_ascii_lowercase())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn invalidate_certificate(&self, _: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
+        let row = sqlx::query(SELECT_CERTIFICATE)
+            .bind(server_name.to_ascii_lowercase())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| self.to_entity(&row)).transpose()
+    }
+}
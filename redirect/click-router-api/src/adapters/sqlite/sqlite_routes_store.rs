@@ -0,0 +1,209 @@
+use anyhow::{Context, Error, Result};
+use sqlx::{query::Query, sqlite::SqliteArguments, Sqlite, SqlitePool};
+
+use super::sqlite_routes_mapper::{from_dest_format, from_status, from_terminal, to_entity};
+use crate::core::BaseRoutesStore;
+use crate::model::Route;
+
+const SELECT_ROUTE: &'static str = "SELECT * FROM routes WHERE switch = ? AND link = ?";
+
+const INSERT_ROUTE: &'static str = r#"
+    INSERT INTO routes (dest, dest_format, code, ttl, blocked, blocked_reason, terminal, policy, properties, switch, link)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT (switch, link) DO UPDATE SET
+        dest = excluded.dest,
+        dest_format = excluded.dest_format,
+        code = excluded.code,
+        ttl = excluded.ttl,
+        blocked = excluded.blocked,
+        blocked_reason = excluded.blocked_reason,
+        terminal = excluded.terminal,
+        policy = excluded.policy,
+        properties = excluded.properties"#;
+
+const UPDATE_ROUTE: &'static str = r#"
+    UPDATE routes SET dest = ?, dest_format = ?, code = ?, ttl = ?, blocked = ?, blocked_reason = ?,
+        terminal = ?, policy = ?, properties = ?
+    WHERE switch = ? AND link = ?"#;
+
+const DELETE_ROUTE: &'static str = "DELETE FROM routes WHERE switch = ? AND link = ?";
+
+#[derive(Clone, Debug)]
+pub struct SqliteRoutesStore {
+    pool: SqlitePool,
+}
+
+impl SqliteRoutesStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+///
+/// Binds the columns of the insert and update statements, keyed by the lowercase switch and the link.
+///
+fn bind_route<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    route: &Route,
+) -> Result<Query<'q, Sqlite, SqliteArguments<'q>>> {
+    let ttl = route
+        .ttl
+        .map(i64::try_from)
+        .transpose()
+        .with_context(|| format!("invalid 'ttl' of route '{}'", route.link))?;
+
+    let (blocked, blocked_reason) = from_status(&route.status);
+
+    Ok(query
+        .bind(route.dest.clone())
+        .bind(from_dest_format(&route.dest_format))
+        .bind(route.code)
+        .bind(ttl)
+        .bind(blocked)
+        .bind(blocked_reason)
+        .bind(from_terminal(&route.terminal))
+        .bind(serde_json::to_string(&route.policy)?)
+        .bind(serde_json::to_string(&route.properties)?)
+        .bind(route.switch.to_ascii_lowercase())
+        .bind(route.link.clone()))
+}
+
+#[async_trait::async_trait()]
+impl BaseRoutesStore for SqliteRoutesStore {
+    async fn store_route(&self, route: &Route) -> Result<()> {
+        bind_route(sqlx::query(INSERT_ROUTE), route)?
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn update_route(&self, route: &Route) -> Result<()> {
+        let result = bind_route(sqlx::query(UPDATE_ROUTE), route)?
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::msg(format!(
+                "Could not find route '{}' of '{}'.",
+                route.link, route.switch
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_route(&self, route: &Route) -> Result<()> {
+        sqlx::query(DELETE_ROUTE)
+            .bind(route.switch.to_ascii_lowercase())
+            .bind(&route.link)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn invalidate_route(&self, _switch: &str, _domain: &str, _path: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_route(&self, switch: &str, domain: &str, path: &str) -> Result<Option<Route>> {
+        let link = format!("{}%2f{}", domain, path);
+
+        let row = sqlx::query(SELECT_ROUTE)
+            .bind(switch.to_ascii_lowercase())
+            .bind(link)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(to_entity).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expression_lang::parse;
+
+    use crate::{
+        adapters::sqlite::{sqlite_database::connect, sqlite_settings},
+        model::route::{
+            BlockedReason, ConditionalRouting, RouteProperties, RouteStatus, RoutingPolicy,
+        },
+    };
+
+    use super::*;
+
+    async fn store() -> SqliteRoutesStore {
+        let pool = connect(&sqlite_settings::Sqlite {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+
+        SqliteRoutesStore::new(pool)
+    }
+
+    fn route() -> Route {
+        let mut route = Route::new(
+            "main".to_string(),
+            "localhost%2fCond".to_string(),
+            Some("https://shop.com".to_string()),
+            RouteProperties {
+                owner_id: Some("owner".to_string()),
+                ..Default::default()
+            },
+        );
+
+        route.code = Some(302);
+        route.policy = RoutingPolicy::Conditional(vec![ConditionalRouting {
+            key: "firefox".to_string(),
+            condition: parse("ua in (Firefox)").unwrap(),
+        }]);
+
+        route
+    }
+
+    #[tokio::test]
+    async fn should_store_update_and_delete_routes() {
+        let store = store().await;
+        let mut route = route();
+
+        store.store_route(&route).await.unwrap();
+
+        let stored = store
+            .get_route("main", "localhost", "Cond")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(stored.link, "localhost%2fCond");
+        assert_eq!(stored.code, Some(302));
+        assert_eq!(stored.properties.owner_id.as_deref(), Some("owner"));
+        assert!(
+            matches!(&stored.policy, RoutingPolicy::Conditional(conditions) if conditions[0].condition.ua.is_some())
+        );
+
+        route.status = RouteStatus::Blocked(BlockedReason::Resoned("spam".to_string()));
+        store.update_route(&route).await.unwrap();
+
+        let updated = store
+            .get_route("main", "localhost", "Cond")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(
+            matches!(updated.status, RouteStatus::Blocked(BlockedReason::Resoned(reason)) if reason == "spam")
+        );
+
+        store.delete_route(&route).await.unwrap();
+
+        assert!(store
+            .get_route("main", "localhost", "Cond")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store.update_route(&route).await.is_err());
+    }
+}
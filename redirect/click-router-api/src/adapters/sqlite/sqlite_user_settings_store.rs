@@ -0,0 +1,135 @@
+use anyhow::{Context, Error, Result};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::core::BaseUserSettingsStore;
+use crate::model::{ActiveStatus, UserSettings};
+
+const ACTIVE: &'static str = "active";
+const BLOCKED: &'static str = "blocked";
+
+const SELECT_USER_SETTINGS: &'static str = "SELECT * FROM user_settings WHERE user_id = ?";
+
+const INSERT_USER_SETTINGS: &'static str = r#"
+    INSERT INTO user_settings (user_email, api_key, status, debug, overflow, skip, request_params, destination_params, user_id)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT (user_id) DO UPDATE SET
+        user_email = excluded.user_email,
+        api_key = excluded.api_key,
+        status = excluded.status,
+        debug = excluded.debug,
+        overflow = excluded.overflow,
+        skip = excluded.skip,
+        request_params = excluded.request_params,
+        destination_params = excluded.destination_params"#;
+
+const UPDATE_USER_SETTINGS: &'static str = r#"
+    UPDATE user_settings SET user_email = ?, api_key = ?, status = ?, debug = ?, overflow = ?,
+        skip = ?, request_params = ?, destination_params = ?
+    WHERE user_id = ?"#;
+
+const DELETE_USER_SETTINGS: &'static str = "DELETE FROM user_settings WHERE user_id = ?";
+
+#[derive(Clone, Debug)]
+pub struct SqliteUserSettingsStore {
+    pool: SqlitePool,
+}
+
+impl SqliteUserSettingsStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn to_entity(&self, row: &SqliteRow) -> Result<UserSettings> {
+        let user_id: String = row.try_get("user_id")?;
+
+        let active_status = match row.try_get::<&str, _>("status")? {
+            BLOCKED => ActiveStatus::Blocked,
+            _ => ActiveStatus::Active,
+        };
+
+        let list = |column: &str| -> Result<Vec<String>> {
+            serde_json::from_str(row.try_get(column)?)
+                .with_context(|| format!("invalid '{}' of user '{}'", column, user_id))
+        };
+
+        Ok(UserSettings::new(
+            user_id.clone(),
+            row.try_get("user_email")?,
+            row.try_get("api_key")?,
+            active_status,
+            row.try_get("debug")?,
+            row.try_get("overflow")?,
+            list("skip")?,
+            list("request_params")?,
+            list("destination_params")?,
+        ))
+    }
+
+    async fn save(&self, statement: &str, user_settings: &UserSettings) -> Result<u64> {
+        let status = match user_settings.active_status {
+            ActiveStatus::Active => ACTIVE,
+            ActiveStatus::Blocked => BLOCKED,
+        };
+
+        let result = sqlx::query(statement)
+            .bind(&user_settings.user_email)
+            .bind(&user_settings.api_key)
+            .bind(status)
+            .bind(user_settings.debug)
+            .bind(user_settings.overflow)
+            .bind(serde_json::to_string(&user_settings.skip)?)
+            .bind(serde_json::to_string(
+                &user_settings.allowed_request_params,
+            )?)
+            .bind(serde_json::to_string(
+                &user_settings.allowed_destination_params,
+            )?)
+            .bind(&user_settings.user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseUserSettingsStore for SqliteUserSettingsStore {
+    async fn store_user_settings(&self, user_settings: &UserSettings) -> Result<()> {
+        self.save(INSERT_USER_SETTINGS, user_settings).await?;
+
+        Ok(())
+    }
+
+    async fn update_user_settings(&self, user_settings: &UserSettings) -> Result<()> {
+        if self.save(UPDATE_USER_SETTINGS, user_settings).await? == 0 {
+            return Err(Error::msg(format!(
+                "Could not find user settings of '{}'.",
+                user_settings.user_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_user_settings(&self, user_settings: &UserSettings) -> Result<()> {
+        sqlx::query(DELETE_USER_SETTINGS)
+            .bind(&user_settings.user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        let row = sqlx::query(SELECT_USER_SETTINGS)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| self.to_entity(&row)).transpose()
+    }
+
+    async fn invalidate_user_settings(&self, _: &str) -> Result<()> {
+        Ok(())
+    }
+}
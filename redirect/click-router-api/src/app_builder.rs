@@ -1,9 +1,14 @@
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use anyhow::Result;
+use link_keys::LinkKeys;
 use tracing::info;
 
 use crate::adapters;
-use crate::core::{BaseCryptoStore, BaseUserSettingsStore};
+use crate::core::link_keys::LinkKeysRoutesStore;
+use crate::core::publishing_stores::{
+    PublishingCryptoStore, PublishingRoutesStore, PublishingUserSettingsStore,
+};
+use crate::core::{BaseChangePublisher, BaseCryptoStore, BaseUserSettingsStore};
 use crate::settings::Server;
 use crate::{adapters::api::app_state::AppState, core::BaseRoutesStore, settings::Settings};
 
@@ -68,13 +73,43 @@ impl AppBuilder {
         }
     }
 
+    ///
+    /// Publishes a change event after every write of the stores, set up the stores first.
+    ///
+    pub fn with_change_publisher(
+        &mut self,
+        publisher: Box<dyn BaseChangePublisher + Send + Sync + 'static>,
+    ) -> &mut Self {
+        self.routes_store = self
+            .routes_store
+            .take()
+            .map(|store| Box::new(PublishingRoutesStore::new(store, publisher.clone())) as Box<_>);
+
+        self.crypto_store = self
+            .crypto_store
+            .take()
+            .map(|store| Box::new(PublishingCryptoStore::new(store, publisher.clone())) as Box<_>);
+
+        self.user_settings_store = self.user_settings_store.take().map(|store| {
+            Box::new(PublishingUserSettingsStore::new(store, publisher.clone())) as Box<_>
+        });
+
+        self
+    }
+
     pub fn build(&self) -> Result<Api> {
         env_logger::try_init()?;
         info!("{}", "BUILDING");
 
+        //outermost, so that the stores and the change events get the stored links
+        let routes_store = LinkKeysRoutesStore::new(
+            self.routes_store.clone().unwrap(),
+            LinkKeys::from_settings(&self.settings.links),
+        );
+
         let router = Api::new(
             self.settings.server.clone(),
-            self.routes_store.clone().unwrap(),
+            Box::new(routes_store),
             self.crypto_store.clone().unwrap(),
             self.user_settings_store.clone().unwrap(),
         );
@@ -0,0 +1,10 @@
+use anyhow::Result;
+use dyn_clone::{clone_trait_object, DynClone};
+
+use crate::model::ChangeEvent;
+
+#[async_trait::async_trait()]
+pub trait BaseChangePublisher: DynClone {
+    async fn publish(&self, event: &ChangeEvent) -> Result<()>;
+}
+clone_trait_object!(BaseChangePublisher);
@@ -0,0 +1,185 @@
+//!
+//! Routes store keyed by the link keys, `{domain}%2f{path}`, with the same rules as the routers,
+//! see the `link_keys` crate.
+//!
+use anyhow::{Error, Result};
+use link_keys::{LinkKeys, DOMAIN_SEPARATOR};
+
+use crate::model::Route;
+
+use super::BaseRoutesStore;
+
+///
+/// Routes store keyed by the link keys, wraps all the other stores.
+///
+#[derive(Clone)]
+pub struct LinkKeysRoutesStore {
+    store: Box<dyn BaseRoutesStore + Send + Sync>,
+    link_keys: LinkKeys,
+}
+
+impl LinkKeysRoutesStore {
+    pub fn new(store: Box<dyn BaseRoutesStore + Send + Sync>, link_keys: LinkKeys) -> Self {
+        Self { store, link_keys }
+    }
+
+    ///
+    /// Domain and path of a `{domain}%2f{path}` link, as stored.
+    ///
+    fn split(&self, link: &str) -> Result<(String, String)> {
+        self.link_keys
+            .split(link)
+            .ok_or_else(|| Error::msg(format!("Link '{}' has no domain.", link)))
+    }
+
+    fn normalize_route(&self, route: &Route) -> Result<Route> {
+        let (domain, path) = self.split(&route.link)?;
+
+        let mut route = route.clone();
+
+        route.link = format!("{}{}{}", domain, DOMAIN_SEPARATOR, path);
+
+        Ok(route)
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseRoutesStore for LinkKeysRoutesStore {
+    async fn store_route(&self, route: &Route) -> Result<()> {
+        let (domain, path) = self.split(&route.link)?;
+
+        //a lowercase link would stop answering the other cases of its path
+        if let Some(lowercase) = self.link_keys.lowercase_path(&domain, &path) {
+            if self
+                .store
+                .get_route(&route.switch, &domain, &lowercase)
+                .await?
+                .is_some()
+            {
+                return Err(Error::msg(format!(
+                    "Link '{}' collides with the existing link '{}%2f{}'.",
+                    route.link, domain, lowercase
+                )));
+            }
+        }
+
+        self.store.store_route(&self.normalize_route(route)?).await
+    }
+
+    async fn update_route(&self, route: &Route) -> Result<()> {
+        self.store.update_route(&self.normalize_route(route)?).await
+    }
+
+    async fn delete_route(&self, route: &Route) -> Result<()> {
+        self.store.delete_route(&self.normalize_route(route)?).await
+    }
+
+    async fn get_route(&self, switch: &str, domain: &str, path: &str) -> Result<Option<Route>> {
+        let (domain, path) = self.link_keys.normalize(domain, path);
+
+        if let Some(route) = self.store.get_route(switch, &domain, &path).await? {
+            return Ok(Some(route));
+        }
+
+        match self.link_keys.lowercase_path(&domain, &path) {
+            Some(lowercase) => self.store.get_route(switch, &domain, &lowercase).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn invalidate_route(&self, switch: &str, domain: &str, path: &str) -> Result<()> {
+        let (domain, path) = self.link_keys.normalize(domain, path);
+
+        self.store.invalidate_route(switch, &domain, &path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adapters::sqlite::{
+            sqlite_database::connect, sqlite_routes_store::SqliteRoutesStore, sqlite_settings,
+        },
+        model::route::RouteProperties,
+    };
+
+    use super::*;
+
+    async fn store() -> LinkKeysRoutesStore {
+        let pool = connect(&sqlite_settings::Sqlite {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+
+        LinkKeysRoutesStore::new(
+            Box::new(SqliteRoutesStore::new(pool)),
+            LinkKeys::new([("go.brand.com".to_string(), true)]),
+        )
+    }
+
+    fn route(link: &str) -> Route {
+        Route::new(
+            "main".to_string(),
+            link.to_string(),
+            Some("https://shop.com".to_string()),
+            RouteProperties {
+                owner_id: Some("owner".to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn should_keep_the_case_of_case_sensitive_links() {
+        let store = store().await;
+
+        store
+            .store_route(&route("Go.Brand.com%2FAbC"))
+            .await
+            .unwrap();
+        store
+            .store_route(&route("Docs.Brand.com%2FAbC"))
+            .await
+            .unwrap();
+
+        let stored = store
+            .get_route("main", "go.brand.com", "AbC")
+            .await
+            .unwrap();
+
+        assert_eq!(stored.unwrap().link, "go.brand.com%2fAbC");
+        assert!(store
+            .get_route("main", "go.brand.com", "abc")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .get_route("main", "DOCS.brand.com", "ABC")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_lowercase_links() {
+        let store = store().await;
+
+        store
+            .store_route(&route("go.brand.com%2fpromo"))
+            .await
+            .unwrap();
+
+        let stored = store
+            .get_route("main", "go.brand.com", "Promo")
+            .await
+            .unwrap();
+
+        assert_eq!(stored.unwrap().link, "go.brand.com%2fpromo");
+        assert!(store
+            .store_route(&route("go.brand.com%2fPROMO"))
+            .await
+            .is_err());
+    }
+}
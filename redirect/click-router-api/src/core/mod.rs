@@ -1,7 +1,11 @@
+pub mod base_change_publisher;
 pub mod base_routes_store;
 pub mod base_crypto_store;
 pub mod base_user_settings_store;
+pub mod link_keys;
+pub mod publishing_stores;
 
+pub use base_change_publisher::BaseChangePublisher;
 pub use base_routes_store::BaseRoutesStore;
 pub use base_crypto_store::BaseCryptoStore;
 pub use base_user_settings_store::BaseUserSettingsStore;
\ No newline at end of file
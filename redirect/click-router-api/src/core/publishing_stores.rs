@@ -0,0 +1,253 @@
+//!
+//! Stores publishing a change event after every write, so that the routers evict their caches.
+//!
+//! A write is committed before its change is published: a failed publish is logged and the write
+//! still succeeds, the routers then serve the cached entry until it expires. The explicit
+//! invalidations only publish, so they fail with the publisher.
+//!
+use anyhow::Result;
+use tracing::warn;
+
+use crate::model::{ChangeEvent, Keycert, Route, UserSettings};
+
+use super::{BaseChangePublisher, BaseCryptoStore, BaseRoutesStore, BaseUserSettingsStore};
+
+async fn publish_change(publisher: &(dyn BaseChangePublisher + Send + Sync), event: ChangeEvent) {
+    if let Err(error) = publisher.publish(&event).await {
+        warn!(
+            "change not published, cached until expiry: {:?}, {}",
+            event, error
+        );
+    }
+}
+
+#[derive(Clone)]
+pub struct PublishingRoutesStore {
+    store: Box<dyn BaseRoutesStore + Send + Sync>,
+    publisher: Box<dyn BaseChangePublisher + Send + Sync>,
+}
+
+impl PublishingRoutesStore {
+    pub fn new(
+        store: Box<dyn BaseRoutesStore + Send + Sync>,
+        publisher: Box<dyn BaseChangePublisher + Send + Sync>,
+    ) -> Self {
+        Self { store, publisher }
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseRoutesStore for PublishingRoutesStore {
+    async fn store_route(&self, route: &Route) -> Result<()> {
+        self.store.store_route(route).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(route)).await;
+
+        Ok(())
+    }
+
+    async fn update_route(&self, route: &Route) -> Result<()> {
+        self.store.update_route(route).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(route)).await;
+
+        Ok(())
+    }
+
+    async fn delete_route(&self, route: &Route) -> Result<()> {
+        self.store.delete_route(route).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(route)).await;
+
+        Ok(())
+    }
+
+    async fn get_route(&self, switch: &str, domain: &str, path: &str) -> Result<Option<Route>> {
+        self.store.get_route(switch, domain, path).await
+    }
+
+    async fn invalidate_route(&self, switch: &str, domain: &str, path: &str) -> Result<()> {
+        self.store.invalidate_route(switch, domain, path).await?;
+
+        self.publisher
+            .publish(&ChangeEvent::route(switch, domain, path))
+            .await
+    }
+}
+
+#[derive(Clone)]
+pub struct PublishingCryptoStore {
+    store: Box<dyn BaseCryptoStore + Send + Sync>,
+    publisher: Box<dyn BaseChangePublisher + Send + Sync>,
+}
+
+impl PublishingCryptoStore {
+    pub fn new(
+        store: Box<dyn BaseCryptoStore + Send + Sync>,
+        publisher: Box<dyn BaseChangePublisher + Send + Sync>,
+    ) -> Self {
+        Self { store, publisher }
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseCryptoStore for PublishingCryptoStore {
+    async fn store_certificate(&self, certificate: &Keycert) -> Result<()> {
+        self.store.store_certificate(certificate).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(certificate)).await;
+
+        Ok(())
+    }
+
+    async fn update_certificate(&self, certificate: &Keycert) -> Result<()> {
+        self.store.update_certificate(certificate).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(certificate)).await;
+
+        Ok(())
+    }
+
+    async fn delete_certificate(&self, certificate: &Keycert) -> Result<()> {
+        self.store.delete_certificate(certificate).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(certificate)).await;
+
+        Ok(())
+    }
+
+    async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
+        self.store.get_certificate(server_name).await
+    }
+
+    async fn invalidate_certificate(&self, server_name: &str) -> Result<()> {
+        self.store.invalidate_certificate(server_name).await?;
+
+        self.publisher
+            .publish(&ChangeEvent::certificate(server_name))
+            .await
+    }
+}
+
+#[derive(Clone)]
+pub struct PublishingUserSettingsStore {
+    store: Box<dyn BaseUserSettingsStore + Send + Sync>,
+    publisher: Box<dyn BaseChangePublisher + Send + Sync>,
+}
+
+impl PublishingUserSettingsStore {
+    pub fn new(
+        store: Box<dyn BaseUserSettingsStore + Send + Sync>,
+        publisher: Box<dyn BaseChangePublisher + Send + Sync>,
+    ) -> Self {
+        Self { store, publisher }
+    }
+}
+
+#[async_trait::async_trait()]
+impl BaseUserSettingsStore for PublishingUserSettingsStore {
+    async fn store_user_settings(&self, user_settings: &UserSettings) -> Result<()> {
+        self.store.store_user_settings(user_settings).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(user_settings)).await;
+
+        Ok(())
+    }
+
+    async fn update_user_settings(&self, user_settings: &UserSettings) -> Result<()> {
+        self.store.update_user_settings(user_settings).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(user_settings)).await;
+
+        Ok(())
+    }
+
+    async fn delete_user_settings(&self, user_settings: &UserSettings) -> Result<()> {
+        self.store.delete_user_settings(user_settings).await?;
+
+        publish_change(self.publisher.as_ref(), ChangeEvent::from(user_settings)).await;
+
+        Ok(())
+    }
+
+    async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        self.store.get_user_settings(user_id).await
+    }
+
+    async fn invalidate_user_settings(&self, user_id: &str) -> Result<()> {
+        self.store.invalidate_user_settings(user_id).await?;
+
+        self.publisher
+            .publish(&ChangeEvent::user_settings(user_id))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use crate::{
+        adapters::sqlite::{
+            sqlite_database::connect, sqlite_routes_store::SqliteRoutesStore, sqlite_settings,
+        },
+        model::route::RouteProperties,
+    };
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct FailingPublisher;
+
+    #[async_trait::async_trait()]
+    impl BaseChangePublisher for FailingPublisher {
+        async fn publish(&self, _event: &ChangeEvent) -> Result<()> {
+            Err(anyhow!("stream unavailable"))
+        }
+    }
+
+    async fn store() -> PublishingRoutesStore {
+        let pool = connect(&sqlite_settings::Sqlite {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+
+        PublishingRoutesStore::new(
+            Box::new(SqliteRoutesStore::new(pool)),
+            Box::new(FailingPublisher),
+        )
+    }
+
+    #[tokio::test]
+    async fn should_commit_writes_when_the_change_is_not_published() {
+        let store = store().await;
+        let route = Route::new(
+            "main".to_string(),
+            "localhost%2fpromo".to_string(),
+            Some("https://shop.com".to_string()),
+            RouteProperties::default(),
+        );
+
+        store.store_route(&route).await.unwrap();
+
+        assert!(store
+            .get_route("main", "localhost", "promo")
+            .await
+            .unwrap()
+            .is_some());
+
+        store.delete_route(&route).await.unwrap();
+
+        assert!(store
+            .get_route("main", "localhost", "promo")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .invalidate_route("main", "localhost", "promo")
+            .await
+            .is_err());
+    }
+}
@@ -24,8 +24,18 @@ async fn main() -> Result<()> {
     )
     .unwrap();
 
-    let _app = AppBuilder::new(settings)
-        .with_aws()
+    let with_sqlite = settings.sqlite.is_some();
+
+    let mut app_builder = AppBuilder::new(settings);
+
+    if with_sqlite {
+        app_builder.with_sqlite().await;
+    } else {
+        app_builder.with_aws().await;
+    }
+
+    let _app = app_builder
+        .with_fluvio()
         .await
         .build()?
         .run()
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Keycert, Route, UserSettings};
+
+///
+/// Change of a stored entity, the routers evict it from their caches.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum ChangeEvent {
+    /// `link` is the stored key of the route, `{domain}%2f{path}`, see `LinkKeys`.
+    Route {
+        switch: String,
+        link: String,
+    },
+    UserSettings {
+        user_id: String,
+    },
+    Certificate {
+        server_name: String,
+    },
+}
+
+impl ChangeEvent {
+    pub fn route(switch: &str, domain: &str, path: &str) -> Self {
+        ChangeEvent::Route {
+            switch: switch.to_ascii_lowercase(),
+            link: format!("{}%2f{}", domain.to_ascii_lowercase(), path),
+        }
+    }
+
+    pub fn user_settings(user_id: &str) -> Self {
+        ChangeEvent::UserSettings {
+            user_id: user_id.to_string(),
+        }
+    }
+
+    pub fn certificate(server_name: &str) -> Self {
+        ChangeEvent::Certificate {
+            server_name: server_name.to_string(),
+        }
+    }
+}
+
+impl From<&Route> for ChangeEvent {
+    fn from(route: &Route) -> Self {
+        ChangeEvent::Route {
+            switch: route.switch.to_ascii_lowercase(),
+            link: route.link.clone(),
+        }
+    }
+}
+
+impl From<&UserSettings> for ChangeEvent {
+    fn from(user_settings: &UserSettings) -> Self {
+        ChangeEvent::user_settings(&user_settings.user_id)
+    }
+}
+
+impl From<&Keycert> for ChangeEvent {
+    fn from(certificate: &Keycert) -> Self {
+        ChangeEvent::certificate(&certificate.hostname)
+    }
+}
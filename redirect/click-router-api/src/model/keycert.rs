@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[non_exhaustive]
 pub struct Keycert {
+    /// Server name the certificate is served for.
+    pub hostname: String,
     /// Private key.
     pub key: Vec<u8>,
     /// Certificate.
@@ -16,12 +18,20 @@ impl Keycert {
     #[inline]
     pub fn new() -> Self {
         Self {
+            hostname: String::new(),
             key: vec![],
             cert: vec![],
             ocsp_resp: vec![],
         }
     }
 
+    /// Sets the server name.
+    #[inline]
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = hostname.into();
+        self
+    }
+
     /// Sets the Tls private key via bytes slice.
     #[inline]
     pub fn key(mut self, key: impl Into<Vec<u8>>) -> Self {
@@ -1,9 +1,11 @@
+pub mod change_event;
 pub mod error;
 pub mod keycert;
 pub mod route;
 pub mod user_settings;
 pub mod condition;
 
+pub use change_event::ChangeEvent;
 pub use keycert::Keycert;
 pub use route::Route;
 pub use user_settings::{ActiveStatus, UserSettings};
\ No newline at end of file
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -26,6 +27,7 @@ pub struct FileRouting {
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct ConditionalRouting {
     pub key: String,
+    #[serde(deserialize_with = "expression_lang::deserialize_expression")]
     pub condition: Condition,
 }
 
@@ -47,6 +49,18 @@ pub enum RoutingPolicy {
     Unknown,
 }
 
+///
+/// Click id appended to the destination, for the conversions posted back with it.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClickIdParam {
+    /// Query parameter of the destination, e.g. `sclid`.
+    pub name: String,
+    /// Also sets the click id as a first-party cookie of the short domain, of the same name.
+    #[serde(default)]
+    pub cookie: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RouteProperties {
     pub route_id: Option<String>,
@@ -54,12 +68,21 @@ pub struct RouteProperties {
     pub owner_id: Option<String>,
     pub creator_id: Option<String>,
     pub workspace_id: Option<String>,
+    pub created: Option<DateTime<Utc>>,
     pub scripts: Option<Vec<String>>,
     pub tags: Option<Vec<String>>,
     pub custom: Option<Value>,
     pub native: Option<Value>,
     pub bundling: Option<Value>,
     pub opengraph: bool,
+    /// Time zone of the date and time conditions: an IANA name (`Europe/Berlin`),
+    /// or `visitor` for the GeoIP time zone of the client. UTC when not set.
+    pub time_zone: Option<String>,
+    /// Methods accepted besides GET, HEAD and OPTIONS, e.g. `POST` for form targets.
+    pub methods: Option<Vec<String>>,
+    /// Origins allowed by the CORS preflight, `*` for any origin.
+    pub cors_origins: Option<Vec<String>>,
+    pub click_id: Option<ClickIdParam>,
 }
 
 impl Default for RouteProperties {
@@ -70,12 +93,17 @@ impl Default for RouteProperties {
             owner_id: Default::default(),
             creator_id: Default::default(),
             workspace_id: Default::default(),
+            created: Default::default(),
             scripts: Default::default(),
             tags: Default::default(),
             custom: Default::default(),
             native: Default::default(),
             bundling: Default::default(),
             opengraph: false,
+            time_zone: Default::default(),
+            methods: Default::default(),
+            cors_origins: Default::default(),
+            click_id: Default::default(),
         }
     }
 }
@@ -8,6 +8,8 @@ pub enum ActiveStatus {
 }
 
 pub const SKIP_TRACKING: &'static str = "tracking"; 
+/// No visitor cookie is issued for the links of the user.
+pub const SKIP_COOKIES: &'static str = "cookies";
 
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct UserSettings {
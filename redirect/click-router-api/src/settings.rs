@@ -1,7 +1,11 @@
 use config::{Config, ConfigError, Environment, File};
+use link_keys::Links;
 use serde_derive::Deserialize;
 
 use crate::adapters::aws::aws_settings::AWS;
+use crate::adapters::fluvio::fluvio_settings::Fluvio;
+use crate::adapters::kafka::kafka_settings::Kafka;
+use crate::adapters::sqlite::sqlite_settings::Sqlite;
 
 #[derive(Debug, Deserialize, Clone)]
 #[allow(unused)]
@@ -16,6 +20,12 @@ pub struct Server {
 #[allow(unused)]
 pub struct Settings {
     pub aws: AWS,
+    pub kafka: Option<Kafka>,
+    pub fluvio: Option<Fluvio>,
+    /// Routes, user settings and certificates in SQLite instead of DynamoDB.
+    pub sqlite: Option<Sqlite>,
+    #[serde(default)]
+    pub links: Links,
     pub server: Server
 }
 const DEV_RUN_MODE: &'static str = "development";
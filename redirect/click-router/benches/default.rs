@@ -1,9 +1,11 @@
 use criterion::criterion_main;
 
+mod expression;
 mod flow_router;
 
 criterion_main!(
-    flow_router::benches
+    flow_router::benches,
+    expression::benches,
     // batch::benches,
     // event::benches,
     // files::benches,
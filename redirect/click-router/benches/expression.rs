@@ -0,0 +1,28 @@
+use chrono::Utc;
+use click_router::core::expression_plan::{ClientFacts, ExpressionPlan};
+use click_router::model::expression::Expression;
+
+use criterion::*;
+
+const CONDITION: &'static str = r#"country in (us, ca, gb, de) and (os = "Mac OS X" or device starts iPhone) and not (day_of_week in (0, 6) or date < 20240101) or lang = en"#;
+
+fn benchmark_expression(c: &mut Criterion) {
+    let expression: Expression = CONDITION.parse().unwrap();
+    let plan = ExpressionPlan::compile(&expression).unwrap();
+
+    let mut facts = ClientFacts::new(&Utc::now());
+    facts.ua = Some("firefox".to_string());
+    facts.os = Some("windows".to_string());
+    facts.device = Some("other".to_string());
+    facts.country = Some("de".to_string());
+    facts.lang = Some("fr".to_string());
+
+    c.bench_function("compile", |b| {
+        b.iter(|| ExpressionPlan::compile(black_box(&expression)).unwrap())
+    });
+
+    c.bench_function("eval", |b| b.iter(|| plan.eval(black_box(&facts))));
+}
+
+criterion_group!(benches, benchmark_expression);
+criterion_main!(benches);
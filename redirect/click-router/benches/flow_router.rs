@@ -10,6 +10,7 @@ use criterion::*;
 
 const APP_CONFIG_PATH: &'static str = "./config";
 const APP_RUN_MODE: &'static str = "test";
+const APP_STORES_PATH: &'static str = "./config/stores";
 
 async fn init_flow_router() -> FlowRouter {
     let settings = Settings::new(Some(APP_RUN_MODE), Some(APP_CONFIG_PATH)).unwrap();
@@ -25,8 +26,7 @@ async fn init_flow_router() -> FlowRouter {
         .with_none_hit_registrar()
         // .with_fluvio()
         // .await
-        .with_dynamo()
-        .await
+        .with_file_stores(APP_STORES_PATH)
         .build();
 
     flow_router
@@ -59,16 +59,12 @@ async fn benchmark_flow_router(c: &mut Criterion) {
 
         let request = RequestType::Test(request_data);
 
-        let response_data = ResponseData {
-            ..Default::default()
-        };
-
-        let response = ResponseType::Test(response_data);
-
         b.to_async(FuturesExecutor).iter(|| async {
             let app_binding = app.as_ref();
 
-            app_binding.handle(&request, &response).await.unwrap();
+            let mut response = ResponseType::Test(ResponseData::default());
+
+            app_binding.handle(&request, &mut response).await;
         })
     });
 
@@ -4,3 +4,11 @@ test-click-router:
 	cargo test
 	
 	@printf -- "DONE: Testing click-router\n\n"
+
+
+test-click-router-redis: 
+	@printf -- "Testing click-router against redis-server\n\n"
+	
+	cargo test -- --ignored redis
+	
+	@printf -- "DONE: Testing click-router against redis-server\n\n"
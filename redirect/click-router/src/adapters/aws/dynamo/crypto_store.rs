@@ -7,6 +7,7 @@ use aws_sdk_dynamodb::operation::get_item::GetItemOutput;
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 
+use crate::core::metrics::Metrics;
 use crate::core::CryptoStore;
 use crate::model::Keycert;
 
@@ -14,13 +15,15 @@ use crate::model::Keycert;
 pub struct DynamoCryptoStore {
     client: Client,
     encryption_table: String,
+    metrics: Metrics,
 }
 
 impl DynamoCryptoStore {
-    pub fn new(sdk_config: &SdkConfig, encryption_table: String) -> Self {
+    pub fn new(sdk_config: &SdkConfig, encryption_table: String, metrics: Metrics) -> Self {
         Self {
             encryption_table,
             client: Client::new(sdk_config),
+            metrics,
         }
     }
 
@@ -44,6 +47,8 @@ impl DynamoCryptoStore {
 #[async_trait::async_trait()]
 impl CryptoStore for DynamoCryptoStore {
     async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
+        let _timer = self.metrics.dependency_timer("dynamo", "get_certificate");
+
         let item = self
             .client
             .get_item()
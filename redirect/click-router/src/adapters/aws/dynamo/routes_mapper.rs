@@ -2,13 +2,14 @@ use std::collections::HashMap;
 
 use anyhow::{Error, Result};
 use aws_sdk_dynamodb::{operation::get_item::GetItemOutput, types::AttributeValue};
+use chrono::{DateTime, Utc};
 use serde_dynamo::from_attribute_value;
 use serde_json::Value;
 
 use crate::model::{
     route::{
-        BlockedReason, ChallengeRouting, ConditionalRouting, DestinationFormat, FileRouting,
-        RouteProperties, RouteStatus, RoutingPolicy, RoutingTerminal,
+        BlockedReason, ChallengeRouting, ClickIdParam, ConditionalRouting, DestinationFormat,
+        FileRouting, RouteProperties, RouteStatus, RoutingPolicy, RoutingTerminal,
     },
     Route,
 };
@@ -140,143 +141,174 @@ fn to_policy(routing_item: &HashMap<String, AttributeValue>) -> Result<RoutingPo
 
 #[allow(dead_code)]
 pub fn to_entity(model: GetItemOutput) -> Result<Option<Route>> {
-    model.item.map_or(Ok(None), |item| {
-        let switch_str = String::from(item.get("switch").unwrap().as_s().unwrap());
-        let link_str = String::from(item.get("link").unwrap().as_s().unwrap());
-        let dest_format = item
-            .get("dest.format")
-            .map_or(DestinationFormat::Http, |d| {
-                let dest_format = d.as_s().unwrap().to_ascii_lowercase();
-
-                if dest_format == "native" {
-                    return DestinationFormat::Native;
-                } else {
-                    return DestinationFormat::Http;
-                }
-            });
-
-        let dest = item.get("dest").map_or(None, |d| {
-            let dest = d.as_s().unwrap();
-            let dest = urlencoding::decode(dest).unwrap().to_string();
-            Some(dest)
-        });
+    model.item.as_ref().map(to_route).transpose()
+}
 
-        let status_code = item.get("code").map_or(None, |d| {
-            let code = d.as_n().unwrap().parse().unwrap();
+pub fn to_route(item: &HashMap<String, AttributeValue>) -> Result<Route> {
+    let switch_str = String::from(item.get("switch").unwrap().as_s().unwrap());
+    let link_str = String::from(item.get("link").unwrap().as_s().unwrap());
+    let dest_format = item
+        .get("dest.format")
+        .map_or(DestinationFormat::Http, |d| {
+            let dest_format = d.as_s().unwrap().to_ascii_lowercase();
 
-            Some(code)
+            if dest_format == "native" {
+                return DestinationFormat::Native;
+            } else {
+                return DestinationFormat::Http;
+            }
         });
 
-        let status = item.get("blocked").map_or(RouteStatus::Active, |d| {
-            let blocked = d.as_bool().unwrap();
+    let dest = item.get("dest").map_or(None, |d| {
+        let dest = d.as_s().unwrap();
+        let dest = urlencoding::decode(dest).unwrap().to_string();
+        Some(dest)
+    });
+
+    let status_code = item.get("code").map_or(None, |d| {
+        let code = d.as_n().unwrap().parse().unwrap();
+
+        Some(code)
+    });
+
+    let status = item.get("blocked").map_or(RouteStatus::Active, |d| {
+        let blocked = d.as_bool().unwrap();
+
+        if *blocked {
+            let blocked_reason = item
+                .get("blocked.reason")
+                .map_or(BlockedReason::Unknown, |r| {
+                    let blocked_reason = r.as_s().unwrap().to_string();
+                    BlockedReason::Resoned(blocked_reason)
+                });
+
+            return RouteStatus::Blocked(blocked_reason);
+        } else {
+            return RouteStatus::Active;
+        }
+    });
+
+    let ttl = item
+        .get("ttl")
+        .map_or(None, |d| Some(d.as_n().unwrap().parse::<u128>().unwrap()));
+
+    //properties
+    let domain_id = item
+        .get("domain.id")
+        .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
+
+    let route_id = item
+        .get("route.id")
+        .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
+
+    let owner_id = item
+        .get("owner.id")
+        .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
+
+    let creator_id = item
+        .get("creator.id")
+        .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
+
+    let workspace_id = item
+        .get("workspace.id")
+        .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
+
+    let created = item
+        .get("created")
+        .and_then(|d| DateTime::parse_from_rfc3339(d.as_s().unwrap()).ok())
+        .map(|created| created.with_timezone(&Utc));
+
+    let scripts = item
+        .get("script.ids")
+        .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
+
+    let tags = item
+        .get("script.ids")
+        .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
+
+    //let custom_json = serde_json::to_string(&custom).unwrap();
+    let custom: Option<Value> = item
+        .get("attributes")
+        .map_or(None, |p| Some(from_attribute_value(p.to_owned()).unwrap()));
+
+    let native: Option<Value> = item
+        .get("native")
+        .map_or(None, |p| Some(from_attribute_value(p.to_owned()).unwrap()));
+
+    let bundling: Option<Value> = item
+        .get("bundling")
+        .map_or(None, |p| Some(from_attribute_value(p.to_owned()).unwrap()));
+
+    let opengraph = item.get("og").map_or(false, |d| *d.as_bool().unwrap());
+    let allow_debug = item.get("debug").map_or(false, |d| *d.as_bool().unwrap());
+
+    let time_zone = item
+        .get("time_zone")
+        .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
+
+    let methods = item
+        .get("methods")
+        .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
+
+    let cors_origins = item
+        .get("cors.origins")
+        .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
+
+    let click_id = item.get("click_id.name").map(|d| ClickIdParam {
+        name: String::from(d.as_s().unwrap()),
+        cookie: item
+            .get("click_id.cookie")
+            .is_some_and(|d| *d.as_bool().unwrap()),
+    });
+
+    let properties = RouteProperties {
+        creator_id: creator_id,
+        owner_id: owner_id,
+        domain_id: domain_id,
+        route_id: route_id,
+        workspace_id: workspace_id,
+        created: created,
+        scripts: scripts,
+        tags: tags,
+        custom: custom,
+        native: native,
+        bundling: bundling,
+        opengraph: opengraph,
+        allow_debug: allow_debug,
+        time_zone: time_zone,
+        methods: methods,
+        cors_origins: cors_origins,
+        click_id: click_id,
+    };
 
-            if *blocked {
-                let blocked_reason =
-                    item.get("blocked.reason")
-                        .map_or(BlockedReason::Unknown, |r| {
-                            let blocked_reason = r.as_s().unwrap().to_string();
-                            BlockedReason::Resoned(blocked_reason)
-                        });
+    //policy
+    let routing_policy = item.get("routing").map_or(Ok(RoutingPolicy::Unknown), |d| {
+        if let Ok(routing_item) = d.as_m() {
+            return to_policy(routing_item);
+        }
 
-                return RouteStatus::Blocked(blocked_reason);
-            } else {
-                return RouteStatus::Active;
-            }
-        });
+        Ok(RoutingPolicy::Basic)
+    });
 
-        let ttl = item
-            .get("ttl")
-            .map_or(None, |d| Some(d.as_n().unwrap().parse::<u128>().unwrap()));
-
-        //properties
-        let domain_id = item
-            .get("domain.id")
-            .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
-
-        let route_id = item
-            .get("route.id")
-            .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
-
-        let owner_id = item
-            .get("owner.id")
-            .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
-
-        let creator_id = item
-            .get("creator.id")
-            .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
-
-        let workspace_id = item
-            .get("workspace.id")
-            .map_or(None, |d| Some(String::from(d.as_s().unwrap())));
-
-        let scripts = item
-            .get("script.ids")
-            .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
-
-        let tags = item
-            .get("script.ids")
-            .map_or(None, |d| Some(d.as_ss().unwrap().clone()));
-
-        //let custom_json = serde_json::to_string(&custom).unwrap();
-        let custom: Option<Value> = item
-            .get("attributes")
-            .map_or(None, |p| Some(from_attribute_value(p.to_owned()).unwrap()));
-
-        let native: Option<Value> = item
-            .get("native")
-            .map_or(None, |p| Some(from_attribute_value(p.to_owned()).unwrap()));
-
-        let bundling: Option<Value> = item
-            .get("bundling")
-            .map_or(None, |p| Some(from_attribute_value(p.to_owned()).unwrap()));
-
-        let opengraph = item.get("og").map_or(false, |d| *d.as_bool().unwrap());
-        let allow_debug = item.get("debug").map_or(false, |d| *d.as_bool().unwrap());
-
-        let properties = RouteProperties {
-            creator_id: creator_id,
-            owner_id: owner_id,
-            domain_id: domain_id,
-            route_id: route_id,
-            workspace_id: workspace_id,
-            scripts: scripts,
-            tags: tags,
-            custom: custom,
-            native: native,
-            bundling: bundling,
-            opengraph: opengraph,
-            allow_debug: allow_debug
-        };
-
-        //policy
-        let routing_policy = item.get("routing").map_or(Ok(RoutingPolicy::Unknown), |d| {
+    //terminal
+    let terminal = item
+        .get("routing")
+        .map_or(Ok(RoutingTerminal::External), |d| {
             if let Ok(routing_item) = d.as_m() {
-                return to_policy(routing_item);
+                return to_terminal(routing_item);
             }
 
-            Ok(RoutingPolicy::Basic)
+            Ok(RoutingTerminal::External)
         });
 
-        //terminal
-        let terminal = item
-            .get("routing")
-            .map_or(Ok(RoutingTerminal::External), |d| {
-                if let Ok(routing_item) = d.as_m() {
-                    return to_terminal(routing_item);
-                }
-
-                Ok(RoutingTerminal::External)
-            });
-
-        let mut route = Route::new(switch_str, link_str, dest, properties);
+    let mut route = Route::new(switch_str, link_str, dest, properties);
 
-        route.dest_format = dest_format;
-        route.code = status_code;
-        route.ttl = ttl;
-        route.status = status;
-        route.terminal = terminal?;
-        route.policy = routing_policy?;
+    route.dest_format = dest_format;
+    route.code = status_code;
+    route.ttl = ttl;
+    route.status = status;
+    route.terminal = terminal?;
+    route.policy = routing_policy?;
 
-        Ok(Some(route))
-    })
+    Ok(route)
 }
@@ -6,7 +6,8 @@ use aws_config::SdkConfig;
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 
-use super::routes_mapper::to_entity;
+use super::routes_mapper::{to_entity, to_route};
+use crate::core::metrics::Metrics;
 use crate::core::RoutesStore;
 use crate::model::Route;
 
@@ -14,13 +15,22 @@ use crate::model::Route;
 pub struct DynamoRoutesStore {
     client: Client,
     routes_table: String,
+    patterns_index: Option<String>,
+    metrics: Metrics,
 }
 
 impl DynamoRoutesStore {
-    pub fn new(sdk_config: &SdkConfig, routes_table: String) -> Self {
+    pub fn new(
+        sdk_config: &SdkConfig,
+        routes_table: String,
+        patterns_index: Option<String>,
+        metrics: Metrics,
+    ) -> Self {
         Self {
             routes_table,
+            patterns_index,
             client: Client::new(sdk_config),
+            metrics,
         }
     }
 }
@@ -28,15 +38,14 @@ impl DynamoRoutesStore {
 #[async_trait::async_trait()]
 impl RoutesStore for DynamoRoutesStore {
     async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
+        let _timer = self.metrics.dependency_timer("dynamo", "get_route");
+
         let item = self
             .client
             .get_item()
             .table_name(&self.routes_table)
             .set_key(Some(HashMap::from([
-                (
-                    "link".to_string(),
-                    AttributeValue::S(path.to_ascii_lowercase()),
-                ),
+                ("link".to_string(), AttributeValue::S(path.to_string())),
                 (
                     "switch".to_string(),
                     AttributeValue::S(switch.to_ascii_lowercase()),
@@ -47,4 +56,32 @@ impl RoutesStore for DynamoRoutesStore {
 
         Ok(to_entity(item)?)
     }
+
+    async fn get_pattern_routes(&self, switch: &str, domain: &str) -> Result<Vec<Route>> {
+        let Some(patterns_index) = &self.patterns_index else {
+            return Ok(Vec::new());
+        };
+
+        let _timer = self
+            .metrics
+            .dependency_timer("dynamo", "get_pattern_routes");
+
+        let items = self
+            .client
+            .query()
+            .table_name(&self.routes_table)
+            .index_name(patterns_index)
+            .key_condition_expression("#domain = :domain AND #switch = :switch")
+            .expression_attribute_names("#domain", "pattern.domain")
+            .expression_attribute_names("#switch", "switch")
+            .expression_attribute_values(":domain", AttributeValue::S(domain.to_ascii_lowercase()))
+            .expression_attribute_values(":switch", AttributeValue::S(switch.to_ascii_lowercase()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?;
+
+        items.iter().map(to_route).collect()
+    }
 }
@@ -4,6 +4,8 @@ use serde_derive::Deserialize;
 pub struct Dynamo {
     pub encryption_table: String,
     pub routes_table: String,
+    /// Sparse index of the routes table on `pattern.domain` and `switch`, no patterns without it.
+    pub routes_patterns_index: Option<String>,
     pub hostname_mappings_table: String,
     pub user_settings_table: String,
 }
@@ -7,6 +7,7 @@ use aws_sdk_dynamodb::operation::get_item::GetItemOutput;
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 
+use crate::core::metrics::Metrics;
 use crate::core::UserSettingsStore;
 use crate::model::{ActiveStatus, UserSettings};
 
@@ -17,13 +18,15 @@ const BLOCKED: &'static str = "blocked";
 pub struct DynamoUserSettingsStore {
     client: Client,
     user_settings_table: String,
+    metrics: Metrics,
 }
 
 impl DynamoUserSettingsStore {
-    pub fn new(sdk_config: &SdkConfig, user_settings_table: String) -> Self {
+    pub fn new(sdk_config: &SdkConfig, user_settings_table: String, metrics: Metrics) -> Self {
         Self {
             user_settings_table,
             client: Client::new(sdk_config),
+            metrics,
         }
     }
 
@@ -82,6 +85,8 @@ impl DynamoUserSettingsStore {
 #[async_trait::async_trait()]
 impl UserSettingsStore for DynamoUserSettingsStore {
     async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        let _timer = self.metrics.dependency_timer("dynamo", "get_user_settings");
+
         let item = self
             .client
             .get_item()
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use tracing::{info, warn};
+
+use crate::core::invalidation::{ChangeSubscriber, Invalidator};
+
+use super::stores::FileStores;
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(2);
+
+///
+/// Reloads the file stores when their directory changes, and evicts what changed from the caches.
+///
+#[derive(Clone)]
+pub struct FileChangeSubscriber {
+    stores: FileStores,
+}
+
+impl FileChangeSubscriber {
+    pub fn new(stores: FileStores) -> Self {
+        Self { stores }
+    }
+}
+
+#[async_trait::async_trait()]
+impl ChangeSubscriber for FileChangeSubscriber {
+    async fn listen(&self, invalidator: Invalidator) -> Result<()> {
+        info!("  {} -> {}", "watching", self.stores.path().display());
+
+        //polling works the same on every platform and in mounted volumes
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let changes = match self.stores.reload_if_changed() {
+                Ok(changes) => changes,
+                Err(error) => {
+                    warn!(
+                        "file stores not reloaded, keeping the last ones: {:#}",
+                        error
+                    );
+                    continue;
+                }
+            };
+
+            for change in changes {
+                if let Err(error) = invalidator.apply(&change).await {
+                    warn!("{:?} not evicted: {}", change, error);
+                }
+            }
+        }
+    }
+}
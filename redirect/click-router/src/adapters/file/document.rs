@@ -0,0 +1,181 @@
+//!
+//! Store documents of the file stores, JSON, YAML or TOML files with `routes`,
+//! `user_settings` and `certificates` lists.
+//!
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use link_keys::LinkKeys;
+use serde::Deserialize;
+
+use crate::model::{Route, UserSettings};
+
+const MAIN_SWITCH: &'static str = "main";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StoreDocument {
+    pub routes: Vec<Route>,
+    pub user_settings: Vec<UserSettings>,
+    pub certificates: Vec<CertificateFiles>,
+}
+
+///
+/// PEM files of a certificate, relative to the stores directory.
+///
+#[derive(Debug, Deserialize)]
+pub struct CertificateFiles {
+    pub server_name: String,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+///
+/// Parses a store document by its extension, other files are skipped.
+///
+pub fn parse(path: &Path, content: &str, link_keys: &LinkKeys) -> Result<Option<StoreDocument>> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    let document: StoreDocument = match extension.as_deref() {
+        Some("json") => serde_json::from_str(content)?,
+        Some("yaml") | Some("yml") => serde_yaml::from_str(content)?,
+        Some("toml") => toml::from_str(content)?,
+        _ => return Ok(None),
+    };
+
+    let routes = document
+        .routes
+        .into_iter()
+        .map(|route| normalize_route(route, link_keys))
+        .collect::<Result<Vec<Route>>>()
+        .with_context(|| format!("invalid route in '{}'", path.display()))?;
+
+    Ok(Some(StoreDocument { routes, ..document }))
+}
+
+///
+/// Routes are looked up by `{domain}%2f{path}` in the main switch unless told otherwise,
+/// files may use `{domain}/{path}`.
+///
+fn normalize_route(mut route: Route, link_keys: &LinkKeys) -> Result<Route> {
+    if route.switch.is_empty() {
+        route.switch = MAIN_SWITCH.to_string();
+    }
+
+    route.link = match route.link.to_ascii_lowercase().contains("%2f") {
+        true => link_keys.link(&route.link),
+        false => route
+            .link
+            .split_once('/')
+            .map(|(domain, path)| link_keys.key(domain, path))
+            .with_context(|| format!("link '{}' has no domain", route.link))?,
+    };
+
+    Ok(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::ActiveStatus;
+
+    use super::*;
+
+    fn links(document: &StoreDocument) -> Vec<(&str, &str, Option<&str>)> {
+        document
+            .routes
+            .iter()
+            .map(|route| {
+                (
+                    route.switch.as_str(),
+                    route.link.as_str(),
+                    route.dest.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_toml_documents() {
+        let document = parse(
+            Path::new("stores/routes.toml"),
+            r#"
+            [[routes]]
+            link = "localhost/Promo"
+            dest = "https://shop.com/promo"
+
+            [[routes]]
+            switch = "en"
+            link = "go.brand.com%2fsale"
+            dest = "https://shop.com/sale"
+            code = 301
+
+            [[routes]]
+            link = "Go.Brand.com/AbC"
+            dest = "https://shop.com/abc"
+            "#,
+            &LinkKeys::new([("go.brand.com".to_string(), true)]),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            links(&document),
+            vec![
+                ("main", "localhost%2fpromo", Some("https://shop.com/promo")),
+                ("en", "go.brand.com%2fsale", Some("https://shop.com/sale")),
+                ("main", "go.brand.com%2fAbC", Some("https://shop.com/abc"))
+            ]
+        );
+        assert_eq!(document.routes[1].code, Some(301));
+    }
+
+    #[test]
+    fn should_parse_yaml_and_json_documents() {
+        let yaml = parse(
+            Path::new("stores/users.yml"),
+            "user_settings:\n  - user_id: u1\n    active_status: blocked\n    debug: true\n",
+            &LinkKeys::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(yaml.user_settings[0].user_id, "u1");
+        assert!(matches!(
+            yaml.user_settings[0].active_status,
+            ActiveStatus::Blocked
+        ));
+        assert!(yaml.user_settings[0].debug);
+
+        let json = parse(
+            Path::new("stores/certs.JSON"),
+            r#"{"certificates":[{"server_name":"localhost","cert":"certs/cert.pem","key":"certs/key.pem"}]}"#,
+            &LinkKeys::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(json.certificates[0].server_name, "localhost");
+        assert_eq!(json.certificates[0].key, PathBuf::from("certs/key.pem"));
+    }
+
+    #[test]
+    fn should_skip_other_files_and_reject_bad_links() {
+        assert!(parse(
+            Path::new("stores/readme.md"),
+            "# stores",
+            &LinkKeys::default()
+        )
+        .unwrap()
+        .is_none());
+
+        assert!(parse(
+            Path::new("stores/routes.json"),
+            r#"{"routes":[{"link":"promo"}]}"#,
+            &LinkKeys::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+
+use crate::{core::hits_register::HitRegistrar, model::Hit};
+
+use super::settings::FileHits;
+
+struct HitFile {
+    file: File,
+    bytes: u64,
+}
+
+///
+/// Hits appended as JSON lines to a file rotated by size, e.g. for sidecar log shippers.
+///
+#[derive(Clone)]
+pub struct FileHitRegistrar {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Arc<Mutex<HitFile>>,
+}
+
+impl FileHitRegistrar {
+    pub fn new(settings: &FileHits) -> Result<Self> {
+        let path = PathBuf::from(&settings.path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(open(&path)?)),
+            path,
+            max_bytes: settings.max_bytes,
+            max_files: settings.max_files,
+        })
+    }
+
+    ///
+    /// Writes the hits on a blocking thread.
+    ///
+    async fn append(&self, hits: &[Hit]) -> Result<()> {
+        let registrar = self.clone();
+        let lines = to_lines(hits)?;
+
+        tokio::task::spawn_blocking(move || registrar.write(&lines)).await?
+    }
+
+    fn write(&self, lines: &[u8]) -> Result<()> {
+        let mut file = self.file.lock().unwrap();
+
+        if file.bytes > 0 && file.bytes + lines.len() as u64 > self.max_bytes {
+            self.rotate()?;
+
+            *file = open(&self.path)?;
+        }
+
+        file.file.write_all(lines)?;
+        file.bytes += lines.len() as u64;
+
+        Ok(())
+    }
+
+    ///
+    /// Shifts `{path}.1` to `{path}.2` and so on, the current file becomes `{path}.1`.
+    ///
+    fn rotate(&self) -> Result<()> {
+        let rotated = |index: usize| PathBuf::from(format!("{}.{}", self.path.display(), index));
+
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+
+            return Ok(());
+        }
+
+        for index in (1..self.max_files).rev() {
+            if rotated(index).exists() {
+                fs::rename(rotated(index), rotated(index + 1))?;
+            }
+        }
+
+        fs::rename(&self.path, rotated(1))?;
+
+        Ok(())
+    }
+}
+
+fn open(path: &PathBuf) -> Result<HitFile> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let bytes = file.metadata()?.len();
+
+    Ok(HitFile { file, bytes })
+}
+
+fn to_lines(hits: &[Hit]) -> Result<Vec<u8>> {
+    let mut lines = Vec::new();
+
+    for hit in hits {
+        serde_json::to_writer(&mut lines, hit)?;
+        lines.push(b'\n');
+    }
+
+    Ok(lines)
+}
+
+async fn print(hits: &[Hit]) -> Result<()> {
+    let lines = to_lines(hits)?;
+
+    tokio::task::spawn_blocking(move || io::stdout().lock().write_all(&lines)).await??;
+
+    Ok(())
+}
+
+#[async_trait::async_trait()]
+impl HitRegistrar for FileHitRegistrar {
+    async fn register(&self, hit: Hit) -> Result<()> {
+        self.append(&[hit]).await
+    }
+
+    async fn register_batch(&self, hits: Vec<Hit>) -> Result<()> {
+        self.append(&hits).await
+    }
+}
+
+///
+/// Hits printed as JSON lines, for local runs.
+///
+#[derive(Clone, Default)]
+pub struct StdoutHitRegistrar;
+
+#[async_trait::async_trait()]
+impl HitRegistrar for StdoutHitRegistrar {
+    async fn register(&self, hit: Hit) -> Result<()> {
+        print(&[hit]).await
+    }
+
+    async fn register_batch(&self, hits: Vec<Hit>) -> Result<()> {
+        print(&hits).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::model::hit::Click;
+
+    use super::*;
+
+    fn hit(id: &str) -> Hit {
+        Hit::click(
+            id.to_string(),
+            Utc::now(),
+            None,
+            None,
+            None,
+            Click::new("https://shop.com"),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn should_rotate_the_hit_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hits.jsonl");
+
+        let registrar = FileHitRegistrar::new(&FileHits {
+            path: path.to_string_lossy().to_string(),
+            max_bytes: 200,
+            max_files: 2,
+        })
+        .unwrap();
+
+        for id in ["1", "2", "3", "4"] {
+            registrar.register(hit(id)).await.unwrap();
+        }
+
+        let ids = |path: PathBuf| -> Vec<String> {
+            fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<Hit>(line).unwrap().id)
+                .collect()
+        };
+
+        assert_eq!(ids(path.clone()), vec!["4"]);
+        assert_eq!(ids(dir.path().join("hits.jsonl.1")), vec!["3"]);
+        assert_eq!(ids(dir.path().join("hits.jsonl.2")), vec!["2"]);
+        assert!(!dir.path().join("hits.jsonl.3").exists());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod change_subscriber;
+pub mod document;
+pub mod hit_registrar;
+pub mod settings;
+pub mod stores;
@@ -0,0 +1,22 @@
+use serde_derive::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct FileHits {
+    /// JSONL file of the hits, rotated to `{path}.1`, `{path}.2`...
+    pub path: String,
+    pub max_bytes: u64,
+    /// Rotated files kept, the oldest are deleted.
+    pub max_files: usize,
+}
+
+impl Default for FileHits {
+    fn default() -> Self {
+        Self {
+            path: "./hits/hits.jsonl".to_string(),
+            max_bytes: 64 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use link_keys::LinkKeys;
+use tracing::info;
+
+use crate::{
+    adapters::memory::{
+        crypto_store::MemoryCryptoStore, routes_store::MemoryRoutesStore,
+        user_settings_store::MemoryUserSettingsStore,
+    },
+    core::{CryptoStore, RoutesStore, UserSettingsStore},
+    model::{ChangeEvent, Keycert, Route, UserSettings},
+};
+
+use super::document::{parse, StoreDocument};
+
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+///
+/// Routes, user settings and certificates loaded from the store documents of a directory.
+///
+#[derive(Clone)]
+pub struct FileStores {
+    path: PathBuf,
+    link_keys: LinkKeys,
+    routes: MemoryRoutesStore,
+    user_settings: MemoryUserSettingsStore,
+    crypto: MemoryCryptoStore,
+    loaded: Arc<Mutex<Fingerprint>>,
+}
+
+impl FileStores {
+    pub fn load(path: impl Into<PathBuf>, link_keys: LinkKeys) -> Result<Self> {
+        let stores = Self {
+            path: path.into(),
+            link_keys,
+            routes: MemoryRoutesStore::default(),
+            user_settings: MemoryUserSettingsStore::default(),
+            crypto: MemoryCryptoStore::default(),
+            loaded: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        stores.reload()?;
+
+        Ok(stores)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn routes_store(&self) -> FileRoutesStore {
+        FileRoutesStore {
+            store: self.routes.clone(),
+        }
+    }
+
+    pub fn user_settings_store(&self) -> FileUserSettingsStore {
+        FileUserSettingsStore {
+            store: self.user_settings.clone(),
+        }
+    }
+
+    pub fn crypto_store(&self) -> FileCryptoStore {
+        FileCryptoStore {
+            store: self.crypto.clone(),
+        }
+    }
+
+    ///
+    /// Reloads the directory when a file was added, changed or removed since the last load,
+    /// returns the changes of everything that was loaded before or after.
+    ///
+    pub fn reload_if_changed(&self) -> Result<Vec<ChangeEvent>> {
+        let fingerprint = fingerprint(&self.path)?;
+
+        if fingerprint == *self.loaded.lock().unwrap() {
+            return Ok(Vec::new());
+        }
+
+        //a broken file is reported once, the next change of the directory reloads it
+        *self.loaded.lock().unwrap() = fingerprint;
+
+        self.reload()
+    }
+
+    fn reload(&self) -> Result<Vec<ChangeEvent>> {
+        let fingerprint = fingerprint(&self.path)?;
+
+        let mut routes: Vec<Route> = Vec::new();
+        let mut user_settings: Vec<UserSettings> = Vec::new();
+        let mut certificates: HashMap<String, Keycert> = HashMap::new();
+
+        //a broken file fails the whole reload, so that the stores are never half loaded
+        for (file, _, _) in fingerprint.iter() {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("can not read '{}'", file.display()))?;
+
+            let document = parse(file, &content, &self.link_keys)
+                .with_context(|| format!("can not parse '{}'", file.display()))?;
+
+            let Some(StoreDocument {
+                routes: file_routes,
+                user_settings: file_user_settings,
+                certificates: file_certificates,
+            }) = document
+            else {
+                continue;
+            };
+
+            routes.extend(file_routes);
+            user_settings.extend(file_user_settings);
+
+            for files in file_certificates {
+                let certificate = Keycert::new()
+                    .cert(read_pem(&self.path, &files.cert)?)
+                    .key(read_pem(&self.path, &files.key)?);
+
+                certificates.insert(files.server_name, certificate);
+            }
+        }
+
+        info!(
+            "  {} -> {} routes, {} user settings, {} certificates",
+            self.path.display(),
+            routes.len(),
+            user_settings.len(),
+            certificates.len()
+        );
+
+        let mut changes: BTreeSet<ChangeEvent> = routes.iter().map(route_change).collect();
+        changes.extend(user_settings.iter().map(user_settings_change));
+        changes.extend(certificates.keys().map(|name| certificate_change(name)));
+
+        changes.extend(self.routes.replace(routes).iter().map(route_change));
+        changes.extend(
+            self.user_settings
+                .replace(user_settings)
+                .iter()
+                .map(user_settings_change),
+        );
+        changes.extend(
+            self.crypto
+                .replace(certificates)
+                .keys()
+                .map(|name| certificate_change(name)),
+        );
+
+        *self.loaded.lock().unwrap() = fingerprint;
+
+        Ok(changes.into_iter().collect())
+    }
+}
+
+fn route_change(route: &Route) -> ChangeEvent {
+    ChangeEvent::Route {
+        switch: route.switch.clone(),
+        link: route.link.clone(),
+    }
+}
+
+fn user_settings_change(user_settings: &UserSettings) -> ChangeEvent {
+    ChangeEvent::UserSettings {
+        user_id: user_settings.user_id.clone(),
+    }
+}
+
+fn certificate_change(server_name: &str) -> ChangeEvent {
+    ChangeEvent::Certificate {
+        server_name: server_name.to_ascii_lowercase(),
+    }
+}
+
+fn read_pem(directory: &Path, file: &Path) -> Result<Vec<u8>> {
+    let path = directory.join(file);
+
+    fs::read(&path).with_context(|| format!("can not read '{}'", path.display()))
+}
+
+///
+/// Files of the directory and its subdirectories, with their modification time and size.
+///
+fn fingerprint(directory: &Path) -> Result<Fingerprint> {
+    let mut files = BTreeSet::new();
+    let mut directories = vec![directory.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        let entries = fs::read_dir(&directory)
+            .with_context(|| format!("can not list '{}'", directory.display()))?;
+
+        for entry in entries {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+
+            if metadata.is_dir() {
+                directories.push(entry.path());
+            } else {
+                files.insert((entry.path(), metadata.modified().ok(), metadata.len()));
+            }
+        }
+    }
+
+    Ok(files.into_iter().collect())
+}
+
+#[derive(Clone, Debug)]
+pub struct FileRoutesStore {
+    store: MemoryRoutesStore,
+}
+
+#[async_trait::async_trait()]
+impl RoutesStore for FileRoutesStore {
+    async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
+        self.store.get_route(switch, path).await
+    }
+
+    async fn get_pattern_routes(&self, switch: &str, domain: &str) -> Result<Vec<Route>> {
+        self.store.get_pattern_routes(switch, domain).await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileUserSettingsStore {
+    store: MemoryUserSettingsStore,
+}
+
+#[async_trait::async_trait()]
+impl UserSettingsStore for FileUserSettingsStore {
+    async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        self.store.get_user_settings(user_id).await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileCryptoStore {
+    store: MemoryCryptoStore,
+}
+
+#[async_trait::async_trait()]
+impl CryptoStore for FileCryptoStore {
+    async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
+        self.store.get_certificate(server_name).await
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use fluvio::{consumer::ConsumerConfigExtBuilder, Fluvio, FluvioClusterConfig, Offset};
+use futures_lite::StreamExt;
+use tracing::{info, warn};
+
+use crate::core::invalidation::{ChangeSubscriber, Invalidator};
+
+use super::settings::ChangeStreamConfig;
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct FluvioChangeSubscriber {
+    settings: ChangeStreamConfig,
+}
+
+impl FluvioChangeSubscriber {
+    pub fn new(settings: &ChangeStreamConfig) -> Self {
+        Self {
+            settings: settings.clone(),
+        }
+    }
+
+    ///
+    /// Applies the changes until the stream fails or ends, keeping the offset of the last one.
+    ///
+    async fn consume(
+        &self,
+        invalidator: &Invalidator,
+        last_offset: &mut Option<i64>,
+    ) -> Result<()> {
+        let fluvio =
+            Fluvio::connect_with_config(&FluvioClusterConfig::new(&self.settings.host)).await?;
+
+        //every instance evicts its own cache, so each one reads the changes made after it started
+        let offset = match last_offset {
+            Some(last_offset) => Offset::absolute(*last_offset + 1)?,
+            None => Offset::end(),
+        };
+
+        let mut stream = fluvio
+            .consumer_with_config(
+                ConsumerConfigExtBuilder::default()
+                    .topic(self.settings.topic.clone())
+                    .offset_start(offset)
+                    .build()?,
+            )
+            .await?;
+
+        info!("  {} -> {}", "change stream", self.settings.topic);
+
+        while let Some(record) = stream.next().await {
+            let record = record?;
+
+            invalidator.apply_payload(record.as_ref()).await;
+
+            *last_offset = Some(record.offset());
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait()]
+impl ChangeSubscriber for FluvioChangeSubscriber {
+    async fn listen(&self, invalidator: Invalidator) -> Result<()> {
+        let mut last_offset = None;
+        let mut delay = MIN_RECONNECT_DELAY;
+
+        //reconnects after the last change applied, so that none is missed while the stream is down
+        loop {
+            let applied = last_offset;
+
+            match self.consume(&invalidator, &mut last_offset).await {
+                Ok(()) => warn!("change stream ended, reconnecting in {:?}", delay),
+                Err(error) => warn!(
+                    "change stream error, reconnecting in {:?}: {}",
+                    delay, error
+                ),
+            }
+
+            if last_offset != applied {
+                delay = MIN_RECONNECT_DELAY;
+            }
+
+            tokio::time::sleep(delay).await;
+
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+}
@@ -43,12 +43,23 @@ impl FluvioHitRegistrar {
 
 #[async_trait::async_trait()]
 impl HitRegistrar for FluvioHitRegistrar {
-    async fn register(&self, hit: &Hit) -> Result<()> {
-        let record = serde_json::to_vec(&hit).unwrap();
-        self.producer
-            .send(RecordKey::NULL, record)
-            .await
-            .expect("Failed to send record");
+    async fn register(&self, hit: Hit) -> Result<()> {
+        let record = serde_json::to_vec(&hit)?;
+
+        self.producer.send(RecordKey::NULL, record).await?;
+
+        Ok(())
+    }
+
+    async fn register_batch(&self, hits: Vec<Hit>) -> Result<()> {
+        for hit in hits {
+            let record = serde_json::to_vec(&hit)?;
+
+            self.producer.send(RecordKey::NULL, record).await?;
+        }
+
+        //the producer batches on its own, flushing acknowledges the whole batch
+        self.producer.flush().await?;
 
         Ok(())
     }
@@ -1,2 +1,3 @@
+pub mod change_subscriber;
 pub mod hit_registrar;
 pub mod settings;
@@ -9,6 +9,13 @@ pub struct HitStreamConfig {
 }
 #[derive(Default, Debug, Deserialize, Clone)]
 #[allow(unused)]
+pub struct ChangeStreamConfig {
+    pub host: String,
+    pub topic: String,
+}
+#[derive(Default, Debug, Deserialize, Clone)]
+#[allow(unused)]
 pub struct Fluvio {
     pub hit_stream: HitStreamConfig,
+    pub change_stream: ChangeStreamConfig,
 }
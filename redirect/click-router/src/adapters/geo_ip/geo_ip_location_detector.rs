@@ -3,25 +3,117 @@ use std::{net::IpAddr, sync::Arc};
 use maxminddb::{geoip2, MaxMindDbError, Reader};
 use tracing::info;
 
-use crate::core::location::{Country, LocationDetector};
+use crate::core::location::{Asn, Coordinates, Country, Location, LocationDetector};
 
 use super::settings::GeoIP;
 
 #[derive(Clone, Debug)]
 pub struct GeoIPLocationDetector {
     reader: Arc<Reader<Vec<u8>>>,
+    city_reader: Option<Arc<Reader<Vec<u8>>>>,
+    asn_reader: Option<Arc<Reader<Vec<u8>>>>,
 }
 
-impl GeoIPLocationDetector {
-    pub fn new(settings: &GeoIP) -> Self {
-        info!("  mmdb -> {}", settings.mmdb);
+fn open_reader(mmdb: &str) -> Arc<Reader<Vec<u8>>> {
+    info!("  mmdb -> {}", mmdb);
 
-        let reader = Reader::open_readfile(&settings.mmdb).unwrap();
+    Arc::new(Reader::open_readfile(mmdb).unwrap())
+}
 
+impl GeoIPLocationDetector {
+    pub fn new(settings: &GeoIP) -> Self {
         Self {
-            reader: Arc::new(reader),
+            reader: open_reader(&settings.mmdb),
+            city_reader: settings.city_mmdb.as_deref().map(open_reader),
+            asn_reader: settings.asn_mmdb.as_deref().map(open_reader),
         }
     }
+
+    fn lookup_city(&self, reader: &Reader<Vec<u8>>, ip_addr: IpAddr) -> Option<Location> {
+        let city = reader.lookup::<geoip2::City>(ip_addr).ok().flatten()?;
+
+        let country = city
+            .country
+            .and_then(|country| country.iso_code)
+            .map(|iso_code| Country {
+                iso_code: iso_code.to_ascii_lowercase(),
+            });
+
+        //subdivisions are ordered from the largest to the smallest
+        let region = match (&country, city.subdivisions.as_ref().and_then(|s| s.first())) {
+            (Some(country), Some(subdivision)) => subdivision
+                .iso_code
+                .map(|iso_code| format!("{}-{}", country.iso_code, iso_code.to_ascii_lowercase())),
+            _ => None,
+        };
+
+        let coordinates = city.location.as_ref().and_then(|location| {
+            match (location.latitude, location.longitude) {
+                (Some(latitude), Some(longitude)) => Some(Coordinates {
+                    latitude,
+                    longitude,
+                }),
+                _ => None,
+            }
+        });
+
+        Some(Location {
+            country,
+            continent: city
+                .continent
+                .and_then(|continent| continent.code)
+                .map(|code| code.to_ascii_lowercase()),
+            region,
+            city: city
+                .city
+                .and_then(|city| city.names)
+                .and_then(|names| names.get("en").map(|name| name.to_string())),
+            coordinates,
+            time_zone: city
+                .location
+                .and_then(|location| location.time_zone)
+                .map(|time_zone| time_zone.to_string()),
+            asn: None,
+        })
+    }
+
+    fn lookup_country(&self, ip_addr: IpAddr) -> Option<Location> {
+        let country = self
+            .reader
+            .lookup::<geoip2::Country>(ip_addr)
+            .ok()
+            .flatten()?;
+
+        Some(Location {
+            country: country
+                .country
+                .and_then(|country| country.iso_code)
+                .map(|iso_code| Country {
+                    iso_code: iso_code.to_ascii_lowercase(),
+                }),
+            continent: country
+                .continent
+                .and_then(|continent| continent.code)
+                .map(|code| code.to_ascii_lowercase()),
+            ..Default::default()
+        })
+    }
+
+    fn lookup_asn(&self, ip_addr: IpAddr) -> Option<Asn> {
+        let asn = self
+            .asn_reader
+            .as_ref()?
+            .lookup::<geoip2::Asn>(ip_addr)
+            .ok()
+            .flatten()?;
+
+        Some(Asn {
+            number: asn.autonomous_system_number?,
+            organization: asn
+                .autonomous_system_organization
+                .map(|organization| organization.to_string()),
+        })
+    }
 }
 
 impl LocationDetector for GeoIPLocationDetector {
@@ -45,4 +137,21 @@ impl LocationDetector for GeoIPLocationDetector {
             None => None,
         }
     }
+
+    fn detect_location(&self, &ip_addr: &IpAddr) -> Option<Location> {
+        //the city database is a superset of the country one
+        let mut location = match &self.city_reader {
+            Some(reader) => self.lookup_city(reader, ip_addr),
+            None => self.lookup_country(ip_addr),
+        }
+        .unwrap_or_default();
+
+        location.asn = self.lookup_asn(ip_addr);
+
+        if location == Location::default() {
+            return None;
+        }
+
+        Some(location)
+    }
 }
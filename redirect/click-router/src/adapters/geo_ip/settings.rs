@@ -3,4 +3,6 @@ use serde_derive::Deserialize;
 #[allow(unused)]
 pub struct GeoIP {
     pub mmdb: String,
+    pub city_mmdb: Option<String>,
+    pub asn_mmdb: Option<String>,
 }
@@ -0,0 +1,64 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::Result;
+
+use crate::core::CryptoStore;
+use crate::model::Keycert;
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryCryptoStore {
+    certificates: Arc<RwLock<HashMap<String, Keycert>>>,
+}
+
+impl MemoryCryptoStore {
+    pub fn new(certificates: HashMap<String, Keycert>) -> Self {
+        let store = Self::default();
+
+        store.replace(certificates);
+
+        store
+    }
+
+    pub fn insert(&self, server_name: &str, certificate: Keycert) {
+        self.certificates
+            .write()
+            .unwrap()
+            .insert(server_name.to_ascii_lowercase(), certificate);
+    }
+
+    pub fn remove(&self, server_name: &str) -> Option<Keycert> {
+        self.certificates
+            .write()
+            .unwrap()
+            .remove(&server_name.to_ascii_lowercase())
+    }
+
+    ///
+    /// Replaces all the certificates, by server name, returns the previous ones.
+    ///
+    pub fn replace(&self, certificates: HashMap<String, Keycert>) -> HashMap<String, Keycert> {
+        let certificates = certificates
+            .into_iter()
+            .map(|(server_name, certificate)| (server_name.to_ascii_lowercase(), certificate))
+            .collect();
+
+        std::mem::replace(&mut *self.certificates.write().unwrap(), certificates)
+    }
+}
+
+#[async_trait::async_trait()]
+impl CryptoStore for MemoryCryptoStore {
+    async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
+        let certificate = self
+            .certificates
+            .read()
+            .unwrap()
+            .get(&server_name.to_ascii_lowercase())
+            .cloned();
+
+        Ok(certificate)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod crypto_store;
+pub mod routes_store;
+pub mod user_settings_store;
@@ -0,0 +1,86 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::Result;
+use link_keys::split_link;
+
+use crate::core::route_patterns::is_pattern;
+use crate::core::RoutesStore;
+use crate::model::Route;
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryRoutesStore {
+    routes: Arc<RwLock<HashMap<String, Route>>>,
+}
+
+//links are keyed as stored, see `LinkKeys`
+fn get_key(switch: &str, link: &str) -> String {
+    format!("{}|{}", switch.to_ascii_lowercase(), link)
+}
+
+impl MemoryRoutesStore {
+    pub fn new(routes: Vec<Route>) -> Self {
+        let store = Self::default();
+
+        store.replace(routes);
+
+        store
+    }
+
+    pub fn insert(&self, route: Route) {
+        let key = get_key(&route.switch, &route.link);
+
+        self.routes.write().unwrap().insert(key, route);
+    }
+
+    pub fn remove(&self, switch: &str, link: &str) -> Option<Route> {
+        self.routes.write().unwrap().remove(&get_key(switch, link))
+    }
+
+    ///
+    /// Replaces all the routes, returns the previous ones.
+    ///
+    pub fn replace(&self, routes: Vec<Route>) -> Vec<Route> {
+        let routes = routes
+            .into_iter()
+            .map(|route| (get_key(&route.switch, &route.link), route))
+            .collect();
+
+        let previous = std::mem::replace(&mut *self.routes.write().unwrap(), routes);
+
+        previous.into_values().collect()
+    }
+}
+
+#[async_trait::async_trait()]
+impl RoutesStore for MemoryRoutesStore {
+    async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
+        let route = self
+            .routes
+            .read()
+            .unwrap()
+            .get(&get_key(switch, path))
+            .cloned();
+
+        Ok(route)
+    }
+
+    async fn get_pattern_routes(&self, switch: &str, domain: &str) -> Result<Vec<Route>> {
+        let routes = self
+            .routes
+            .read()
+            .unwrap()
+            .values()
+            .filter(|route| route.switch.eq_ignore_ascii_case(switch) && is_pattern(&route.link))
+            .filter(|route| {
+                split_link(&route.link)
+                    .is_some_and(|(route_domain, _)| route_domain.eq_ignore_ascii_case(domain))
+            })
+            .cloned()
+            .collect();
+
+        Ok(routes)
+    }
+}
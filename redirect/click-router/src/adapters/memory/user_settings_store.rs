@@ -0,0 +1,56 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::Result;
+
+use crate::core::UserSettingsStore;
+use crate::model::UserSettings;
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryUserSettingsStore {
+    user_settings: Arc<RwLock<HashMap<String, UserSettings>>>,
+}
+
+impl MemoryUserSettingsStore {
+    pub fn new(user_settings: Vec<UserSettings>) -> Self {
+        let store = Self::default();
+
+        store.replace(user_settings);
+
+        store
+    }
+
+    pub fn insert(&self, user_settings: UserSettings) {
+        self.user_settings
+            .write()
+            .unwrap()
+            .insert(user_settings.user_id.clone(), user_settings);
+    }
+
+    pub fn remove(&self, user_id: &str) -> Option<UserSettings> {
+        self.user_settings.write().unwrap().remove(user_id)
+    }
+
+    ///
+    /// Replaces all the user settings, returns the previous ones.
+    ///
+    pub fn replace(&self, user_settings: Vec<UserSettings>) -> Vec<UserSettings> {
+        let user_settings = user_settings
+            .into_iter()
+            .map(|user_settings| (user_settings.user_id.clone(), user_settings))
+            .collect();
+
+        let previous = std::mem::replace(&mut *self.user_settings.write().unwrap(), user_settings);
+
+        previous.into_values().collect()
+    }
+}
+
+#[async_trait::async_trait()]
+impl UserSettingsStore for MemoryUserSettingsStore {
+    async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        Ok(self.user_settings.read().unwrap().get(user_id).cloned())
+    }
+}
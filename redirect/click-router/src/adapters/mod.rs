@@ -1,61 +1,147 @@
-use std::net::IpAddr;
+use std::{net::IpAddr, sync::Arc};
 
 use anyhow::{Error, Result};
 use aws::dynamo::{
     crypto_store::DynamoCryptoStore, routes_store::DynamoRoutesStore,
     user_settings_store::DynamoUserSettingsStore,
 };
-use fluvio::hit_registrar::FluvioHitRegistrar;
+use file::{
+    change_subscriber::FileChangeSubscriber,
+    hit_registrar::{FileHitRegistrar, StdoutHitRegistrar},
+    stores::{FileCryptoStore, FileRoutesStore, FileUserSettingsStore},
+};
+use fluvio::{change_subscriber::FluvioChangeSubscriber, hit_registrar::FluvioHitRegistrar};
 use geo_ip::geo_ip_location_detector::GeoIPLocationDetector;
 use http::{header::IntoHeaderName, uri::Scheme, HeaderValue};
+use memory::{
+    crypto_store::MemoryCryptoStore, routes_store::MemoryRoutesStore,
+    user_settings_store::MemoryUserSettingsStore,
+};
 use moka::{
-    crypto_cache::MokaCryptoCache, routes_cache::MokaRoutesCache,
+    crypto_cache::MokaCryptoCache, routes_cache::MokaRoutesCache, swr_cache::CacheMetrics,
     user_settings_cache::MokaUserSettingsCache,
 };
-use rdkafka::hit_registrar::KafkaHitRegistrar;
+use rdkafka::{change_subscriber::KafkaChangeSubscriber, hit_registrar::KafkaHitRegistrar};
+use redis::{
+    routes_cache::{RedisRoutesCache, RedisRoutesStore},
+    user_settings_cache::{RedisUserSettingsCache, RedisUserSettingsStore},
+};
 use salvo::{SalvoRequest, SalvoResponse};
+use sqlite::{
+    crypto_store::SqliteCryptoStore, routes_store::SqliteRoutesStore,
+    user_settings_store::SqliteUserSettingsStore,
+};
 use uaparser::user_agent_detector::UAParserUserAgentDetector;
 
 use crate::{
     core::{
         crypto::CryptoCache,
         flow_router::{Request, RequestData, Response, ResponseData},
+        hit_queue::HitQueue,
+        hit_spool::SpoolMetrics,
         hits_register::HitRegistrar,
-        location::{Country, LocationDetector},
+        invalidation::{ChangeSubscriber, Invalidator},
+        location::{Country, Location, LocationDetector},
+        route_patterns::RoutePatterns,
         routes::RoutesCache,
         user_agent::{Device, UserAgent, UserAgentDetector, OS},
         user_settings::UserSettingsCache,
         CryptoStore, RoutesStore, UserSettingsStore,
     },
     model::{Hit, Keycert, Route, UserSettings},
+    utils::async_queue::QueueMetrics,
 };
 
 pub mod aws;
+pub mod file;
 pub mod fluvio;
 pub mod geo_ip;
+pub mod memory;
 pub mod moka;
 pub mod rdkafka;
+pub mod redis;
 pub mod salvo;
+pub mod sqlite;
 pub mod uaparser;
 
 #[derive(Clone)]
 pub enum HitRegistrarType {
     Kafka(KafkaHitRegistrar),
     Fluvio(FluvioHitRegistrar),
+    File(FileHitRegistrar),
+    Stdout(StdoutHitRegistrar),
+    Queued(HitQueue),
     None(),
 }
 
+impl HitRegistrarType {
+    ///
+    /// Registers the hits still queued, before the router exits.
+    ///
+    pub async fn close(&self) {
+        if let HitRegistrarType::Queued(queue) = self {
+            queue.close().await;
+        }
+    }
+
+    pub fn queue_metrics(&self) -> Option<QueueMetrics> {
+        match self {
+            HitRegistrarType::Queued(queue) => Some(queue.metrics()),
+            _ => None,
+        }
+    }
+
+    pub fn spool_metrics(&self) -> Option<SpoolMetrics> {
+        match self {
+            HitRegistrarType::Queued(queue) => queue.spool_metrics(),
+            _ => None,
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl HitRegistrar for HitRegistrarType {
-    async fn register(&self, hit: &Hit) -> Result<()> {
+    async fn register(&self, hit: Hit) -> Result<()> {
         match self {
             HitRegistrarType::Kafka(registrar) => registrar.register(hit).await,
             HitRegistrarType::Fluvio(registrar) => registrar.register(hit).await,
+            HitRegistrarType::File(registrar) => registrar.register(hit).await,
+            HitRegistrarType::Stdout(registrar) => registrar.register(hit).await,
+            HitRegistrarType::Queued(registrar) => registrar.register(hit).await,
+            HitRegistrarType::None() => Ok(()),
+        }
+    }
+
+    async fn register_batch(&self, hits: Vec<Hit>) -> Result<()> {
+        match self {
+            HitRegistrarType::Kafka(registrar) => registrar.register_batch(hits).await,
+            HitRegistrarType::Fluvio(registrar) => registrar.register_batch(hits).await,
+            HitRegistrarType::File(registrar) => registrar.register_batch(hits).await,
+            HitRegistrarType::Stdout(registrar) => registrar.register_batch(hits).await,
+            HitRegistrarType::Queued(registrar) => registrar.register_batch(hits).await,
             HitRegistrarType::None() => Ok(()),
         }
     }
 }
 
+#[derive(Clone)]
+pub enum ChangeSubscriberType {
+    Kafka(KafkaChangeSubscriber),
+    Fluvio(FluvioChangeSubscriber),
+    File(FileChangeSubscriber),
+}
+
+#[async_trait::async_trait]
+impl ChangeSubscriber for ChangeSubscriberType {
+    async fn listen(&self, invalidator: Invalidator) -> Result<()> {
+        match self {
+            ChangeSubscriberType::Kafka(subscriber) => subscriber.listen(invalidator).await,
+            ChangeSubscriberType::Fluvio(subscriber) => subscriber.listen(invalidator).await,
+            ChangeSubscriberType::File(subscriber) => subscriber.listen(invalidator).await,
+        }
+    }
+}
+
 pub enum RequestType<'a> {
     //hyper,
     Salvo(&'a SalvoRequest<'a>),
@@ -91,6 +177,13 @@ impl<'a> Request for RequestType<'a> {
         }
     }
 
+    fn version(&self) -> http::Version {
+        match self {
+            RequestType::Salvo(request) => request.version(),
+            RequestType::Test(request) => request.version,
+        }
+    }
+
     fn remote_addr(&self) -> Option<std::net::SocketAddr> {
         match self {
             RequestType::Salvo(request) => request.remote_addr(),
@@ -168,7 +261,10 @@ impl<'a> Response for ResponseType<'a> {
     }
 
     fn cookies_mut(&mut self) -> &mut cookie::CookieJar {
-        todo!()
+        match self {
+            ResponseType::Salvo(response) => response.cookies_mut(),
+            ResponseType::Test(response) => &mut response.cookies,
+        }
     }
 
     fn cookie<T>(&self, name: T) -> Option<&cookie::Cookie<'static>>
@@ -193,6 +289,10 @@ impl<'a> Response for ResponseType<'a> {
 pub enum UserSettingsStoreType {
     //Redis,
     Dynamo(DynamoUserSettingsStore),
+    Sqlite(SqliteUserSettingsStore),
+    Redis(RedisUserSettingsStore),
+    Memory(MemoryUserSettingsStore),
+    File(FileUserSettingsStore),
 }
 
 #[async_trait::async_trait]
@@ -200,14 +300,27 @@ impl UserSettingsStore for UserSettingsStoreType {
     async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
         match self {
             UserSettingsStoreType::Dynamo(store) => store.get_user_settings(user_id).await,
+            UserSettingsStoreType::Sqlite(store) => store.get_user_settings(user_id).await,
+            UserSettingsStoreType::Redis(store) => store.get_user_settings(user_id).await,
+            UserSettingsStoreType::Memory(store) => store.get_user_settings(user_id).await,
+            UserSettingsStoreType::File(store) => store.get_user_settings(user_id).await,
         }
     }
 }
 
 #[derive(Clone)]
 pub enum UserSettingsCacheType {
-    //Redis,
     Moka(MokaUserSettingsCache),
+    Redis(RedisUserSettingsCache),
+}
+
+impl UserSettingsCacheType {
+    pub fn metrics(&self) -> CacheMetrics {
+        match self {
+            UserSettingsCacheType::Moka(cache) => cache.metrics(),
+            UserSettingsCacheType::Redis(cache) => cache.metrics(),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -215,11 +328,13 @@ impl UserSettingsCache for UserSettingsCacheType {
     async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
         match self {
             UserSettingsCacheType::Moka(cache) => cache.get_user_settings(user_id).await,
+            UserSettingsCacheType::Redis(cache) => cache.get_user_settings(user_id).await,
         }
     }
     async fn invalidate(&self, user_id: &str) -> Result<()> {
         match self {
             UserSettingsCacheType::Moka(cache) => cache.invalidate(user_id).await,
+            UserSettingsCacheType::Redis(cache) => cache.invalidate(user_id).await,
         }
     }
 }
@@ -227,6 +342,9 @@ impl UserSettingsCache for UserSettingsCacheType {
 #[derive(Clone)]
 pub enum CryptoStoreType {
     Dynamo(DynamoCryptoStore),
+    Sqlite(SqliteCryptoStore),
+    Memory(MemoryCryptoStore),
+    File(FileCryptoStore),
 }
 
 #[async_trait::async_trait]
@@ -234,6 +352,9 @@ impl CryptoStore for CryptoStoreType {
     async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
         match self {
             CryptoStoreType::Dynamo(store) => store.get_certificate(server_name).await,
+            CryptoStoreType::Sqlite(store) => store.get_certificate(server_name).await,
+            CryptoStoreType::Memory(store) => store.get_certificate(server_name).await,
+            CryptoStoreType::File(store) => store.get_certificate(server_name).await,
         }
     }
 }
@@ -243,6 +364,14 @@ pub enum CryptoCacheType {
     Moka(MokaCryptoCache),
 }
 
+impl CryptoCacheType {
+    pub fn metrics(&self) -> CacheMetrics {
+        match self {
+            CryptoCacheType::Moka(cache) => cache.metrics(),
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl CryptoCache for CryptoCacheType {
     async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
@@ -260,6 +389,10 @@ impl CryptoCache for CryptoCacheType {
 #[derive(Clone)]
 pub enum RoutesStoreType {
     Dynamo(DynamoRoutesStore),
+    Sqlite(SqliteRoutesStore),
+    Redis(RedisRoutesStore),
+    Memory(MemoryRoutesStore),
+    File(FileRoutesStore),
 }
 
 #[async_trait::async_trait]
@@ -267,6 +400,20 @@ impl RoutesStore for RoutesStoreType {
     async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
         match self {
             RoutesStoreType::Dynamo(store) => store.get_route(switch, path).await,
+            RoutesStoreType::Sqlite(store) => store.get_route(switch, path).await,
+            RoutesStoreType::Redis(store) => store.get_route(switch, path).await,
+            RoutesStoreType::Memory(store) => store.get_route(switch, path).await,
+            RoutesStoreType::File(store) => store.get_route(switch, path).await,
+        }
+    }
+
+    async fn get_pattern_routes(&self, switch: &str, domain: &str) -> Result<Vec<Route>> {
+        match self {
+            RoutesStoreType::Dynamo(store) => store.get_pattern_routes(switch, domain).await,
+            RoutesStoreType::Sqlite(store) => store.get_pattern_routes(switch, domain).await,
+            RoutesStoreType::Redis(store) => store.get_pattern_routes(switch, domain).await,
+            RoutesStoreType::Memory(store) => store.get_pattern_routes(switch, domain).await,
+            RoutesStoreType::File(store) => store.get_pattern_routes(switch, domain).await,
         }
     }
 }
@@ -274,6 +421,23 @@ impl RoutesStore for RoutesStoreType {
 #[derive(Clone)]
 pub enum RoutesCacheType {
     Moka(MokaRoutesCache),
+    Redis(RedisRoutesCache),
+}
+
+impl RoutesCacheType {
+    pub fn metrics(&self) -> CacheMetrics {
+        match self {
+            RoutesCacheType::Moka(cache) => cache.metrics(),
+            RoutesCacheType::Redis(cache) => cache.metrics(),
+        }
+    }
+
+    pub fn patterns_metrics(&self) -> CacheMetrics {
+        match self {
+            RoutesCacheType::Moka(cache) => cache.patterns_metrics(),
+            RoutesCacheType::Redis(cache) => cache.patterns_metrics(),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -281,12 +445,21 @@ impl RoutesCache for RoutesCacheType {
     async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
         match self {
             RoutesCacheType::Moka(cache) => cache.get_route(switch, path).await,
+            RoutesCacheType::Redis(cache) => cache.get_route(switch, path).await,
+        }
+    }
+
+    async fn get_route_patterns(&self, switch: &str, domain: &str) -> Result<Arc<RoutePatterns>> {
+        match self {
+            RoutesCacheType::Moka(cache) => cache.get_route_patterns(switch, domain).await,
+            RoutesCacheType::Redis(cache) => cache.get_route_patterns(switch, domain).await,
         }
     }
 
     async fn invalidate(&self, switch: &str, path: &str) -> Result<()> {
         match self {
             RoutesCacheType::Moka(cache) => cache.invalidate(switch, path).await,
+            RoutesCacheType::Redis(cache) => cache.invalidate(switch, path).await,
         }
     }
 }
@@ -305,6 +478,13 @@ impl LocationDetector for LocationDetectorType {
             LocationDetectorType::None() => None,
         }
     }
+
+    fn detect_location(&self, &ip_addr: &IpAddr) -> Option<Location> {
+        match self {
+            LocationDetectorType::GeoIP(locator) => locator.detect_location(&ip_addr),
+            LocationDetectorType::None() => None,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -1,44 +1,33 @@
-use std::time::Duration;
-
 use anyhow::Result;
-use moka::future::Cache;
 
 use crate::adapters::CryptoStoreType;
 use crate::core::crypto::CryptoCache;
+use crate::core::flow_error::FlowError;
 use crate::core::CryptoStore;
 use crate::model::Keycert;
 
 use super::settings::CryptoCacheSettings;
+use super::swr_cache::{CacheMetrics, SwrCache};
 
 const KEY_PREFIX: &'static str = "crypto";
 
-#[derive(Clone, Debug)]
-pub struct KeycertCacheItem {
-    value: Option<Keycert>,
-}
-
 #[derive(Clone)]
 pub struct MokaCryptoCache {
-    cache: Cache<String, KeycertCacheItem>,
+    cache: SwrCache<Keycert>,
     crypto_store: CryptoStoreType,
 }
 
 impl MokaCryptoCache {
     pub fn new(crypto_store: CryptoStoreType, settings: CryptoCacheSettings) -> Self {
-        let cache = Cache::builder()
-            .max_capacity(settings.max_capacity)
-            .time_to_live(Duration::from_secs(settings.time_to_live_minutes * 60))
-            .time_to_idle(Duration::from_secs(settings.time_to_idle_minutes * 60))
-            // .eviction_listener(|key, value, cause| {
-            //     println!("Evicted ({key:?},{value:?}) because {cause:?}")
-            // })
-            .build();
-
         Self {
-            cache,
+            cache: SwrCache::new("crypto", &settings),
             crypto_store,
         }
     }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        self.cache.metrics()
+    }
 }
 
 fn get_key(domain: &str) -> String {
@@ -52,21 +41,26 @@ impl CryptoCache for MokaCryptoCache {
     async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
         let key = get_key(server_name);
 
-        let cache_result = self
+        let crypto_store = self.crypto_store.clone();
+        let server_name = server_name.to_string();
+
+        let certificate = self
             .cache
-            .get_with(key, async move {
-                let cert_result = self.crypto_store.get_certificate(server_name).await;
-                KeycertCacheItem {
-                    value: cert_result.unwrap(),
-                }
+            .get(key, || async move {
+                crypto_store
+                    .get_certificate(&server_name)
+                    .await
+                    .map_err(FlowError::from)
             })
-            .await;
+            .await?;
 
-        Ok(cache_result.value)
+        Ok(certificate)
     }
 
     async fn invalidate(&self, server_name: &str) -> Result<()> {
-        self.cache.invalidate(server_name).await;
+        let key = get_key(server_name);
+
+        self.cache.invalidate(&key).await;
 
         Ok(())
     }
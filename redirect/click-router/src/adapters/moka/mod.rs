@@ -1,5 +1,6 @@
 pub mod crypto_cache;
 pub mod routes_cache;
+pub mod swr_cache;
 pub mod user_settings_cache;
 
 pub mod settings;
@@ -1,46 +1,48 @@
-use std::time::Duration;
+use std::sync::Arc;
 
 use anyhow::Result;
-use moka::future::Cache;
+use link_keys::split_link;
+use tracing::warn;
 
 use crate::adapters::RoutesStoreType;
+use crate::core::expression_plan::compile_route;
+use crate::core::flow_error::FlowError;
+use crate::core::route_patterns::{is_pattern, RoutePatterns};
 use crate::core::routes::RoutesCache;
 use crate::core::RoutesStore;
 use crate::model::Route;
 
 use super::settings::RoutesCacheSettings;
-
-#[derive(Clone, Debug)]
-pub struct RouteCacheItem {
-    value: Option<Route>,
-}
+use super::swr_cache::{CacheMetrics, SwrCache};
 
 #[derive(Clone)]
 pub struct MokaRoutesCache {
-    cache: Cache<String, RouteCacheItem>,
+    cache: SwrCache<Route>,
+    patterns_cache: SwrCache<Arc<RoutePatterns>>,
     routes_store: RoutesStoreType,
 }
 
 impl MokaRoutesCache {
     pub fn new(routes_store: RoutesStoreType, settings: RoutesCacheSettings) -> Self {
-        let cache = Cache::builder()
-            .max_capacity(settings.max_capacity)
-            .time_to_live(Duration::from_secs(settings.time_to_live_minutes * 60))
-            .time_to_idle(Duration::from_secs(settings.time_to_idle_minutes * 60))
-            // .eviction_listener(|key, value, cause| {
-            //     println!("Evicted ({key:?},{value:?}) because {cause:?}")
-            // })
-            .build();
-
         Self {
-            cache,
+            cache: SwrCache::new("routes", &settings),
+            patterns_cache: SwrCache::new("route patterns", &settings),
             routes_store,
         }
     }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        self.cache.metrics()
+    }
+
+    pub fn patterns_metrics(&self) -> CacheMetrics {
+        self.patterns_cache.metrics()
+    }
 }
 
+//links are keyed as stored, see `LinkKeys`
 fn get_key(switch: &str, link: &str) -> String {
-    format!("{}|{}", switch, link).to_ascii_lowercase()
+    format!("{}|{}", switch.to_ascii_lowercase(), link)
 }
 
 #[async_trait::async_trait()]
@@ -50,22 +52,69 @@ impl RoutesCache for MokaRoutesCache {
 
         self.cache.invalidate(&key).await;
 
+        if let Some((domain, _)) = split_link(path).filter(|_| is_pattern(path)) {
+            self.patterns_cache
+                .invalidate(&get_key(switch, domain))
+                .await;
+        }
+
         Ok(())
     }
 
     async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
         let key = get_key(switch, path);
 
-        let cache_result = self
+        let routes_store = self.routes_store.clone();
+        let (switch, path) = (switch.to_string(), path.to_string());
+
+        //conditions are compiled once, when the route gets into the cache
+        let route = self
             .cache
-            .get_with(key, async move {
-                let route_result = self.routes_store.get_route(switch, path).await;
-                RouteCacheItem {
-                    value: route_result.unwrap(),
+            .get(key, || async move {
+                let mut route = routes_store
+                    .get_route(&switch, &path)
+                    .await
+                    .map_err(|error| FlowError::RoutesUnavailable(error.to_string()))?;
+
+                if let Some(route) = route.as_mut() {
+                    compile_route(route)
+                        .map_err(|error| FlowError::InvalidRoute(error.to_string()))?;
                 }
+
+                Ok(route)
+            })
+            .await?;
+
+        Ok(route)
+    }
+    async fn get_route_patterns(&self, switch: &str, domain: &str) -> Result<Arc<RoutePatterns>> {
+        let key = get_key(switch, domain);
+
+        let routes_store = self.routes_store.clone();
+        let (switch, domain) = (switch.to_string(), domain.to_string());
+
+        //an empty matcher is cached too, most domains have no patterns
+        let patterns = self
+            .patterns_cache
+            .get(key, || async move {
+                let mut routes = routes_store
+                    .get_pattern_routes(&switch, &domain)
+                    .await
+                    .map_err(|error| FlowError::RoutesUnavailable(error.to_string()))?;
+
+                //like a broken pattern, a broken route only disables itself
+                routes.retain_mut(|route| match compile_route(route) {
+                    Ok(()) => true,
+                    Err(error) => {
+                        warn!("invalid pattern route '{}': {}", route.link, error);
+                        false
+                    }
+                });
+
+                Ok(Some(Arc::new(RoutePatterns::new(routes))))
             })
-            .await;
+            .await?;
 
-        Ok(cache_result.value)
+        Ok(patterns.unwrap_or_default())
     }
 }
@@ -1,25 +1,20 @@
 use serde_derive::Deserialize;
 #[derive(Default, Debug, Deserialize, Clone)]
 #[allow(unused)]
-pub struct CryptoCacheSettings {
-    pub max_capacity: u64,
-    pub time_to_live_minutes: u64,
-    pub time_to_idle_minutes: u64,
-}
-#[derive(Default, Debug, Deserialize, Clone)]
-#[allow(unused)]
-pub struct RoutesCacheSettings {
-    pub max_capacity: u64,
-    pub time_to_live_minutes: u64,
-    pub time_to_idle_minutes: u64,
-}
-#[derive(Default, Debug, Deserialize, Clone)]
-#[allow(unused)]
-pub struct UserSettingsCacheSettings {
+pub struct CacheSettings {
     pub max_capacity: u64,
     pub time_to_live_minutes: u64,
     pub time_to_idle_minutes: u64,
+    /// How long an expired entry is still served, while it is refreshed or when the store fails.
+    #[serde(default)]
+    pub stale_minutes: u64,
+    /// Time to live of missing entries, short so that new keys show up quickly.
+    #[serde(default)]
+    pub negative_ttl_seconds: u64,
 }
+pub type CryptoCacheSettings = CacheSettings;
+pub type RoutesCacheSettings = CacheSettings;
+pub type UserSettingsCacheSettings = CacheSettings;
 #[derive(Default, Debug, Deserialize, Clone)]
 #[allow(unused)]
 pub struct Moka {
@@ -0,0 +1,460 @@
+//!
+//! Moka cache serving expired entries while they are refreshed in the background, and for as
+//! long as the store fails, so that a store outage does not take down redirects.
+//!
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use moka::{future::Cache, notification::RemovalCause, Expiry};
+use tracing::warn;
+
+use crate::core::flow_error::FlowError;
+
+use super::settings::CacheSettings;
+
+#[derive(Clone, Debug)]
+struct SwrEntry<V> {
+    value: Option<V>,
+    stale_at: Instant,
+}
+
+struct SwrExpiry {
+    hit_ttl: Duration,
+    miss_ttl: Duration,
+}
+
+impl<V> Expiry<String, SwrEntry<V>> for SwrExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        entry: &SwrEntry<V>,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(self.ttl(entry))
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        entry: &SwrEntry<V>,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(self.ttl(entry))
+    }
+}
+
+impl SwrExpiry {
+    fn ttl<V>(&self, entry: &SwrEntry<V>) -> Duration {
+        match entry.value {
+            Some(_) => self.hit_ttl,
+            None => self.miss_ttl,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    stale_hits: AtomicU64,
+    misses: AtomicU64,
+    loads: AtomicU64,
+    refreshes: AtomicU64,
+    failed_refreshes: AtomicU64,
+    evictions: AtomicU64,
+}
+
+///
+/// Counters of a cache since it was created.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheMetrics {
+    /// Fresh entries served.
+    pub hits: u64,
+    /// Expired entries served while refreshing, or because the store failed.
+    pub stale_hits: u64,
+    /// Lookups of absent entries.
+    pub misses: u64,
+    /// Store lookups of absent entries, concurrent misses of a key share one load.
+    pub loads: u64,
+    /// Background refreshes of expired entries.
+    pub refreshes: u64,
+    /// Refreshes that failed and kept the expired entry.
+    pub failed_refreshes: u64,
+    /// Entries evicted to stay within the capacity.
+    pub evictions: u64,
+}
+
+impl CacheMetrics {
+    /// Misses answered by another request's load.
+    pub fn coalesced(&self) -> u64 {
+        self.misses.saturating_sub(self.loads)
+    }
+}
+
+#[derive(Clone)]
+pub struct SwrCache<V> {
+    name: &'static str,
+    cache: Cache<String, SwrEntry<V>>,
+    fresh_for: Duration,
+    miss_ttl: Duration,
+    //keys being refreshed, flagged when invalidated meanwhile so that the old value is not put back
+    refreshing: Arc<Mutex<HashMap<String, bool>>>,
+    counters: Arc<CacheCounters>,
+}
+
+impl<V> SwrCache<V>
+where
+    V: Clone + Send + Sync + 'static,
+{
+    pub fn new(name: &'static str, settings: &CacheSettings) -> Self {
+        let fresh_for = Duration::from_secs(settings.time_to_live_minutes * 60);
+        let miss_ttl = Duration::from_secs(settings.negative_ttl_seconds);
+
+        let counters = Arc::new(CacheCounters::default());
+        let evicted = counters.clone();
+
+        let cache = Cache::builder()
+            .max_capacity(settings.max_capacity)
+            .time_to_idle(Duration::from_secs(settings.time_to_idle_minutes * 60))
+            .expire_after(SwrExpiry {
+                hit_ttl: fresh_for + Duration::from_secs(settings.stale_minutes * 60),
+                miss_ttl,
+            })
+            .eviction_listener(move |_key, _entry, cause| {
+                if cause == RemovalCause::Size {
+                    evicted.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build();
+
+        Self {
+            name,
+            cache,
+            fresh_for,
+            miss_ttl,
+            refreshing: Arc::new(Mutex::new(HashMap::new())),
+            counters,
+        }
+    }
+
+    ///
+    /// Value of the key, loaded once for concurrent misses. An expired value is returned
+    /// right away and refreshed in the background.
+    ///
+    pub async fn get<F, Fut>(&self, key: String, load: F) -> Result<Option<V>, FlowError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<V>, FlowError>> + Send + 'static,
+    {
+        if let Some(entry) = self.cache.get(&key).await {
+            if Instant::now() < entry.stale_at {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.counters.stale_hits.fetch_add(1, Ordering::Relaxed);
+                self.refresh(key, load());
+            }
+
+            return Ok(entry.value);
+        }
+
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+
+        let this = self.clone();
+        let loading = load();
+
+        self.cache
+            .try_get_with(key, async move {
+                this.counters.loads.fetch_add(1, Ordering::Relaxed);
+
+                Ok::<SwrEntry<V>, FlowError>(this.entry(loading.await?))
+            })
+            .await
+            .map(|entry| entry.value)
+            .map_err(|error| error.as_ref().clone())
+    }
+
+    pub async fn invalidate(&self, key: &str) {
+        if let Some(invalidated) = self.refreshing.lock().unwrap().get_mut(key) {
+            *invalidated = true;
+        }
+
+        self.cache.invalidate(key).await;
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            stale_hits: self.counters.stale_hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            loads: self.counters.loads.load(Ordering::Relaxed),
+            refreshes: self.counters.refreshes.load(Ordering::Relaxed),
+            failed_refreshes: self.counters.failed_refreshes.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn entry(&self, value: Option<V>) -> SwrEntry<V> {
+        let fresh_for = match value {
+            Some(_) => self.fresh_for,
+            None => self.miss_ttl,
+        };
+
+        SwrEntry {
+            value,
+            stale_at: Instant::now() + fresh_for,
+        }
+    }
+
+    fn refresh<Fut>(&self, key: String, loading: Fut)
+    where
+        Fut: Future<Output = Result<Option<V>, FlowError>> + Send + 'static,
+    {
+        //one refresh per key at a time, the other requests keep getting the expired value
+        {
+            let mut refreshing = self.refreshing.lock().unwrap();
+
+            if refreshing.contains_key(&key) {
+                return;
+            }
+
+            refreshing.insert(key.clone(), false);
+        }
+
+        self.counters.refreshes.fetch_add(1, Ordering::Relaxed);
+
+        let this = self.clone();
+
+        tokio::spawn(async move {
+            match loading.await {
+                Ok(value) => {
+                    if !this.is_invalidated(&key) {
+                        this.cache.insert(key.clone(), this.entry(value)).await;
+
+                        //an invalidation racing the insert still wins
+                        if this.is_invalidated(&key) {
+                            this.cache.invalidate(&key).await;
+                        }
+                    }
+                }
+                Err(error) => {
+                    this.counters
+                        .failed_refreshes
+                        .fetch_add(1, Ordering::Relaxed);
+
+                    warn!(
+                        "{} cache refresh of '{}' failed, serving the expired value: {}",
+                        this.name, key, error
+                    );
+                }
+            }
+
+            this.refreshing.lock().unwrap().remove(&key);
+        });
+    }
+
+    fn is_invalidated(&self, key: &str) -> bool {
+        self.refreshing
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|invalidated| *invalidated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(time_to_live_minutes: u64) -> SwrCache<String> {
+        SwrCache::new(
+            "test",
+            &CacheSettings {
+                max_capacity: 100,
+                time_to_live_minutes,
+                time_to_idle_minutes: 60,
+                stale_minutes: 60,
+                negative_ttl_seconds: 30,
+            },
+        )
+    }
+
+    async fn found(value: &str) -> Result<Option<String>, FlowError> {
+        Ok(Some(value.to_string()))
+    }
+
+    async fn failed() -> Result<Option<String>, FlowError> {
+        Err(FlowError::RoutesUnavailable("throttled".to_string()))
+    }
+
+    #[tokio::test]
+    async fn should_load_once_and_serve_fresh_hits() {
+        let cache = cache(60);
+
+        assert_eq!(
+            cache.get("a".to_string(), || found("1")).await,
+            Ok(Some("1".to_string()))
+        );
+        assert_eq!(
+            cache.get("a".to_string(), || found("2")).await,
+            Ok(Some("1".to_string()))
+        );
+
+        let metrics = cache.metrics();
+
+        assert_eq!((metrics.hits, metrics.misses, metrics.loads), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn should_coalesce_concurrent_misses() {
+        let cache = cache(60);
+
+        let slow = || async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            found("1").await
+        };
+
+        let (first, second) = tokio::join!(
+            cache.get("a".to_string(), slow),
+            cache.get("a".to_string(), slow)
+        );
+
+        assert_eq!(first, second);
+        assert_eq!(cache.metrics().coalesced(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_serve_stale_values_and_refresh_them() {
+        //entries are stale right away
+        let cache = cache(0);
+
+        cache.get("a".to_string(), || found("1")).await.unwrap();
+
+        assert_eq!(
+            cache.get("a".to_string(), || found("2")).await,
+            Ok(Some("1".to_string()))
+        );
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(
+            cache.get("a".to_string(), || found("3")).await,
+            Ok(Some("2".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_serve_stale_values_when_the_store_fails() {
+        let cache = cache(0);
+
+        cache.get("a".to_string(), || found("1")).await.unwrap();
+
+        assert_eq!(
+            cache.get("a".to_string(), failed).await,
+            Ok(Some("1".to_string()))
+        );
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(
+            cache.get("a".to_string(), failed).await,
+            Ok(Some("1".to_string()))
+        );
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(cache.metrics().failed_refreshes, 2);
+    }
+
+    #[tokio::test]
+    async fn should_fail_misses_when_the_store_fails() {
+        let cache = cache(60);
+
+        assert_eq!(
+            cache.get("a".to_string(), failed).await,
+            Err(FlowError::RoutesUnavailable("throttled".to_string()))
+        );
+        assert_eq!(
+            cache.get("a".to_string(), || found("1")).await,
+            Ok(Some("1".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_cache_misses_and_drop_invalidated_keys() {
+        let cache = cache(60);
+
+        assert_eq!(
+            cache.get("a".to_string(), || async { Ok(None) }).await,
+            Ok(None)
+        );
+        assert_eq!(cache.get("a".to_string(), || found("1")).await, Ok(None));
+
+        cache.invalidate("a").await;
+
+        assert_eq!(
+            cache.get("a".to_string(), || found("1")).await,
+            Ok(Some("1".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_only_drop_the_refreshes_of_invalidated_keys() {
+        let cache = cache(0);
+
+        let slow = |value: &'static str| {
+            move || async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                found(value).await
+            }
+        };
+
+        cache.get("a".to_string(), || found("1")).await.unwrap();
+        cache.get("b".to_string(), || found("1")).await.unwrap();
+
+        //both refreshes start, only the one of the invalidated key is dropped
+        cache.get("a".to_string(), slow("2")).await.unwrap();
+        cache.get("b".to_string(), slow("2")).await.unwrap();
+
+        cache.invalidate("a").await;
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(
+            cache.get("a".to_string(), || found("3")).await,
+            Ok(Some("3".to_string()))
+        );
+        assert_eq!(
+            cache.get("b".to_string(), || found("3")).await,
+            Ok(Some("2".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_count_the_evictions() {
+        let cache = SwrCache::<String>::new(
+            "test",
+            &CacheSettings {
+                max_capacity: 1,
+                time_to_live_minutes: 60,
+                time_to_idle_minutes: 60,
+                stale_minutes: 60,
+                negative_ttl_seconds: 30,
+            },
+        );
+
+        for key in ["a", "b", "c"] {
+            cache.get(key.to_string(), || found(key)).await.unwrap();
+            cache.cache.run_pending_tasks().await;
+        }
+
+        assert!(cache.metrics().evictions >= 1);
+    }
+}
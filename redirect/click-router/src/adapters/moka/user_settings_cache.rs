@@ -1,25 +1,19 @@
-use std::time::Duration;
-
 use anyhow::Result;
-use moka::future::Cache;
 
 use crate::adapters::UserSettingsStoreType;
+use crate::core::flow_error::FlowError;
 use crate::core::user_settings::UserSettingsCache;
 use crate::core::UserSettingsStore;
 use crate::model::UserSettings;
 
 use super::settings::UserSettingsCacheSettings;
+use super::swr_cache::{CacheMetrics, SwrCache};
 
 const KEY_PREFIX: &'static str = "settings";
 
-#[derive(Clone, Debug)]
-pub struct UserSettingsCacheItem {
-    value: Option<UserSettings>,
-}
-
 #[derive(Clone)]
 pub struct MokaUserSettingsCache {
-    cache: Cache<String, UserSettingsCacheItem>,
+    cache: SwrCache<UserSettings>,
     user_settings_store: UserSettingsStoreType,
 }
 
@@ -28,20 +22,15 @@ impl MokaUserSettingsCache {
         user_settings_store: UserSettingsStoreType,
         settings: UserSettingsCacheSettings,
     ) -> Self {
-        let cache = Cache::builder()
-            .max_capacity(settings.max_capacity)
-            .time_to_live(Duration::from_secs(settings.time_to_live_minutes * 60))
-            .time_to_idle(Duration::from_secs(settings.time_to_idle_minutes * 60))
-            // .eviction_listener(|key, value, cause| {
-            //     println!("Evicted ({key:?},{value:?}) because {cause:?}")
-            // })
-            .build();
-
         Self {
-            cache,
+            cache: SwrCache::new("user settings", &settings),
             user_settings_store,
         }
     }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        self.cache.metrics()
+    }
 }
 
 fn get_key(user_id: &str) -> String {
@@ -55,22 +44,26 @@ impl UserSettingsCache for MokaUserSettingsCache {
     async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
         let key = get_key(user_id);
 
-        let cache_result = self
+        let user_settings_store = self.user_settings_store.clone();
+        let user_id = user_id.to_string();
+
+        let user_settings = self
             .cache
-            .get_with(key, async move {
-                let user_settings_result =
-                    self.user_settings_store.get_user_settings(user_id).await;
-                UserSettingsCacheItem {
-                    value: user_settings_result.unwrap(),
-                }
+            .get(key, || async move {
+                user_settings_store
+                    .get_user_settings(&user_id)
+                    .await
+                    .map_err(FlowError::from)
             })
-            .await;
+            .await?;
 
-        Ok(cache_result.value)
+        Ok(user_settings)
     }
 
-    async fn invalidate(&self, server_name: &str) -> Result<()> {
-        self.cache.invalidate(server_name).await;
+    async fn invalidate(&self, user_id: &str) -> Result<()> {
+        let key = get_key(user_id);
+
+        self.cache.invalidate(&key).await;
 
         Ok(())
     }
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use rdkafka::{
+    consumer::{Consumer, StreamConsumer},
+    ClientConfig, Message,
+};
+use tracing::{info, warn};
+use ulid::Ulid;
+
+use crate::core::invalidation::{ChangeSubscriber, Invalidator};
+
+use super::settings::ChangeStreamConfig;
+
+#[derive(Clone)]
+pub struct KafkaChangeSubscriber {
+    settings: ChangeStreamConfig,
+}
+
+impl KafkaChangeSubscriber {
+    pub fn new(settings: ChangeStreamConfig) -> Self {
+        Self { settings }
+    }
+}
+
+#[async_trait::async_trait()]
+impl ChangeSubscriber for KafkaChangeSubscriber {
+    async fn listen(&self, invalidator: Invalidator) -> Result<()> {
+        //every instance evicts its own cache, so each one gets a group of its own
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", self.settings.hosts.join(","))
+            .set(
+                "group.id",
+                format!("{}-{}", self.settings.group_prefix, Ulid::new()),
+            )
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "latest")
+            .create()?;
+
+        consumer.subscribe(&[&self.settings.topic])?;
+
+        info!("  {} -> {}", "change stream", self.settings.topic);
+
+        loop {
+            match consumer.recv().await {
+                Ok(message) => {
+                    if let Some(payload) = message.payload() {
+                        invalidator.apply_payload(payload).await;
+                    }
+                }
+                Err(error) => warn!("change stream error: {}", error),
+            }
+        }
+    }
+}
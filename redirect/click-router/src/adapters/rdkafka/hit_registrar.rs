@@ -1,10 +1,10 @@
-use std::time::Duration;
-
-use anyhow::{Ok, Result};
+use anyhow::{Error, Result};
 use rdkafka::{
-    producer::{FutureProducer, FutureRecord},
+    message::{Header, OwnedHeaders},
+    producer::{DeliveryFuture, FutureProducer, FutureRecord},
     ClientConfig,
 };
+use tracing::error;
 
 use crate::{core::hits_register::HitRegistrar, model::Hit};
 
@@ -18,37 +18,104 @@ pub struct KafkaHitRegistrar {
 
 impl KafkaHitRegistrar {
     pub fn new(settings: HitStreamConfig) -> Self {
-        let producer = ClientConfig::new()
+        let mut config = ClientConfig::new();
+
+        config
             .set("bootstrap.servers", settings.hosts.join(","))
-            .set("message.timeout.ms", settings.ack_timeout_secs.to_string())
+            .set(
+                "message.timeout.ms",
+                (settings.ack_timeout_secs * 1000).to_string(),
+            )
+            .set("batch.num.messages", settings.batch_size.to_string())
+            .set("acks", "all");
+
+        for (key, value) in &settings.producer {
+            config.set(key, value);
+        }
+
+        let producer = config
             .create()
             .expect("Hit registrar producer creation error");
 
         Self { producer, settings }
     }
+
+    ///
+    /// Queues the hit in the producer, the future resolves with its delivery report.
+    ///
+    fn enqueue(&self, hit: &Hit) -> Result<DeliveryFuture> {
+        let payload = serde_json::to_vec(hit)?;
+        let version = hit.version.to_string();
+
+        //hits of a route share a partition, so they are consumed in order
+        let key = hit
+            .route
+            .as_ref()
+            .and_then(|route| route.id.as_deref())
+            .unwrap_or(&hit.id);
+
+        let record = FutureRecord::to(&self.settings.topic)
+            .payload(&payload)
+            .key(key)
+            .headers(OwnedHeaders::new().insert(Header {
+                key: "schema_version",
+                value: Some(version.as_str()),
+            }));
+
+        self.producer
+            .send_result(record)
+            .map_err(|(error, _)| error.into())
+    }
+}
+
+async fn delivered(delivery: DeliveryFuture) -> Result<()> {
+    match delivery.await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err((error, _))) => Err(error.into()),
+        //the producer was dropped before reporting the delivery
+        Err(_) => Err(Error::msg("hit delivery canceled")),
+    }
 }
 
 #[async_trait::async_trait()]
 impl HitRegistrar for KafkaHitRegistrar {
-    async fn register(&self, hit: &Hit) -> Result<()> {
-        let result = self
-            .producer
-            .send(
-                FutureRecord::to(&self.settings.topic)
-                    .payload(&serde_json::to_vec(&hit).unwrap())
-                    .key(hit.id),
-                // .headers(OwnedHeaders::new().insert(Header {
-                //     key: "header_key",
-                //     value: Some("header_value"),
-                // })),
-                Duration::from_secs(0),
-            )
-            .await;
+    async fn register(&self, hit: Hit) -> Result<()> {
+        delivered(self.enqueue(&hit)?).await
+    }
+
+    ///
+    /// Queues the whole batch before waiting for the delivery reports.
+    ///
+    async fn register_batch(&self, hits: Vec<Hit>) -> Result<()> {
+        let mut deliveries = Vec::with_capacity(hits.len());
+        let mut failed = 0;
 
-        if let Err(err) = result {
-            return Err(err.0.into());
+        for hit in &hits {
+            match self.enqueue(hit) {
+                Ok(delivery) => deliveries.push(delivery),
+                Err(error) => {
+                    error!("hit {} was not queued: {}", hit.id, error);
+
+                    failed += 1;
+                }
+            }
         }
 
-        Ok(())
+        for delivery in deliveries {
+            if let Err(error) = delivered(delivery).await {
+                error!("hit delivery failed: {}", error);
+
+                failed += 1;
+            }
+        }
+
+        match failed {
+            0 => Ok(()),
+            _ => Err(Error::msg(format!(
+                "{} of {} hits were not delivered",
+                failed,
+                hits.len()
+            ))),
+        }
     }
 }
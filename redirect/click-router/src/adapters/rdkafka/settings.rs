@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde_derive::Deserialize;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -9,10 +11,23 @@ pub struct HitStreamConfig {
     pub batch_size: usize,
     pub consumers_count: usize,
     pub iteration_seconds: u64,
+    /// librdkafka producer properties, e.g. `compression.type`.
+    #[serde(default)]
+    pub producer: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct ChangeStreamConfig {
+    pub hosts: Vec<String>,
+    pub topic: String,
+    /// Prefix of the consumer group, every router instance gets a group of its own.
+    pub group_prefix: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 #[allow(unused)]
 pub struct Kafka {
-    pub hit_stream: HitStreamConfig
-}
\ No newline at end of file
+    pub hit_stream: HitStreamConfig,
+    pub change_stream: ChangeStreamConfig,
+}
@@ -0,0 +1,5 @@
+pub mod redis_cache;
+pub mod routes_cache;
+pub mod user_settings_cache;
+
+pub mod settings;
@@ -0,0 +1,288 @@
+//!
+//! Second cache layer shared by the router instances, so that a restarted fleet warms up from
+//! Redis instead of the stores. Redis errors are logged and fall through to the stores.
+//!
+use std::{future::Future, time::Duration};
+
+use anyhow::Result;
+use redis::{aio::ConnectionManager, AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{info, warn};
+
+use super::settings::{Redis, RedisCacheSettings};
+
+/// How long a key remembers its deletes, longer than any load of its value.
+const GENERATION_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone)]
+pub struct RedisCache {
+    connection: ConnectionManager,
+    key_prefix: String,
+}
+
+impl RedisCache {
+    pub async fn connect(settings: &Redis) -> Result<Self> {
+        let client = redis::Client::open(settings.url.as_str())?;
+        let connection = ConnectionManager::new(client).await?;
+
+        info!("  {} -> {}", "redis", settings.url);
+
+        Ok(Self {
+            connection,
+            key_prefix: settings.key_prefix.clone(),
+        })
+    }
+
+    pub fn key(&self, kind: &str, id: &str) -> String {
+        format!("{}:{}:{}", self.key_prefix, kind, id)
+    }
+
+    //bumped by every delete of the key
+    fn generation_key(key: &str) -> String {
+        format!("{}#generation", key)
+    }
+
+    async fn generation(&self, key: &str) -> Result<Option<u64>> {
+        let mut connection = self.connection.clone();
+
+        Ok(connection.get(Self::generation_key(key)).await?)
+    }
+
+    ///
+    /// Cached value of the key, `Some(None)` for a cached miss.
+    ///
+    pub async fn get<V: DeserializeOwned>(&self, key: &str) -> Result<Option<Option<V>>> {
+        let mut connection = self.connection.clone();
+
+        let value: Option<String> = connection.get(key).await?;
+
+        Ok(value
+            .map(|value| serde_json::from_str(&value))
+            .transpose()?)
+    }
+
+    pub async fn set<V: Serialize>(
+        &self,
+        key: &str,
+        value: &Option<V>,
+        ttl: Duration,
+    ) -> Result<()> {
+        //redis refuses a zero expiration
+        if ttl.is_zero() {
+            return Ok(());
+        }
+
+        let mut connection = self.connection.clone();
+
+        let _: () = connection
+            .set_ex(key, serde_json::to_string(value)?, ttl.as_secs())
+            .await?;
+
+        Ok(())
+    }
+
+    ///
+    /// Sets the key unless it exists, whether it was set.
+    ///
+    pub async fn set_if_absent(&self, key: &str, ttl: Duration) -> Result<bool> {
+        let mut connection = self.connection.clone();
+
+        let set: Option<String> = connection
+            .set_options(
+                key,
+                1,
+                SetOptions::default()
+                    .conditional_set(ExistenceCheck::NX)
+                    .with_expiration(SetExpiry::EX(ttl.as_secs().max(1))),
+            )
+            .await?;
+
+        Ok(set.is_some())
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        let mut connection = self.connection.clone();
+        let generation_key = Self::generation_key(key);
+
+        let _: () = redis::pipe()
+            .atomic()
+            .incr(&generation_key, 1)
+            .ignore()
+            .expire(&generation_key, GENERATION_TTL.as_secs() as i64)
+            .ignore()
+            .del(key)
+            .ignore()
+            .query_async(&mut connection)
+            .await?;
+
+        Ok(())
+    }
+
+    ///
+    /// Value of the key from Redis, or from the load, which is then written to Redis.
+    ///
+    /// A delete during the load means the loaded value may be stale: the generation of the key
+    /// is read again after the write, and the value is dropped when it changed.
+    ///
+    pub async fn get_or_load<V, F, Fut>(
+        &self,
+        key: &str,
+        settings: &RedisCacheSettings,
+        load: F,
+    ) -> Result<Option<V>>
+    where
+        V: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<V>>>,
+    {
+        match self.get(key).await {
+            Ok(Some(value)) => return Ok(value),
+            Ok(None) => {}
+            Err(error) => warn!("redis read of '{}' failed: {}", key, error),
+        }
+
+        let generation = match self.generation(key).await {
+            Ok(generation) => generation,
+            Err(error) => {
+                //without a generation a concurrent delete could not be detected
+                warn!("redis read of '{}' failed: {}", key, error);
+                return load().await;
+            }
+        };
+
+        let value = load().await?;
+
+        let ttl = match value {
+            Some(_) => Duration::from_secs(settings.time_to_live_minutes * 60),
+            None => Duration::from_secs(settings.negative_ttl_seconds),
+        };
+
+        if let Err(error) = self.write_back(key, &value, ttl, generation).await {
+            warn!("redis write of '{}' failed: {}", key, error);
+        }
+
+        Ok(value)
+    }
+
+    async fn write_back<V: Serialize>(
+        &self,
+        key: &str,
+        value: &Option<V>,
+        ttl: Duration,
+        generation: Option<u64>,
+    ) -> Result<()> {
+        self.set(key, value, ttl).await?;
+
+        if self.generation(key).await? != generation {
+            let mut connection = self.connection.clone();
+
+            let _: () = connection.del(key).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    async fn connect() -> RedisCache {
+        let url = std::env::var("REDIS_URL").unwrap_or("redis://127.0.0.1:6379".to_string());
+
+        RedisCache::connect(&Redis {
+            url,
+            key_prefix: format!("test-{}", ulid::Ulid::new()),
+            routes_cache: Default::default(),
+            user_settings_cache: Default::default(),
+        })
+        .await
+        .unwrap()
+    }
+
+    fn settings() -> RedisCacheSettings {
+        RedisCacheSettings {
+            time_to_live_minutes: 1,
+            negative_ttl_seconds: 60,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a redis-server, REDIS_URL or redis://127.0.0.1:6379"]
+    async fn should_load_once_and_share_values() {
+        let cache = connect().await;
+        let key = cache.key("value", "a");
+        let loads = AtomicU64::new(0);
+
+        for _ in 0..2 {
+            let value = cache
+                .get_or_load(&key, &settings(), || async {
+                    loads.fetch_add(1, Ordering::Relaxed);
+                    Ok(Some("1".to_string()))
+                })
+                .await
+                .unwrap();
+
+            assert_eq!(value, Some("1".to_string()));
+        }
+
+        assert_eq!(loads.load(Ordering::Relaxed), 1);
+
+        cache.delete(&key).await.unwrap();
+
+        assert_eq!(cache.get::<String>(&key).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a redis-server, REDIS_URL or redis://127.0.0.1:6379"]
+    async fn should_cache_misses() {
+        let cache = connect().await;
+        let key = cache.key("value", "missing");
+
+        let value: Option<String> = cache
+            .get_or_load(&key, &settings(), || async { Ok(None) })
+            .await
+            .unwrap();
+
+        assert_eq!(value, None);
+        assert_eq!(cache.get::<String>(&key).await.unwrap(), Some(None));
+
+        cache.delete(&key).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a redis-server, REDIS_URL or redis://127.0.0.1:6379"]
+    async fn should_not_write_back_values_loaded_before_a_delete() {
+        let cache = connect().await;
+        let key = cache.key("value", "b");
+
+        let value = cache
+            .get_or_load(&key, &settings(), || async {
+                //the value changes and is invalidated while it is being loaded
+                cache.delete(&key).await.unwrap();
+                Ok(Some("stale".to_string()))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(value, Some("stale".to_string()));
+        assert_eq!(cache.get::<String>(&key).await.unwrap(), None);
+
+        let value = cache
+            .get_or_load(&key, &settings(), || async {
+                Ok(Some("fresh".to_string()))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(value, Some("fresh".to_string()));
+        assert_eq!(
+            cache.get::<String>(&key).await.unwrap(),
+            Some(Some("fresh".to_string()))
+        );
+
+        cache.delete(&key).await.unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::adapters::moka::{
+    routes_cache::MokaRoutesCache, settings::RoutesCacheSettings, swr_cache::CacheMetrics,
+};
+use crate::adapters::RoutesStoreType;
+use crate::core::route_patterns::RoutePatterns;
+use crate::core::routes::RoutesCache;
+use crate::core::RoutesStore;
+use crate::model::Route;
+
+use super::{redis_cache::RedisCache, settings::RedisCacheSettings};
+
+const KIND: &'static str = "route";
+
+//links are keyed as stored, see `LinkKeys`
+fn get_key(switch: &str, link: &str) -> String {
+    format!("{}|{}", switch.to_ascii_lowercase(), link)
+}
+
+///
+/// Routes from Redis, loaded from the store on a miss and written back for the other instances.
+///
+#[derive(Clone)]
+pub struct RedisRoutesStore {
+    cache: RedisCache,
+    settings: RedisCacheSettings,
+    routes_store: Box<RoutesStoreType>,
+}
+
+impl RedisRoutesStore {
+    pub async fn invalidate(&self, switch: &str, path: &str) -> Result<()> {
+        self.cache
+            .delete(&self.cache.key(KIND, &get_key(switch, path)))
+            .await
+    }
+}
+
+#[async_trait::async_trait()]
+impl RoutesStore for RedisRoutesStore {
+    async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
+        let key = self.cache.key(KIND, &get_key(switch, path));
+
+        self.cache
+            .get_or_load(&key, &self.settings, || {
+                self.routes_store.get_route(switch, path)
+            })
+            .await
+    }
+
+    //patterns are compiled per instance, their routes are read from the store
+    async fn get_pattern_routes(&self, switch: &str, domain: &str) -> Result<Vec<Route>> {
+        self.routes_store.get_pattern_routes(switch, domain).await
+    }
+}
+
+///
+/// Moka in front of Redis in front of the routes store.
+///
+#[derive(Clone)]
+pub struct RedisRoutesCache {
+    local: MokaRoutesCache,
+    shared: RedisRoutesStore,
+}
+
+impl RedisRoutesCache {
+    pub fn new(
+        routes_store: RoutesStoreType,
+        cache: RedisCache,
+        settings: RedisCacheSettings,
+        local_settings: RoutesCacheSettings,
+    ) -> Self {
+        let shared = RedisRoutesStore {
+            cache,
+            settings,
+            routes_store: Box::new(routes_store),
+        };
+
+        Self {
+            local: MokaRoutesCache::new(RoutesStoreType::Redis(shared.clone()), local_settings),
+            shared,
+        }
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        self.local.metrics()
+    }
+
+    pub fn patterns_metrics(&self) -> CacheMetrics {
+        self.local.patterns_metrics()
+    }
+}
+
+#[async_trait::async_trait()]
+impl RoutesCache for RedisRoutesCache {
+    async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
+        self.local.get_route(switch, path).await
+    }
+
+    async fn get_route_patterns(&self, switch: &str, domain: &str) -> Result<Arc<RoutePatterns>> {
+        self.local.get_route_patterns(switch, domain).await
+    }
+
+    async fn invalidate(&self, switch: &str, path: &str) -> Result<()> {
+        //the shared layer first, so that the local one is not reloaded with the old route
+        let shared = self.shared.invalidate(switch, path).await;
+
+        self.local.invalidate(switch, path).await?;
+
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expression_lang::parse;
+
+    use crate::model::route::{ConditionalRouting, RoutingPolicy};
+
+    use super::*;
+
+    #[test]
+    fn should_share_routes_as_json() {
+        let mut route = Route::new(
+            "main".to_string(),
+            "localhost%2fcond".to_string(),
+            Some("https://shop.com".to_string()),
+            Default::default(),
+        );
+
+        route.ttl = Some(u64::MAX as u128 + 1);
+        route.policy = RoutingPolicy::Conditional(vec![ConditionalRouting {
+            key: "firefox".to_string(),
+            condition: parse("ua in (Firefox) and country = us").unwrap(),
+            plan: None,
+        }]);
+
+        let shared: Option<Route> =
+            serde_json::from_str(&serde_json::to_string(&Some(route)).unwrap()).unwrap();
+        let shared = shared.unwrap();
+
+        assert_eq!(shared.ttl, Some(u64::MAX as u128 + 1));
+        assert!(matches!(
+            &shared.policy,
+            RoutingPolicy::Conditional(conditions)
+                if conditions[0].condition.ua.is_some() && conditions[0].condition.country.is_some()
+        ));
+    }
+}
@@ -0,0 +1,25 @@
+use serde_derive::Deserialize;
+
+fn default_key_prefix() -> String {
+    "shortas".to_string()
+}
+
+#[derive(Default, Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct RedisCacheSettings {
+    pub time_to_live_minutes: u64,
+    /// Time to live of missing entries, nothing is written to Redis when 0.
+    #[serde(default)]
+    pub negative_ttl_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct Redis {
+    pub url: String,
+    /// Prefix of the keys, so that several environments can share a server.
+    #[serde(default = "default_key_prefix")]
+    pub key_prefix: String,
+    pub routes_cache: RedisCacheSettings,
+    pub user_settings_cache: RedisCacheSettings,
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+
+use crate::adapters::moka::{
+    settings::UserSettingsCacheSettings, swr_cache::CacheMetrics,
+    user_settings_cache::MokaUserSettingsCache,
+};
+use crate::adapters::UserSettingsStoreType;
+use crate::core::user_settings::UserSettingsCache;
+use crate::core::UserSettingsStore;
+use crate::model::UserSettings;
+
+use super::{redis_cache::RedisCache, settings::RedisCacheSettings};
+
+const KIND: &'static str = "settings";
+
+///
+/// User settings from Redis, loaded from the store on a miss and written back for the other
+/// instances.
+///
+#[derive(Clone)]
+pub struct RedisUserSettingsStore {
+    cache: RedisCache,
+    settings: RedisCacheSettings,
+    user_settings_store: Box<UserSettingsStoreType>,
+}
+
+impl RedisUserSettingsStore {
+    pub async fn invalidate(&self, user_id: &str) -> Result<()> {
+        self.cache.delete(&self.cache.key(KIND, user_id)).await
+    }
+}
+
+#[async_trait::async_trait()]
+impl UserSettingsStore for RedisUserSettingsStore {
+    async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        let key = self.cache.key(KIND, user_id);
+
+        self.cache
+            .get_or_load(&key, &self.settings, || {
+                self.user_settings_store.get_user_settings(user_id)
+            })
+            .await
+    }
+}
+
+///
+/// Moka in front of Redis in front of the user settings store.
+///
+#[derive(Clone)]
+pub struct RedisUserSettingsCache {
+    local: MokaUserSettingsCache,
+    shared: RedisUserSettingsStore,
+}
+
+impl RedisUserSettingsCache {
+    pub fn new(
+        user_settings_store: UserSettingsStoreType,
+        cache: RedisCache,
+        settings: RedisCacheSettings,
+        local_settings: UserSettingsCacheSettings,
+    ) -> Self {
+        let shared = RedisUserSettingsStore {
+            cache,
+            settings,
+            user_settings_store: Box::new(user_settings_store),
+        };
+
+        Self {
+            local: MokaUserSettingsCache::new(
+                UserSettingsStoreType::Redis(shared.clone()),
+                local_settings,
+            ),
+            shared,
+        }
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        self.local.metrics()
+    }
+}
+
+#[async_trait::async_trait()]
+impl UserSettingsCache for RedisUserSettingsCache {
+    async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        self.local.get_user_settings(user_id).await
+    }
+
+    async fn invalidate(&self, user_id: &str) -> Result<()> {
+        //the shared layer first, so that the local one is not reloaded with the old settings
+        let shared = self.shared.invalidate(user_id).await;
+
+        self.local.invalidate(user_id).await?;
+
+        shared
+    }
+}
@@ -38,6 +38,10 @@ impl<'a> Request for SalvoRequest<'a> {
         &self.request.scheme()
     }
 
+    fn version(&self) -> http::Version {
+        self.request.version()
+    }
+
     fn remote_addr(&self) -> Option<std::net::SocketAddr> {
         self.request.remote_addr().clone().into_std()
     }
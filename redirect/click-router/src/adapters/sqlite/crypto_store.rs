@@ -0,0 +1,36 @@
+use anyhow::Result;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::core::CryptoStore;
+use crate::model::Keycert;
+
+const SELECT_CERTIFICATE: &'static str = "SELECT key, cert FROM certificates WHERE hostname = ?";
+
+#[derive(Clone, Debug)]
+pub struct SqliteCryptoStore {
+    pool: SqlitePool,
+}
+
+impl SqliteCryptoStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn to_entity(&self, row: &SqliteRow) -> Result<Keycert> {
+        Ok(Keycert::new()
+            .key(row.try_get::<Vec<u8>, _>("key")?)
+            .cert(row.try_get::<Vec<u8>, _>("cert")?))
+    }
+}
+
+#[async_trait::async_trait()]
+impl CryptoStore for SqliteCryptoStore {
+    async fn get_certificate(&self, server_name: &str) -> Result<Option<Keycert>> {
+        let row = sqlx::query(SELECT_CERTIFICATE)
+            .bind(server_name.to_ascii_lowercase())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| self.to_entity(&row)).transpose()
+    }
+}
@@ -0,0 +1,31 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use sqlx::{
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+use tracing::info;
+
+use super::settings::Sqlite;
+
+///
+/// Schema of the routes, user settings and certificates tables, shared with the API.
+///
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations/sqlite");
+
+pub async fn connect(settings: &Sqlite) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::from_str(&settings.url)?.create_if_missing(true);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(settings.max_connections)
+        .connect_with(options)
+        .await?;
+
+    MIGRATOR.run(&pool).await?;
+
+    info!("  {} -> {}", "sqlite", settings.url);
+
+    Ok(pool)
+}
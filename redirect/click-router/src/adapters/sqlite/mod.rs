@@ -0,0 +1,8 @@
+mod routes_mapper;
+
+pub mod crypto_store;
+pub mod database;
+pub mod routes_store;
+pub mod user_settings_store;
+
+pub mod settings;
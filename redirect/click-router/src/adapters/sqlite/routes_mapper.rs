@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use sqlx::{sqlite::SqliteRow, Row};
+
+use crate::model::{
+    route::{BlockedReason, DestinationFormat, RouteStatus, RoutingTerminal},
+    Route,
+};
+
+fn to_dest_format(dest_format: &str) -> DestinationFormat {
+    match dest_format.to_ascii_lowercase().as_str() {
+        "native" => DestinationFormat::Native,
+        _ => DestinationFormat::Http,
+    }
+}
+
+fn to_terminal(terminal: &str) -> RoutingTerminal {
+    match terminal.to_ascii_lowercase().as_str() {
+        "internal" => RoutingTerminal::Internal,
+        "middleware" => RoutingTerminal::Middleware,
+        _ => RoutingTerminal::External,
+    }
+}
+
+fn to_status(blocked: bool, blocked_reason: Option<String>) -> RouteStatus {
+    if !blocked {
+        return RouteStatus::Active;
+    }
+
+    RouteStatus::Blocked(blocked_reason.map_or(BlockedReason::Unknown, BlockedReason::Resoned))
+}
+
+pub fn to_entity(row: &SqliteRow) -> Result<Route> {
+    let switch: String = row.try_get("switch")?;
+    let link: String = row.try_get("link")?;
+
+    let code = row
+        .try_get::<Option<i64>, _>("code")?
+        .map(u16::try_from)
+        .transpose()
+        .with_context(|| format!("invalid 'code' of route '{}'", link))?;
+
+    let ttl = row
+        .try_get::<Option<i64>, _>("ttl")?
+        .map(u128::try_from)
+        .transpose()
+        .with_context(|| format!("invalid 'ttl' of route '{}'", link))?;
+
+    let policy = serde_json::from_str(row.try_get("policy")?)
+        .with_context(|| format!("invalid 'policy' of route '{}'", link))?;
+
+    let properties = serde_json::from_str(row.try_get("properties")?)
+        .with_context(|| format!("invalid 'properties' of route '{}'", link))?;
+
+    let mut route = Route::new(switch, link, row.try_get("dest")?, properties);
+
+    route.dest_format = to_dest_format(row.try_get("dest_format")?);
+    route.code = code;
+    route.ttl = ttl;
+    route.status = to_status(row.try_get("blocked")?, row.try_get("blocked_reason")?);
+    route.terminal = to_terminal(row.try_get("terminal")?);
+    route.policy = policy;
+
+    Ok(route)
+}
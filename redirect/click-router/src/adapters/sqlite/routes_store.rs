@@ -0,0 +1,130 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+use super::routes_mapper::to_entity;
+use crate::core::RoutesStore;
+use crate::model::Route;
+
+const SELECT_ROUTE: &'static str = "SELECT * FROM routes WHERE switch = ? AND link = ?";
+const SELECT_PATTERN_ROUTES: &'static str =
+    "SELECT * FROM routes WHERE switch = ? AND substr(link, 1, ?) = ? AND instr(link, '{') > 0";
+
+#[derive(Clone, Debug)]
+pub struct SqliteRoutesStore {
+    pool: SqlitePool,
+}
+
+impl SqliteRoutesStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait()]
+impl RoutesStore for SqliteRoutesStore {
+    async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>> {
+        let row = sqlx::query(SELECT_ROUTE)
+            .bind(switch.to_ascii_lowercase())
+            .bind(path)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(to_entity).transpose()
+    }
+
+    async fn get_pattern_routes(&self, switch: &str, domain: &str) -> Result<Vec<Route>> {
+        let prefix = format!("{}%2f", domain).to_ascii_lowercase();
+
+        let rows = sqlx::query(SELECT_PATTERN_ROUTES)
+            .bind(switch.to_ascii_lowercase())
+            .bind(prefix.len() as i64)
+            .bind(&prefix)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(to_entity).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adapters::sqlite::{database::connect, settings::Sqlite},
+        model::route::{BlockedReason, RouteStatus, RoutingPolicy, RoutingTerminal},
+    };
+
+    use super::*;
+
+    async fn store() -> SqliteRoutesStore {
+        let pool = connect(&Sqlite {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+
+        sqlx::query(
+            r#"INSERT INTO routes (switch, link, dest, code, blocked, terminal, policy, properties)
+            VALUES ('main', 'localhost%2fcond', 'https://shop.com', 302, 1, 'internal',
+                '{"Conditional":[{"key":"firefox","condition":"ua in (Firefox)"}]}',
+                '{"owner_id":"owner","time_zone":"Europe/Berlin"}')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        SqliteRoutesStore::new(pool)
+    }
+
+    #[tokio::test]
+    async fn should_read_routes_with_their_policy_and_properties() {
+        let route = store()
+            .await
+            .get_route("Main", "localhost%2fcond")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(route.dest.as_deref(), Some("https://shop.com"));
+        assert_eq!(route.code, Some(302));
+        assert!(matches!(
+            route.status,
+            RouteStatus::Blocked(BlockedReason::Unknown)
+        ));
+        assert!(matches!(route.terminal, RoutingTerminal::Internal));
+        assert!(
+            matches!(&route.policy, RoutingPolicy::Conditional(conditions) if conditions[0].key == "firefox")
+        );
+        assert_eq!(route.properties.owner_id.as_deref(), Some("owner"));
+        assert_eq!(route.properties.time_zone.as_deref(), Some("Europe/Berlin"));
+    }
+
+    #[tokio::test]
+    async fn should_read_the_pattern_routes_of_a_domain() {
+        let store = store().await;
+
+        sqlx::query(
+            r#"INSERT INTO routes (switch, link, dest) VALUES
+            ('main', 'localhost%2fdocs/{*rest}', 'https://site.com/{rest}'),
+            ('main', 'other%2fdocs/{*rest}', 'https://other.com/{rest}')"#,
+        )
+        .execute(&store.pool)
+        .await
+        .unwrap();
+
+        let routes = store.get_pattern_routes("main", "LocalHost").await.unwrap();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].link, "localhost%2fdocs/{*rest}");
+    }
+
+    #[tokio::test]
+    async fn should_not_find_missing_routes() {
+        assert!(store()
+            .await
+            .get_route("main", "localhost%2fother")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,14 @@
+use serde_derive::Deserialize;
+
+fn default_max_connections() -> u32 {
+    5
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct Sqlite {
+    /// Database of the stores, e.g. `sqlite://shortas.db`, created and migrated on start.
+    pub url: String,
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+}
@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::core::UserSettingsStore;
+use crate::model::{ActiveStatus, UserSettings};
+
+const BLOCKED: &'static str = "blocked";
+
+const SELECT_USER_SETTINGS: &'static str = "SELECT * FROM user_settings WHERE user_id = ?";
+
+#[derive(Clone, Debug)]
+pub struct SqliteUserSettingsStore {
+    pool: SqlitePool,
+}
+
+impl SqliteUserSettingsStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn to_entity(&self, row: &SqliteRow) -> Result<UserSettings> {
+        let user_id: String = row.try_get("user_id")?;
+
+        let active_status = match row.try_get::<&str, _>("status")? {
+            BLOCKED => ActiveStatus::Blocked,
+            _ => ActiveStatus::Active,
+        };
+
+        let list = |column: &str| -> Result<Vec<String>> {
+            serde_json::from_str(row.try_get(column)?)
+                .with_context(|| format!("invalid '{}' of user '{}'", column, user_id))
+        };
+
+        Ok(UserSettings::new(
+            user_id.clone(),
+            row.try_get("user_email")?,
+            row.try_get("api_key")?,
+            active_status,
+            row.try_get("debug")?,
+            row.try_get("overflow")?,
+            list("skip")?,
+            list("request_params")?,
+            list("destination_params")?,
+        ))
+    }
+}
+
+#[async_trait::async_trait()]
+impl UserSettingsStore for SqliteUserSettingsStore {
+    async fn get_user_settings(&self, user_id: &str) -> Result<Option<UserSettings>> {
+        let row = sqlx::query(SELECT_USER_SETTINGS)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| self.to_entity(&row)).transpose()
+    }
+}
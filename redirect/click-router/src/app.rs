@@ -1,5 +1,6 @@
 use aws_config::SdkConfig;
-use tracing::info;
+use link_keys::LinkKeys;
+use tracing::{error, info};
 
 use crate::{
     adapters::{
@@ -10,24 +11,54 @@ use crate::{
             },
             settings::AWS,
         },
-        fluvio::hit_registrar::FluvioHitRegistrar,
+        file::{
+            change_subscriber::FileChangeSubscriber,
+            hit_registrar::{FileHitRegistrar, StdoutHitRegistrar},
+            stores::FileStores,
+        },
+        fluvio::{change_subscriber::FluvioChangeSubscriber, hit_registrar::FluvioHitRegistrar},
         geo_ip::geo_ip_location_detector::GeoIPLocationDetector,
+        memory::{
+            crypto_store::MemoryCryptoStore, routes_store::MemoryRoutesStore,
+            user_settings_store::MemoryUserSettingsStore,
+        },
         moka::{
-            crypto_cache::MokaCryptoCache, routes_cache::MokaRoutesCache, settings::Moka,
+            crypto_cache::MokaCryptoCache, routes_cache::MokaRoutesCache,
             user_settings_cache::MokaUserSettingsCache,
         },
+        rdkafka::{change_subscriber::KafkaChangeSubscriber, hit_registrar::KafkaHitRegistrar},
+        redis::{
+            redis_cache::RedisCache, routes_cache::RedisRoutesCache,
+            user_settings_cache::RedisUserSettingsCache,
+        },
+        sqlite::{
+            crypto_store::SqliteCryptoStore, database::connect, routes_store::SqliteRoutesStore,
+            user_settings_store::SqliteUserSettingsStore,
+        },
         uaparser::user_agent_detector::UAParserUserAgentDetector,
-        CryptoCacheType, CryptoStoreType, HitRegistrarType, LocationDetectorType, RoutesCacheType,
-        RoutesStoreType, UserAgentDetectorType, UserSettingsCacheType, UserSettingsStoreType,
+        ChangeSubscriberType, CryptoCacheType, CryptoStoreType, HitRegistrarType,
+        LocationDetectorType, RoutesCacheType, RoutesStoreType, UserAgentDetectorType,
+        UserSettingsCacheType, UserSettingsStoreType,
     },
     core::{
+        conversion::ClickRegistry,
+        fallback::Fallback,
         flow_router::FlowRouter,
+        hit_queue::HitQueue,
+        hit_spool::HitSpool,
+        invalidation::{ChangeSubscriber, Invalidator},
+        metrics::{Metrics, StateCollector},
         modules::{
-            conditional::ConditionalModule, not_found::NotFoundModule,
-            redirect_only::RedirectOnlyModule, root::RootModule, FlowModules,
+            click_id::ClickIdModule, conditional::ConditionalModule, conversion::ConversionModule,
+            methods::MethodsModule, not_found::NotFoundModule, preview::PreviewModule,
+            qr::QrModule, redirect_only::RedirectOnlyModule, root::RootModule,
+            visitor::VisitorModule, FlowModules,
         },
+        preview::PreviewPage,
+        qr::QrRenderer,
+        visitor::VisitorIds,
     },
-    settings::Settings,
+    settings::{ChangeSubscriberKind, HitRegistrarKind, Settings},
 };
 
 // #[derive(TypedBuilder)]
@@ -42,6 +73,9 @@ pub struct AppBuilder {
     user_agent_detector: Option<UserAgentDetectorType>,
     location_detector: Option<LocationDetectorType>,
     hit_registrar: Option<HitRegistrarType>,
+    change_subscriber: Option<ChangeSubscriberType>,
+    redis_cache: Option<RedisCache>,
+    metrics: Metrics,
 }
 
 impl AppBuilder {
@@ -70,47 +104,112 @@ impl AppBuilder {
     ) {
         let aws_config = &self.load_aws_config(settings.clone()).await;
 
-        let routes_store =
-            DynamoRoutesStore::new(&aws_config, settings.dynamo.routes_table.clone());
+        let routes_store = DynamoRoutesStore::new(
+            &aws_config,
+            settings.dynamo.routes_table.clone(),
+            settings.dynamo.routes_patterns_index.clone(),
+            self.metrics.clone(),
+        );
 
-        let crypto_store =
-            DynamoCryptoStore::new(&aws_config, settings.dynamo.encryption_table.clone());
+        let crypto_store = DynamoCryptoStore::new(
+            &aws_config,
+            settings.dynamo.encryption_table.clone(),
+            self.metrics.clone(),
+        );
 
-        let user_settings_store =
-            DynamoUserSettingsStore::new(&aws_config, settings.dynamo.user_settings_table.clone());
+        let user_settings_store = DynamoUserSettingsStore::new(
+            &aws_config,
+            settings.dynamo.user_settings_table.clone(),
+            self.metrics.clone(),
+        );
 
         (routes_store, crypto_store, user_settings_store)
     }
 
-    async fn init_moka_cache_with_dynamo_stores(
+    fn init_moka_caches(
         &self,
-        moka_settings: &Moka,
-        aws_settings: &AWS,
+        routes_store: RoutesStoreType,
+        crypto_store: CryptoStoreType,
+        user_settings_store: UserSettingsStoreType,
     ) -> (RoutesCacheType, CryptoCacheType, UserSettingsCacheType) {
-        let (routes_store, crypto_store, user_settings_store) =
-            self.init_dynamo_stores(&aws_settings).await;
-
-        let routes_cache = RoutesCacheType::Moka(MokaRoutesCache::new(
-            RoutesStoreType::Dynamo(routes_store),
-            moka_settings.routes_cache.clone(),
-        ));
+        let moka_settings = &self.settings.moka;
 
         let crypto_cache = CryptoCacheType::Moka(MokaCryptoCache::new(
-            CryptoStoreType::Dynamo(crypto_store),
+            crypto_store,
             moka_settings.crypto_cache.clone(),
         ));
 
+        if let Some(redis_cache) = self.redis_cache.clone() {
+            let redis_settings = self.settings.redis.clone().unwrap();
+
+            let routes_cache = RoutesCacheType::Redis(RedisRoutesCache::new(
+                routes_store,
+                redis_cache.clone(),
+                redis_settings.routes_cache,
+                moka_settings.routes_cache.clone(),
+            ));
+
+            let user_settings_cache = UserSettingsCacheType::Redis(RedisUserSettingsCache::new(
+                user_settings_store,
+                redis_cache,
+                redis_settings.user_settings_cache,
+                moka_settings.user_settings_cache.clone(),
+            ));
+
+            return (routes_cache, crypto_cache, user_settings_cache);
+        }
+
+        let routes_cache = RoutesCacheType::Moka(MokaRoutesCache::new(
+            routes_store,
+            moka_settings.routes_cache.clone(),
+        ));
+
         let user_settings_cache = UserSettingsCacheType::Moka(MokaUserSettingsCache::new(
-            UserSettingsStoreType::Dynamo(user_settings_store),
+            user_settings_store,
             moka_settings.user_settings_cache.clone(),
         ));
 
         (routes_cache, crypto_cache, user_settings_cache)
     }
 
+    async fn init_moka_cache_with_dynamo_stores(
+        &self,
+        aws_settings: &AWS,
+    ) -> (RoutesCacheType, CryptoCacheType, UserSettingsCacheType) {
+        let (routes_store, crypto_store, user_settings_store) =
+            self.init_dynamo_stores(&aws_settings).await;
+
+        self.init_moka_caches(
+            RoutesStoreType::Dynamo(routes_store),
+            CryptoStoreType::Dynamo(crypto_store),
+            UserSettingsStoreType::Dynamo(user_settings_store),
+        )
+    }
+
+    ///
+    /// Shares the routes and user settings between the instances through Redis, behind the
+    /// local caches, and the clicks between their conversions. Set it up before the stores and
+    /// the modules.
+    ///
+    pub async fn with_redis(mut self) -> Self {
+        let redis_settings = self
+            .settings
+            .redis
+            .clone()
+            .expect("No redis settings specified.");
+
+        let redis_cache = RedisCache::connect(&redis_settings)
+            .await
+            .expect("Can not connect to redis.");
+
+        self.redis_cache = Some(redis_cache);
+
+        self
+    }
+
     pub async fn with_dynamo(mut self) -> Self {
         let (routes_cache, crypto_cache, user_settings_cache) = self
-            .init_moka_cache_with_dynamo_stores(&self.settings.moka, &self.settings.aws)
+            .init_moka_cache_with_dynamo_stores(&self.settings.aws)
             .await;
 
         self.crypto_cache = Some(crypto_cache);
@@ -120,6 +219,77 @@ impl AppBuilder {
         self
     }
 
+    pub async fn with_sqlite(mut self) -> Self {
+        let sqlite_settings = self
+            .settings
+            .sqlite
+            .clone()
+            .expect("No sqlite settings specified.");
+
+        let pool = connect(&sqlite_settings)
+            .await
+            .expect("Can not open the sqlite database.");
+
+        let (routes_cache, crypto_cache, user_settings_cache) = self.init_moka_caches(
+            RoutesStoreType::Sqlite(SqliteRoutesStore::new(pool.clone())),
+            CryptoStoreType::Sqlite(SqliteCryptoStore::new(pool.clone())),
+            UserSettingsStoreType::Sqlite(SqliteUserSettingsStore::new(pool)),
+        );
+
+        self.crypto_cache = Some(crypto_cache);
+        self.routes_cache = Some(routes_cache);
+        self.user_settings_cache = Some(user_settings_cache);
+
+        self
+    }
+
+    ///
+    /// Routes, user settings and certificates from the JSON, YAML and TOML files of a directory,
+    /// reloaded when they change.
+    ///
+    pub fn with_file_stores(mut self, path: &str) -> Self {
+        let stores = FileStores::load(path, LinkKeys::from_settings(&self.settings.links))
+            .expect("Can not load the file stores.");
+
+        let (routes_cache, crypto_cache, user_settings_cache) = self.init_moka_caches(
+            RoutesStoreType::File(stores.routes_store()),
+            CryptoStoreType::File(stores.crypto_store()),
+            UserSettingsStoreType::File(stores.user_settings_store()),
+        );
+
+        self.crypto_cache = Some(crypto_cache);
+        self.routes_cache = Some(routes_cache);
+        self.user_settings_cache = Some(user_settings_cache);
+
+        self.change_subscriber = Some(ChangeSubscriberType::File(FileChangeSubscriber::new(
+            stores,
+        )));
+
+        self
+    }
+
+    ///
+    /// Stores kept in memory, changes made to them show up once the cached entries expire.
+    ///
+    pub fn with_memory_stores(
+        mut self,
+        routes_store: MemoryRoutesStore,
+        crypto_store: MemoryCryptoStore,
+        user_settings_store: MemoryUserSettingsStore,
+    ) -> Self {
+        let (routes_cache, crypto_cache, user_settings_cache) = self.init_moka_caches(
+            RoutesStoreType::Memory(routes_store),
+            CryptoStoreType::Memory(crypto_store),
+            UserSettingsStoreType::Memory(user_settings_store),
+        );
+
+        self.crypto_cache = Some(crypto_cache);
+        self.routes_cache = Some(routes_cache);
+        self.user_settings_cache = Some(user_settings_cache);
+
+        self
+    }
+
     pub async fn with_fluvio(mut self) -> Self {
         let hit_registrar = HitRegistrarType::Fluvio(
             FluvioHitRegistrar::new(&self.settings.fluvio.hit_stream).await,
@@ -129,6 +299,99 @@ impl AppBuilder {
 
         self
     }
+
+    pub fn with_kafka(mut self) -> Self {
+        let kafka = self
+            .settings
+            .kafka
+            .clone()
+            .expect("No kafka settings specified.");
+
+        let hit_registrar = HitRegistrarType::Kafka(KafkaHitRegistrar::new(kafka.hit_stream));
+
+        self.hit_registrar = Some(hit_registrar);
+
+        self
+    }
+
+    pub fn with_file_hit_registrar(mut self) -> Self {
+        let hit_registrar = HitRegistrarType::File(
+            FileHitRegistrar::new(&self.settings.hits.file).expect("Failed to open the hits file"),
+        );
+
+        self.hit_registrar = Some(hit_registrar);
+
+        self
+    }
+
+    pub fn with_stdout_hit_registrar(mut self) -> Self {
+        let hit_registrar = HitRegistrarType::Stdout(StdoutHitRegistrar);
+
+        self.hit_registrar = Some(hit_registrar);
+
+        self
+    }
+
+    ///
+    /// Hit registrar of the settings, or the given one when they have none.
+    ///
+    pub async fn with_hit_registrar(self, default: HitRegistrarKind) -> Self {
+        let kind = self.settings.hits.registrar.unwrap_or(default);
+
+        info!("  hit registrar -> {:?}", kind);
+
+        match kind {
+            HitRegistrarKind::Kafka => self.with_kafka(),
+            HitRegistrarKind::Fluvio => self.with_fluvio().await,
+            HitRegistrarKind::File => self.with_file_hit_registrar(),
+            HitRegistrarKind::Stdout => self.with_stdout_hit_registrar(),
+            HitRegistrarKind::None => self.with_none_hit_registrar(),
+        }
+    }
+
+    pub fn with_invalidation(self, default: ChangeSubscriberKind) -> Self {
+        let kind = self.settings.invalidation.subscriber.unwrap_or(default);
+
+        info!("  change subscriber -> {:?}", kind);
+
+        match kind {
+            ChangeSubscriberKind::Kafka => self.with_kafka_invalidation(),
+            ChangeSubscriberKind::Fluvio => self.with_fluvio_invalidation(),
+            ChangeSubscriberKind::None => self.with_none_invalidation(),
+        }
+    }
+
+    pub fn with_fluvio_invalidation(mut self) -> Self {
+        let change_subscriber = ChangeSubscriberType::Fluvio(FluvioChangeSubscriber::new(
+            &self.settings.fluvio.change_stream,
+        ));
+
+        self.change_subscriber = Some(change_subscriber);
+
+        self
+    }
+
+    pub fn with_kafka_invalidation(mut self) -> Self {
+        let kafka = self
+            .settings
+            .kafka
+            .clone()
+            .expect("No kafka settings specified.");
+
+        let change_subscriber =
+            ChangeSubscriberType::Kafka(KafkaChangeSubscriber::new(kafka.change_stream));
+
+        self.change_subscriber = Some(change_subscriber);
+
+        self
+    }
+
+    pub fn with_none_invalidation(mut self) -> Self {
+        self.change_subscriber = None;
+
+        self
+    }
+
     pub fn with_none_hit_registrar(mut self) -> Self {
         let hit_registrar = HitRegistrarType::None();
 
@@ -177,6 +440,21 @@ impl AppBuilder {
             self.settings.redirect.clone(),
         )));
 
+        self.modules
+            .push(FlowModules::Methods(MethodsModule::new()));
+
+        self.modules
+            .push(FlowModules::Qr(QrModule::new(
+                QrRenderer::from_settings(&self.settings.qr)
+                    .expect("Can not load the QR settings."),
+            )));
+
+        self.modules.push(FlowModules::Preview(PreviewModule::new(
+            self.settings.preview.suffix.clone(),
+            PreviewPage::from_settings(&self.settings.preview)
+                .expect("Can not load the preview page."),
+        )));
+
         self.modules
             .push(FlowModules::Conditional(ConditionalModule::new()));
 
@@ -187,24 +465,90 @@ impl AppBuilder {
         self.modules
             .push(FlowModules::RedirectOnly(RedirectOnlyModule::new()));
 
+        self.modules
+            .push(FlowModules::ClickId(ClickIdModule::new()));
+
+        if let Some(ids) = VisitorIds::from_settings(&self.settings.visitors) {
+            self.modules
+                .push(FlowModules::Visitor(VisitorModule::new(ids)));
+        }
+
+        //last, so that only the registered clicks are remembered
+        self.modules
+            .push(FlowModules::Conversion(ConversionModule::new(
+                &self.settings.conversions,
+                ClickRegistry::new(&self.settings.conversions, self.redis_cache.clone()),
+            )));
+
         self
     }
 
     pub fn new(settings: Settings) -> Self {
         Self {
+            metrics: Metrics::new(&settings.admin),
             settings,
             ..Default::default()
         }
     }
 
+    fn spawn_change_subscriber(&self) {
+        let Some(change_subscriber) = self.change_subscriber.clone() else {
+            return;
+        };
+
+        let invalidator = Invalidator::new(
+            self.routes_cache.clone().unwrap(),
+            self.user_settings_cache.clone().unwrap(),
+            self.crypto_cache.clone(),
+        );
+
+        //routes are cached until they expire when the change stream is down
+        tokio::spawn(async move {
+            if let Err(err) = change_subscriber.listen(invalidator).await {
+                error!("change stream stopped: {}", err);
+            }
+        });
+    }
+
     pub fn build(self) -> FlowRouter {
+        self.spawn_change_subscriber();
+
+        //redirects never wait on the hit stream
+        let hit_registrar = match self.hit_registrar.clone().unwrap() {
+            HitRegistrarType::None() => HitRegistrarType::None(),
+            registrar => {
+                let spool = self.settings.hit_spool.as_ref().map(|settings| {
+                    HitSpool::open(settings).expect("Failed to open the hit spool")
+                });
+
+                HitRegistrarType::Queued(HitQueue::new(
+                    registrar,
+                    &self.settings.hit_queue,
+                    spool,
+                    self.metrics.clone(),
+                ))
+            }
+        };
+
+        self.metrics
+            .register(StateCollector::new(
+                self.routes_cache.clone().unwrap(),
+                self.user_settings_cache.clone().unwrap(),
+                self.crypto_cache.clone(),
+                hit_registrar.clone(),
+            ))
+            .expect("Failed to register the cache and hit queue metrics");
+
         FlowRouter::default(
             self.routes_cache.clone().unwrap(),
             self.user_settings_cache.clone().unwrap(),
             self.user_agent_detector.clone().unwrap(),
             self.location_detector.clone().unwrap(),
-            self.hit_registrar.clone().unwrap(),
+            hit_registrar,
             self.modules.clone(),
+            Fallback::from_settings(&self.settings.redirect).expect("Can not load the error page."),
+            LinkKeys::from_settings(&self.settings.links),
+            self.metrics.clone(),
         )
     }
 }
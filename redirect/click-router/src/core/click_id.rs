@@ -0,0 +1,94 @@
+//!
+//! Click ids passed on to the destinations, so that advertisers post their conversions back
+//! with them.
+//!
+use cookie::{time::Duration, Cookie, SameSite};
+
+/// Lifetime of the click id cookies, the default attribution window of the conversions.
+const COOKIE_MAX_AGE_DAYS: i64 = 30;
+
+///
+/// Destination with the click id as its `name` query parameter, replacing the value it had.
+///
+pub fn with_click_id(destination: &str, name: &str, click_id: &str) -> String {
+    let (destination, fragment) = match destination.split_once('#') {
+        Some((destination, fragment)) => (destination, Some(fragment)),
+        None => (destination, None),
+    };
+
+    let (path, query) = destination.split_once('?').unwrap_or((destination, ""));
+    let name = urlencoding::encode(name);
+
+    let mut params: Vec<String> = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .filter(|param| param.split('=').next() != Some(name.as_ref()))
+        .map(str::to_string)
+        .collect();
+
+    params.push(format!("{}={}", name, urlencoding::encode(click_id)));
+
+    let mut destination = path.to_string();
+
+    if !params.is_empty() {
+        destination.push('?');
+        destination.push_str(&params.join("&"));
+    }
+
+    if let Some(fragment) = fragment {
+        destination.push('#');
+        destination.push_str(fragment);
+    }
+
+    destination
+}
+
+///
+/// First-party cookie of the short domain holding the click id.
+///
+pub fn click_id_cookie(name: &str, click_id: &str, secure: bool) -> Cookie<'static> {
+    let mut cookie = Cookie::new(name.to_string(), click_id.to_string());
+
+    cookie.set_path("/");
+    cookie.set_max_age(Duration::days(COOKIE_MAX_AGE_DAYS));
+    cookie.set_same_site(SameSite::Lax);
+    cookie.set_http_only(true);
+    cookie.set_secure(secure);
+
+    cookie
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_append_the_click_id() {
+        assert_eq!(
+            with_click_id("https://shop.com/promo", "sclid", "01J"),
+            "https://shop.com/promo?sclid=01J"
+        );
+        assert_eq!(
+            with_click_id("https://shop.com/promo?utm_source=news", "sclid", "01J"),
+            "https://shop.com/promo?utm_source=news&sclid=01J"
+        );
+        assert_eq!(
+            with_click_id("https://shop.com/promo?sclid=old&a=1#top", "sclid", "01J"),
+            "https://shop.com/promo?a=1&sclid=01J#top"
+        );
+        assert_eq!(
+            with_click_id("https://shop.com/?", "click id", "01J"),
+            "https://shop.com/?click%20id=01J"
+        );
+    }
+
+    #[test]
+    fn should_build_the_click_id_cookie() {
+        let cookie = click_id_cookie("sclid", "01J", true);
+
+        assert_eq!(cookie.value(), "01J");
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.max_age(), Some(Duration::days(30)));
+    }
+}
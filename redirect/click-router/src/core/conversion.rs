@@ -0,0 +1,385 @@
+//!
+//! Conversions attributed to the click they follow, reported by a pixel on the pages of the
+//! destination or by a postback from the servers of the owner, signed with their API key.
+//!
+//! Postbacks carry a signed `ts`, older ones are refused so that a captured postback can't be
+//! replayed. A click converts once per conversion name during the attribution window.
+//!
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use multimap::MultiMap;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    adapters::redis::redis_cache::RedisCache,
+    model::hit::{Event, HitRoute},
+    settings::Conversions,
+};
+
+const KIND: &'static str = "click";
+const CONVERSION_KIND: &'static str = "conversion";
+
+const CLICK_PARAM: &'static str = "click";
+const NAME_PARAM: &'static str = "name";
+const VALUE_PARAM: &'static str = "value";
+const CURRENCY_PARAM: &'static str = "currency";
+/// Unix time of a postback, in seconds.
+const TIMESTAMP_PARAM: &'static str = "ts";
+/// Last query parameter of the postbacks, the hex HMAC-SHA256 of the query before it.
+pub const SIGNATURE_PARAM: &'static str = "sig";
+
+/// Transparent 1x1 GIF answered by the pixel.
+pub const PIXEL: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+pub const PIXEL_CONTENT_TYPE: &'static str = "image/gif";
+
+///
+/// What the conversions of a click are attributed to.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClickRef {
+    pub route: Option<HitRoute>,
+    pub utc: DateTime<Utc>,
+}
+
+///
+/// Clicks registered during the attribution window, shared through Redis so that a conversion
+/// reaching another instance than its click is still attributed.
+///
+#[derive(Clone)]
+pub struct ClickRegistry {
+    local: Cache<String, ClickRef>,
+    //`{click}:{name}` of the conversions registered
+    converted: Cache<String, ()>,
+    shared: Option<RedisCache>,
+    window: Duration,
+}
+
+impl ClickRegistry {
+    pub fn new(settings: &Conversions, shared: Option<RedisCache>) -> Self {
+        let window = Duration::from_secs(settings.attribution_days * 24 * 60 * 60);
+
+        Self {
+            local: Cache::builder()
+                .max_capacity(settings.capacity)
+                .time_to_live(window)
+                .build(),
+            converted: Cache::builder()
+                .max_capacity(settings.capacity)
+                .time_to_live(window)
+                .build(),
+            shared,
+            window,
+        }
+    }
+
+    pub async fn remember(&self, click_id: &str, click: ClickRef) {
+        if let Some(shared) = &self.shared {
+            let key = shared.key(KIND, click_id);
+
+            if let Err(error) = shared.set(&key, &Some(click.clone()), self.window).await {
+                warn!("redis write of '{}' failed: {}", key, error);
+            }
+        }
+
+        self.local.insert(click_id.to_string(), click).await;
+    }
+
+    ///
+    /// Click of a conversion, while still in the attribution window.
+    ///
+    pub async fn find(&self, click_id: &str) -> Option<ClickRef> {
+        let click = match self.local.get(click_id).await {
+            Some(click) => Some(click),
+            None => self.find_shared(click_id).await,
+        }?;
+
+        let age = (Utc::now() - click.utc).to_std().unwrap_or_default();
+
+        (age <= self.window).then_some(click)
+    }
+
+    ///
+    /// Whether the conversion is the first of its click with this name, the others are dropped
+    /// during the attribution window.
+    ///
+    /// Fails when Redis is unavailable, the conversion may have been registered by another
+    /// instance and is neither registered nor remembered here.
+    ///
+    pub async fn first_conversion(&self, conversion: &Conversion) -> Result<bool> {
+        let id = format!(
+            "{}:{}",
+            conversion.click,
+            conversion.name.as_deref().unwrap_or_default()
+        );
+
+        let first = self
+            .converted
+            .entry(id.clone())
+            .or_insert(())
+            .await
+            .is_fresh();
+
+        let Some(shared) = self.shared.as_ref().filter(|_| first) else {
+            return Ok(first);
+        };
+
+        let key = shared.key(CONVERSION_KIND, &id);
+
+        match shared.set_if_absent(&key, self.window).await {
+            Ok(first) => Ok(first),
+            Err(error) => {
+                //the retry of the conversion must not be dropped as a duplicate
+                self.converted.invalidate(&id).await;
+
+                Err(error.context(format!("redis write of '{}' failed", key)))
+            }
+        }
+    }
+
+    async fn find_shared(&self, click_id: &str) -> Option<ClickRef> {
+        let shared = self.shared.as_ref()?;
+        let key = shared.key(KIND, click_id);
+
+        match shared.get::<ClickRef>(&key).await {
+            Ok(click) => click.flatten(),
+            Err(error) => {
+                warn!("redis read of '{}' failed: {}", key, error);
+                None
+            }
+        }
+    }
+}
+
+///
+/// Conversion reported by the query of a pixel or postback, e.g.
+/// `?click={id}&name=purchase&value=49.90&currency=EUR&ts=1767225600`.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conversion {
+    pub click: String,
+    pub name: Option<String>,
+    pub value: Option<f64>,
+    pub currency: Option<String>,
+    pub ts: Option<DateTime<Utc>>,
+}
+
+impl Conversion {
+    ///
+    /// Conversion of the query, none without a click or with a value or a `ts` that is not a number.
+    ///
+    pub fn parse(queries: &MultiMap<String, String>) -> Option<Self> {
+        let click = queries.get(CLICK_PARAM).filter(|click| !click.is_empty())?;
+
+        let value = match queries.get(VALUE_PARAM).map(|value| value.parse::<f64>()) {
+            Some(Ok(value)) if value.is_finite() => Some(value),
+            Some(_) => return None,
+            None => None,
+        };
+
+        let ts = match queries.get(TIMESTAMP_PARAM).map(|ts| ts.parse::<i64>()) {
+            Some(Ok(ts)) => Some(DateTime::from_timestamp(ts, 0)?),
+            Some(Err(_)) => return None,
+            None => None,
+        };
+
+        Some(Self {
+            click: click.clone(),
+            name: queries.get(NAME_PARAM).cloned(),
+            value,
+            currency: queries
+                .get(CURRENCY_PARAM)
+                .map(|currency| currency.to_ascii_uppercase()),
+            ts,
+        })
+    }
+
+    ///
+    /// Whether the `ts` of the conversion is within `max_age` of `utc`, either way to allow for
+    /// clock skew. A conversion without `ts` is never fresh.
+    ///
+    pub fn is_fresh(&self, utc: DateTime<Utc>, max_age: Duration) -> bool {
+        self.ts
+            .is_some_and(|ts| (utc - ts).abs().to_std().is_ok_and(|age| age <= max_age))
+    }
+
+    pub fn to_event(&self, signed: bool) -> Event {
+        Event {
+            click: self.click.clone(),
+            name: self.name.clone(),
+            value: self.value,
+            currency: self.currency.clone(),
+            signed,
+        }
+    }
+}
+
+///
+/// Signature of a postback query, appended to it as the last `sig` parameter.
+///
+pub fn sign(query: &str, api_key: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, api_key.as_bytes());
+
+    hmac::sign(&key, query.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+///
+/// Whether the raw query ends with the signature of what precedes it.
+///
+pub fn verify_signature(query: &str, api_key: &str) -> bool {
+    let Some((signed, signature)) = query.rsplit_once(&format!("&{}=", SIGNATURE_PARAM)) else {
+        return false;
+    };
+
+    let Some(signature) = decode_hex(signature) else {
+        return false;
+    };
+
+    let key = hmac::Key::new(hmac::HMAC_SHA256, api_key.as_bytes());
+
+    hmac::verify(&key, signed.as_bytes(), &signature).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration as ChronoDuration;
+
+    use super::*;
+
+    fn queries(query: &str) -> MultiMap<String, String> {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_conversions() {
+        assert_eq!(
+            Conversion::parse(&queries("click=01J&name=purchase&value=49.90&currency=eur")),
+            Some(Conversion {
+                click: "01J".to_string(),
+                name: Some("purchase".to_string()),
+                value: Some(49.90),
+                currency: Some("EUR".to_string()),
+                ts: None,
+            })
+        );
+
+        assert_eq!(
+            Conversion::parse(&queries("click=01J&ts=1767225600")).and_then(|c| c.ts),
+            DateTime::from_timestamp(1_767_225_600, 0)
+        );
+
+        assert_eq!(Conversion::parse(&queries("name=purchase")), None);
+        assert_eq!(Conversion::parse(&queries("click=01J&value=much")), None);
+        assert_eq!(Conversion::parse(&queries("click=01J&value=NaN")), None);
+        assert_eq!(Conversion::parse(&queries("click=01J&ts=today")), None);
+    }
+
+    #[test]
+    fn should_only_accept_recent_timestamps() {
+        let now = Utc::now();
+        let max_age = Duration::from_secs(300);
+        let conversion = |query: &str| Conversion::parse(&queries(query)).unwrap();
+
+        let recent = format!(
+            "click=01J&ts={}",
+            (now - ChronoDuration::seconds(60)).timestamp()
+        );
+        let skewed = format!(
+            "click=01J&ts={}",
+            (now + ChronoDuration::seconds(60)).timestamp()
+        );
+        let old = format!(
+            "click=01J&ts={}",
+            (now - ChronoDuration::hours(1)).timestamp()
+        );
+
+        assert!(conversion(&recent).is_fresh(now, max_age));
+        assert!(conversion(&skewed).is_fresh(now, max_age));
+        assert!(!conversion(&old).is_fresh(now, max_age));
+        assert!(!conversion("click=01J").is_fresh(now, max_age));
+    }
+
+    #[test]
+    fn should_verify_signed_postbacks() {
+        let query = "click=01J&name=purchase&value=49.90";
+        let signed = format!("{}&sig={}", query, sign(query, "key"));
+
+        assert!(verify_signature(&signed, "key"));
+        assert!(!verify_signature(&signed, "other key"));
+        assert!(!verify_signature(&signed.replace("49.90", "4990"), "key"));
+        assert!(!verify_signature(query, "key"));
+        assert!(!verify_signature(&format!("{}&sig=zz", query), "key"));
+    }
+
+    #[tokio::test]
+    async fn should_only_find_clicks_of_the_attribution_window() {
+        let clicks = ClickRegistry::new(
+            &Conversions {
+                attribution_days: 1,
+                ..Default::default()
+            },
+            None,
+        );
+
+        let click = |utc| ClickRef { route: None, utc };
+
+        clicks.remember("recent", click(Utc::now())).await;
+        clicks
+            .remember("old", click(Utc::now() - ChronoDuration::days(2)))
+            .await;
+
+        assert!(clicks.find("recent").await.is_some());
+        assert!(clicks.find("old").await.is_none());
+        assert!(clicks.find("missing").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_convert_a_click_once_per_name() {
+        let clicks = ClickRegistry::new(&Conversions::default(), None);
+        let conversion = |query: &str| Conversion::parse(&queries(query)).unwrap();
+
+        assert!(clicks
+            .first_conversion(&conversion("click=01J&name=purchase"))
+            .await
+            .unwrap());
+        assert!(!clicks
+            .first_conversion(&conversion("click=01J&name=purchase&value=5"))
+            .await
+            .unwrap());
+        assert!(clicks
+            .first_conversion(&conversion("click=01J&name=signup"))
+            .await
+            .unwrap());
+        assert!(clicks
+            .first_conversion(&conversion("click=01K&name=purchase"))
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,305 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use super::user_agent::{Device, UserAgent, OS};
+
+///
+/// Normalized class of the client device, on top of the `UserAgentDetector` families.
+///
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceClass {
+    Mobile,
+    Tablet,
+    Desktop,
+    TV,
+    Bot,
+    Other,
+}
+
+/// Words of crawler user agents, matched whole so that devices like "CUBOT X30" are not bots.
+const BOT_WORDS: [&'static str; 26] = [
+    "bot",
+    "crawler",
+    "crawl",
+    "spider",
+    "slurp",
+    "headless",
+    "headlesschrome",
+    "lighthouse",
+    "googlebot",
+    "bingbot",
+    "yandexbot",
+    "duckduckbot",
+    "baiduspider",
+    "applebot",
+    "ahrefsbot",
+    "semrushbot",
+    "mj12bot",
+    "dotbot",
+    "petalbot",
+    "gptbot",
+    "facebookexternalhit",
+    "twitterbot",
+    "linkedinbot",
+    "slackbot",
+    "discordbot",
+    "telegrambot",
+];
+
+const TV_MARKERS: [&'static str; 10] = [
+    "smart-tv",
+    "smarttv",
+    "googletv",
+    "android tv",
+    "appletv",
+    "hbbtv",
+    "roku",
+    "crkey",
+    "aftb",
+    "netcast",
+];
+
+const TABLET_MARKERS: [&'static str; 5] = ["ipad", "tablet", "kindle", "silk", "playbook"];
+
+const MOBILE_MARKERS: [&'static str; 5] = ["mobi", "iphone", "ipod", "phone", "opera mini"];
+
+const DESKTOP_OS: [&'static str; 8] = [
+    "windows",
+    "mac os x",
+    "linux",
+    "ubuntu",
+    "chrome os",
+    "fedora",
+    "debian",
+    "freebsd",
+];
+
+fn contains_any(value: &str, markers: &[&str]) -> bool {
+    markers.iter().any(|marker| value.contains(marker))
+}
+
+fn has_any_word(value: &str, words: &[&str]) -> bool {
+    value
+        .split(|ch: char| !ch.is_ascii_alphanumeric())
+        .any(|word| words.contains(&word))
+}
+
+impl DeviceClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceClass::Mobile => "mobile",
+            DeviceClass::Tablet => "tablet",
+            DeviceClass::Desktop => "desktop",
+            DeviceClass::TV => "tv",
+            DeviceClass::Bot => "bot",
+            DeviceClass::Other => "other",
+        }
+    }
+
+    ///
+    /// Classifies a client out of the raw user agent string and the detected families.
+    /// Checks go from the most to the least specific: bots, TVs, tablets, phones, desktops.
+    ///
+    pub fn classify(user_agent: &str, device: &Device, os: &OS, ua: &UserAgent) -> Self {
+        let raw = user_agent.to_lowercase();
+        let device_family = device.family.to_lowercase();
+        let os_family = os.family.to_lowercase();
+        let ua_family = ua.family.to_lowercase();
+
+        //uaparser reports crawlers with the "Spider" device family
+        if device_family == "spider" || has_any_word(&ua_family, &BOT_WORDS) {
+            return DeviceClass::Bot;
+        }
+
+        if has_any_word(&raw, &BOT_WORDS) {
+            return DeviceClass::Bot;
+        }
+
+        if os_family == "tvos" || contains_any(&raw, &TV_MARKERS) || raw.contains(" tv ") {
+            return DeviceClass::TV;
+        }
+
+        if contains_any(&device_family, &TABLET_MARKERS) || contains_any(&raw, &TABLET_MARKERS) {
+            return DeviceClass::Tablet;
+        }
+
+        //android tablets do not advertise "mobile"
+        if os_family == "android" && !raw.contains("mobile") {
+            return DeviceClass::Tablet;
+        }
+
+        if contains_any(&device_family, &MOBILE_MARKERS)
+            || contains_any(&raw, &MOBILE_MARKERS)
+            || os_family == "android"
+            || os_family == "ios"
+        {
+            return DeviceClass::Mobile;
+        }
+
+        if DESKTOP_OS
+            .iter()
+            .any(|family| os_family.starts_with(family))
+        {
+            return DeviceClass::Desktop;
+        }
+
+        DeviceClass::Other
+    }
+}
+
+impl FromStr for DeviceClass {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "mobile" => Ok(DeviceClass::Mobile),
+            "tablet" => Ok(DeviceClass::Tablet),
+            "desktop" => Ok(DeviceClass::Desktop),
+            "tv" => Ok(DeviceClass::TV),
+            "bot" => Ok(DeviceClass::Bot),
+            "other" => Ok(DeviceClass::Other),
+            _ => Err(anyhow!("unknown device class '{}'", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expression_lang::DEVICE_CLASSES;
+
+    use super::*;
+
+    fn classify(user_agent: &str, device: &str, os: &str, ua: &str) -> DeviceClass {
+        DeviceClass::classify(
+            user_agent,
+            &Device {
+                family: device.to_string(),
+                ..Default::default()
+            },
+            &OS {
+                family: os.to_string(),
+                ..Default::default()
+            },
+            &UserAgent {
+                family: ua.to_string(),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn should_classify_phones_and_tablets() {
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+                "iPhone",
+                "iOS",
+                "Mobile Safari"
+            ),
+            DeviceClass::Mobile
+        );
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (Linux; Android 14; SM-S918B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+                "Samsung SM-S918B",
+                "Android",
+                "Chrome Mobile"
+            ),
+            DeviceClass::Mobile
+        );
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (iPad; CPU OS 16_6 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.6 Mobile/15E148 Safari/604.1",
+                "iPad",
+                "iOS",
+                "Mobile Safari"
+            ),
+            DeviceClass::Tablet
+        );
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (Linux; Android 13; SM-X700) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+                "Samsung SM-X700",
+                "Android",
+                "Chrome"
+            ),
+            DeviceClass::Tablet
+        );
+    }
+
+    #[test]
+    fn should_classify_desktops_tvs_and_bots() {
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:126.0) Gecko/20100101 Firefox/126.0",
+                "Other",
+                "Windows",
+                "Firefox"
+            ),
+            DeviceClass::Desktop
+        );
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (SMART-TV; Linux; Tizen 6.0) AppleWebKit/538.1 (KHTML, like Gecko) Version/6.0 TV Safari/538.1",
+                "Samsung SMART-TV",
+                "Tizen",
+                "Safari"
+            ),
+            DeviceClass::TV
+        );
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+                "Spider",
+                "Other",
+                "Googlebot"
+            ),
+            DeviceClass::Bot
+        );
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)",
+                "Other",
+                "Other",
+                "Other"
+            ),
+            DeviceClass::Bot
+        );
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.0.0 Safari/537.36",
+                "Other",
+                "Linux",
+                "HeadlessChrome"
+            ),
+            DeviceClass::Bot
+        );
+        assert_eq!(
+            classify("curl/8.4.0", "Other", "Other", "curl"),
+            DeviceClass::Other
+        );
+    }
+
+    #[test]
+    fn should_not_take_bot_in_device_names_for_crawlers() {
+        assert_eq!(
+            classify(
+                "Mozilla/5.0 (Linux; Android 13; CUBOT X30) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+                "CUBOT X30",
+                "Android",
+                "Chrome Mobile"
+            ),
+            DeviceClass::Mobile
+        );
+    }
+
+    #[test]
+    fn should_round_trip_names() {
+        for name in DEVICE_CLASSES {
+            assert_eq!(name.parse::<DeviceClass>().unwrap().as_str(), name);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//!
+//! Trace of the routing decisions of a request, returned as JSON instead of the
+//! regular result when `x_debug_explain` is sent to a route with `allow_debug`.
+//!
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::adapters::RequestType;
+
+use super::{
+    device_class::DeviceClass,
+    flow_router::{FlowStep, Request},
+    location::Location,
+    user_agent::{Device, UserAgent, OS},
+};
+
+const DEBUG_EXPLAIN_PARAM: &'static str = "x_debug_explain";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExplainStep {
+    pub step: String,
+    /// Module that stopped the step, when any did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub break_by: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ExplainClient {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub langs: Vec<String>,
+    pub ua: Option<UserAgent>,
+    pub os: Option<OS>,
+    pub device: Option<Device>,
+    pub device_class: Option<DeviceClass>,
+    pub location: Option<Location>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExplainCondition {
+    pub key: String,
+    pub condition: String,
+    pub matched: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Explain {
+    pub id: String,
+    pub utc: DateTime<Utc>,
+    pub time_zone: Option<String>,
+    pub steps: Vec<ExplainStep>,
+    pub client: ExplainClient,
+    pub conditions: Vec<ExplainCondition>,
+    pub switch: Option<String>,
+    pub destination: Option<String>,
+    pub result: Option<String>,
+    /// Explained requests never register hits, so that debugging does not skew statistics.
+    pub hit_registered: bool,
+}
+
+impl Explain {
+    pub fn new(id: &str, utc: DateTime<Utc>) -> Self {
+        Self {
+            id: id.to_string(),
+            utc,
+            time_zone: None,
+            steps: Vec::new(),
+            client: ExplainClient::default(),
+            conditions: Vec::new(),
+            switch: None,
+            destination: None,
+            result: None,
+            hit_registered: false,
+        }
+    }
+
+    pub fn visit(&mut self, step: &FlowStep) {
+        self.steps.push(ExplainStep {
+            step: step.to_string(),
+            break_by: None,
+        });
+    }
+
+    ///
+    /// Marks the last visited step as stopped by the given module.
+    ///
+    pub fn break_by(&mut self, module: &'static str) {
+        if let Some(step) = self.steps.last_mut() {
+            step.break_by = Some(module);
+        }
+    }
+}
+
+///
+/// Checks the `x_debug_explain` query parameter or header of the request.
+///
+pub fn is_explain_requested(request: &RequestType) -> bool {
+    request.queries().get(DEBUG_EXPLAIN_PARAM).is_some()
+        || request.headers().contains_key(DEBUG_EXPLAIN_PARAM)
+}
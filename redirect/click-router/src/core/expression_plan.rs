@@ -0,0 +1,994 @@
+//!
+//! Conditions compiled into an evaluation plan once per route, so that the hot path
+//! does not lowercase strings, parse dates or walk the serde model on every click.
+//!
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use chrono::{prelude::*, DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use expression_lang::{validate, DATETIME_FORMAT, DATE_FORMAT};
+use rand::{rng, Rng};
+
+use crate::{
+    core::{
+        device_class::DeviceClass,
+        flow_router::FlowRouterContext,
+        location::Coordinates,
+        version::Version,
+    },
+    model::{
+        expression::{
+            Asn, City, Continent, Country, Date, DateTime as DateTimeExpr, DayOfMonth, DayOfWeek,
+            DefaultOperator, Device, DeviceClass as DeviceClassExpr, Expression, Hour, Lang,
+            Location, Month, Org, Region, TimeZone, Version as VersionExpr, OS, RND, UA,
+        },
+        route::{ConditionalRouting, RouteProperties, RoutingPolicy},
+        Route,
+    },
+};
+
+const RND_RANGE: RangeInclusive<u32> = 0..=100;
+const DAY_OF_WEEK_RANGE: RangeInclusive<u32> = 0..=6;
+const DAY_OF_MONTH_RANGE: RangeInclusive<u32> = 1..=31;
+const MONTH_RANGE: RangeInclusive<u32> = 1..=12;
+const HOUR_RANGE: RangeInclusive<u32> = 0..=23;
+
+const VISITOR_TIME_ZONE: &'static str = "visitor";
+
+///
+/// Detectors a plan needs to be loaded before it is evaluated.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Requirements {
+    pub ua: bool,
+    pub os: bool,
+    pub device: bool,
+    pub country: bool,
+    pub location: bool,
+    pub device_class: bool,
+    pub time: bool,
+}
+
+impl Requirements {
+    pub fn from_expression(expression: &Expression) -> Self {
+        Self {
+            ua: expression.needs_ua(),
+            os: expression.needs_os(),
+            device: expression.needs_device(),
+            country: expression.needs_country(),
+            location: expression.needs_location(),
+            device_class: expression.needs_device_class(),
+            time: expression.needs_time(),
+        }
+    }
+
+    ///
+    /// Requirements of a conditional routing, taken from its plan when it was compiled.
+    ///
+    pub fn of(routing: &ConditionalRouting) -> Self {
+        match &routing.plan {
+            Some(plan) => plan.requirements,
+            None => Self::from_expression(&routing.condition),
+        }
+    }
+
+    pub fn merge(&mut self, other: &Requirements) {
+        self.ua |= other.ua;
+        self.os |= other.os;
+        self.device |= other.device;
+        self.country |= other.country;
+        self.location |= other.location;
+        self.device_class |= other.device_class;
+        self.time |= other.time;
+    }
+}
+
+///
+/// Time zone the date and time conditions of a route are evaluated in.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ScheduleZone {
+    #[default]
+    Utc,
+    Fixed(Tz),
+    /// GeoIP time zone of the client, UTC when it is unknown.
+    Visitor,
+}
+
+impl ScheduleZone {
+    pub fn parse(time_zone: Option<&str>) -> Result<Self> {
+        match time_zone {
+            None => Ok(ScheduleZone::Utc),
+            Some(name) if name.eq_ignore_ascii_case(VISITOR_TIME_ZONE) => Ok(ScheduleZone::Visitor),
+            Some(name) => name
+                .parse::<Tz>()
+                .map(ScheduleZone::Fixed)
+                .map_err(|_| anyhow!("unknown time zone '{}'", name)),
+        }
+    }
+
+    ///
+    /// Time zone of a route, falling back to UTC when it is invalid (rejected by `compile_route`).
+    ///
+    pub fn of(properties: &RouteProperties) -> Self {
+        Self::parse(properties.time_zone.as_deref()).unwrap_or_default()
+    }
+
+    pub fn needs_location(&self) -> bool {
+        matches!(self, ScheduleZone::Visitor)
+    }
+
+    pub fn local_time(
+        &self,
+        utc: &DateTime<Utc>,
+        visitor_time_zone: Option<&str>,
+    ) -> NaiveDateTime {
+        let tz = match self {
+            ScheduleZone::Utc => return utc.naive_utc(),
+            ScheduleZone::Fixed(tz) => *tz,
+            ScheduleZone::Visitor => match visitor_time_zone.and_then(|name| name.parse().ok()) {
+                Some(tz) => tz,
+                None => return utc.naive_utc(),
+            },
+        };
+
+        utc.with_timezone(&tz).naive_local()
+    }
+}
+
+///
+/// Request values the plans are evaluated against, lowercased once per request.
+///
+#[derive(Clone, Debug, Default)]
+pub struct ClientFacts {
+    pub ua: Option<String>,
+    pub os: Option<String>,
+    pub device: Option<String>,
+    pub country: Option<String>,
+    pub lang: Option<String>,
+    pub continent: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub time_zone: Option<String>,
+    pub asn: Option<u32>,
+    pub org: Option<String>,
+    pub coordinates: Option<Coordinates>,
+    pub os_version: Option<Version>,
+    pub ua_version: Option<Version>,
+    pub device_class: Option<DeviceClass>,
+    pub datetime: NaiveDateTime,
+    pub date: NaiveDate,
+    pub day_of_week: u32,
+    pub day_of_month: u32,
+    pub month: u32,
+    pub hour: u32,
+}
+
+impl ClientFacts {
+    pub fn new(utc: &DateTime<Utc>) -> Self {
+        Self::at(utc.naive_utc())
+    }
+
+    ///
+    /// Facts of a request made at the given local date and time.
+    ///
+    pub fn at(local: NaiveDateTime) -> Self {
+        Self {
+            datetime: local,
+            date: local.date(),
+            day_of_week: local.weekday().num_days_from_sunday(),
+            day_of_month: local.day(),
+            month: local.month(),
+            hour: local.hour(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_context(context: &FlowRouterContext, zone: &ScheduleZone) -> Self {
+        let visitor_time_zone = context
+            .client_location
+            .value()
+            .as_ref()
+            .and_then(|location| location.time_zone.as_deref());
+
+        let mut facts = Self::at(zone.local_time(&context.utc, visitor_time_zone));
+
+        facts.ua = context
+            .client_ua
+            .value()
+            .as_ref()
+            .map(|ua| ua.family.to_lowercase());
+        facts.os = context
+            .client_os
+            .value()
+            .as_ref()
+            .map(|os| os.family.to_lowercase());
+        facts.device = context
+            .client_device
+            .value()
+            .as_ref()
+            .map(|device| device.family.to_lowercase());
+        facts.country = context
+            .client_country
+            .value()
+            .as_ref()
+            .map(|country| country.iso_code.to_lowercase());
+        facts.lang = context
+            .client_langs
+            .as_ref()
+            .and_then(|langs| langs.first())
+            .and_then(|lang| lang.name.get(..2))
+            .map(|lang| lang.to_lowercase());
+
+        facts.os_version = context.client_os.value().as_ref().and_then(|os| {
+            Version::from_parts(
+                os.major.as_deref(),
+                os.minor.as_deref(),
+                os.patch.as_deref(),
+            )
+        });
+        facts.ua_version = context.client_ua.value().as_ref().and_then(|ua| {
+            Version::from_parts(
+                ua.major.as_deref(),
+                ua.minor.as_deref(),
+                ua.patch.as_deref(),
+            )
+        });
+        facts.device_class = *context.client_device_class.value();
+
+        if let Some(location) = context.client_location.value() {
+            facts.continent = location.continent.as_ref().map(|v| v.to_lowercase());
+            facts.region = location.region.as_ref().map(|v| v.to_lowercase());
+            facts.city = location.city.as_ref().map(|v| v.to_lowercase());
+            facts.time_zone = location.time_zone.as_ref().map(|v| v.to_lowercase());
+            facts.asn = location.asn.as_ref().map(|asn| asn.number);
+            facts.org = location
+                .asn
+                .as_ref()
+                .and_then(|asn| asn.organization.as_ref())
+                .map(|v| v.to_lowercase());
+            facts.coordinates = location.coordinates;
+        }
+
+        facts
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TextTest {
+    EQ(Box<str>),
+    Starts(Box<str>),
+    Ends(Box<str>),
+    IN(HashSet<Box<str>>),
+}
+
+impl TextTest {
+    fn matches(&self, value: &Option<String>) -> bool {
+        let value = match value {
+            Some(value) => value.as_str(),
+            None => return false,
+        };
+
+        match self {
+            TextTest::EQ(str) => value == str.as_ref(),
+            TextTest::Starts(str) => value.starts_with(str.as_ref()),
+            TextTest::Ends(str) => value.ends_with(str.as_ref()),
+            TextTest::IN(set) => set.contains(value),
+        }
+    }
+}
+
+///
+/// Set of small numbers (days, months, percents) a numeric condition matches.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BitSet(u128);
+
+impl BitSet {
+    fn from_fn(range: RangeInclusive<u32>, predicate: impl Fn(u32) -> bool) -> Self {
+        let bits = range
+            .filter(|&value| predicate(value))
+            .fold(0u128, |bits, value| bits | (1 << value));
+
+        Self(bits)
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        value < 128 && self.0 & (1 << value) != 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum DateTest {
+    EQ(NaiveDate),
+    GT(NaiveDate),
+    LT(NaiveDate),
+    IN(Vec<NaiveDate>),
+}
+
+impl DateTest {
+    //keeps the "value OP request" semantics of the model, inclusive for GT and LT
+    fn matches(&self, request_date: &NaiveDate) -> bool {
+        match self {
+            DateTest::EQ(date) => date == request_date,
+            DateTest::GT(date) => date >= request_date,
+            DateTest::LT(date) => date <= request_date,
+            DateTest::IN(dates) => dates.contains(request_date),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum DateTimeTest {
+    GT(NaiveDateTime),
+    LT(NaiveDateTime),
+    Between(NaiveDateTime, NaiveDateTime),
+}
+
+impl DateTimeTest {
+    //like hours and versions, datetimes compare as "request OP value"
+    fn matches(&self, request_datetime: &NaiveDateTime) -> bool {
+        match self {
+            DateTimeTest::GT(datetime) => request_datetime > datetime,
+            DateTimeTest::LT(datetime) => request_datetime < datetime,
+            DateTimeTest::Between(from, to) => from <= request_datetime && request_datetime < to,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum VersionTest {
+    EQ(Version),
+    GT(Version),
+    GE(Version),
+    LT(Version),
+    LE(Version),
+    IN(Vec<Version>),
+}
+
+impl VersionTest {
+    fn matches(&self, version: &Option<Version>) -> bool {
+        let version = match version {
+            Some(version) => version,
+            None => return false,
+        };
+
+        match self {
+            VersionTest::EQ(value) => version.compare(value) == Ordering::Equal,
+            VersionTest::GT(value) => version.compare(value) == Ordering::Greater,
+            VersionTest::GE(value) => version.compare(value) != Ordering::Less,
+            VersionTest::LT(value) => version.compare(value) == Ordering::Less,
+            VersionTest::LE(value) => version.compare(value) != Ordering::Greater,
+            VersionTest::IN(values) => values
+                .iter()
+                .any(|value| version.compare(value) == Ordering::Equal),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct GeoTest {
+    center: Coordinates,
+    radius_km: f64,
+}
+
+impl GeoTest {
+    fn matches(&self, coordinates: &Option<Coordinates>) -> bool {
+        coordinates.map_or(false, |coordinates| {
+            self.center.distance_km(&coordinates) <= self.radius_km
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Test {
+    Ua(TextTest),
+    Os(TextTest),
+    Device(TextTest),
+    Lang(TextTest),
+    Country(TextTest),
+    Continent(TextTest),
+    Region(TextTest),
+    City(TextTest),
+    TimeZone(TextTest),
+    Org(TextTest),
+    Asn(HashSet<u32>),
+    Location(GeoTest),
+    OsVersion(VersionTest),
+    UaVersion(VersionTest),
+    DeviceClass(HashSet<DeviceClass>),
+    Date(DateTest),
+    DateTime(DateTimeTest),
+    Rnd(BitSet),
+    DayOfWeek(BitSet),
+    DayOfMonth(BitSet),
+    Month(BitSet),
+    Hour(BitSet),
+    All(Vec<PlanNode>),
+    Any(Vec<PlanNode>),
+    Not(Box<PlanNode>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PlanNode {
+    tests: Vec<Test>,
+    all: bool,
+}
+
+impl PlanNode {
+    fn eval(&self, facts: &ClientFacts) -> bool {
+        let mut tests = self.tests.iter();
+
+        if self.all {
+            tests.all(|test| test.eval(facts))
+        } else {
+            tests.any(|test| test.eval(facts))
+        }
+    }
+}
+
+impl Test {
+    fn eval(&self, facts: &ClientFacts) -> bool {
+        match self {
+            Test::Ua(test) => test.matches(&facts.ua),
+            Test::Os(test) => test.matches(&facts.os),
+            Test::Device(test) => test.matches(&facts.device),
+            Test::Lang(test) => test.matches(&facts.lang),
+            Test::Country(test) => test.matches(&facts.country),
+            Test::Continent(test) => test.matches(&facts.continent),
+            Test::Region(test) => test.matches(&facts.region),
+            Test::City(test) => test.matches(&facts.city),
+            Test::TimeZone(test) => test.matches(&facts.time_zone),
+            Test::Org(test) => test.matches(&facts.org),
+            Test::Asn(set) => facts.asn.map_or(false, |asn| set.contains(&asn)),
+            Test::Location(test) => test.matches(&facts.coordinates),
+            Test::OsVersion(test) => test.matches(&facts.os_version),
+            Test::UaVersion(test) => test.matches(&facts.ua_version),
+            Test::DeviceClass(set) => facts
+                .device_class
+                .map_or(false, |device_class| set.contains(&device_class)),
+            Test::Date(test) => test.matches(&facts.date),
+            Test::DateTime(test) => test.matches(&facts.datetime),
+            Test::Rnd(set) => set.contains(rng().random_range(0..100)),
+            Test::DayOfWeek(set) => set.contains(facts.day_of_week),
+            Test::DayOfMonth(set) => set.contains(facts.day_of_month),
+            Test::Month(set) => set.contains(facts.month),
+            Test::Hour(set) => set.contains(facts.hour),
+            Test::All(nodes) => nodes.iter().all(|node| node.eval(facts)),
+            Test::Any(nodes) => nodes.iter().any(|node| node.eval(facts)),
+            Test::Not(node) => !node.eval(facts),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpressionPlan {
+    root: PlanNode,
+    requirements: Requirements,
+}
+
+impl ExpressionPlan {
+    ///
+    /// Compiles an expression once `validate` accepted its values, so that a condition the API
+    /// stored is never rejected here for a different reason.
+    ///
+    pub fn compile(expression: &Expression) -> Result<Self> {
+        validate(expression)?;
+
+        Ok(Self {
+            root: compile_node(expression)?,
+            requirements: Requirements::from_expression(expression),
+        })
+    }
+
+    pub fn requirements(&self) -> &Requirements {
+        &self.requirements
+    }
+
+    pub fn eval(&self, facts: &ClientFacts) -> bool {
+        self.root.eval(facts)
+    }
+}
+
+///
+/// Compiles the plans of all conditional routings of a route.
+///
+pub fn compile_route(route: &mut Route) -> Result<()> {
+    if let RoutingPolicy::Conditional(conditions) = &mut route.policy {
+        ScheduleZone::parse(route.properties.time_zone.as_deref()).map_err(|error| {
+            anyhow!(
+                "invalid time zone of route {}/{}: {}",
+                route.switch,
+                route.link,
+                error
+            )
+        })?;
+
+        for routing in conditions.iter_mut() {
+            let plan = ExpressionPlan::compile(&routing.condition).map_err(|error| {
+                anyhow!(
+                    "invalid condition '{}' of route {}/{}: {}",
+                    routing.key,
+                    route.switch,
+                    route.link,
+                    error
+                )
+            })?;
+
+            routing.plan = Some(Arc::new(plan));
+        }
+    }
+
+    Ok(())
+}
+
+macro_rules! text_test {
+    ($enum:ident, $value:expr) => {
+        match $value {
+            $enum::EQ(str) => TextTest::EQ(intern(str)),
+            $enum::Starts(str) => TextTest::Starts(intern(str)),
+            $enum::Ends(str) => TextTest::Ends(intern(str)),
+            $enum::IN(array) => TextTest::IN(intern_set(array)),
+        }
+    };
+}
+
+//keeps the "value OP request" semantics of the stored conditions: RND GT 30 matches 0 to 29
+macro_rules! number_test {
+    ($enum:ident, $range:expr, $value:expr) => {
+        match $value {
+            $enum::EQ(num) => BitSet::from_fn($range, |request| *num == request),
+            $enum::GT(num) => BitSet::from_fn($range, |request| *num > request),
+            $enum::LT(num) => BitSet::from_fn($range, |request| *num < request),
+            $enum::IN(nums) => BitSet::from_fn($range, |request| nums.contains(&request)),
+        }
+    };
+}
+
+fn compile_node(expression: &Expression) -> Result<PlanNode> {
+    let mut tests = Vec::new();
+
+    if let Some(country) = &expression.country {
+        tests.push(Test::Country(text_test!(Country, country)));
+    }
+
+    if let Some(version) = &expression.os_version {
+        tests.push(Test::OsVersion(version_test("os_version", version)?));
+    }
+
+    if let Some(version) = &expression.ua_version {
+        tests.push(Test::UaVersion(version_test("ua_version", version)?));
+    }
+
+    if let Some(device_class) = &expression.device_class {
+        let names = match device_class {
+            DeviceClassExpr::EQ(name) => vec![name.clone()],
+            DeviceClassExpr::IN(names) => names.clone(),
+        };
+
+        tests.push(Test::DeviceClass(
+            names
+                .iter()
+                .map(|name| name.parse::<DeviceClass>())
+                .collect::<Result<HashSet<DeviceClass>>>()?,
+        ));
+    }
+
+    if let Some(continent) = &expression.continent {
+        tests.push(Test::Continent(text_test!(Continent, continent)));
+    }
+
+    if let Some(region) = &expression.region {
+        tests.push(Test::Region(text_test!(Region, region)));
+    }
+
+    if let Some(city) = &expression.city {
+        tests.push(Test::City(text_test!(City, city)));
+    }
+
+    if let Some(time_zone) = &expression.time_zone {
+        tests.push(Test::TimeZone(text_test!(TimeZone, time_zone)));
+    }
+
+    if let Some(org) = &expression.org {
+        tests.push(Test::Org(text_test!(Org, org)));
+    }
+
+    if let Some(asn) = &expression.asn {
+        tests.push(Test::Asn(match asn {
+            Asn::EQ(num) => HashSet::from([*num]),
+            Asn::IN(nums) => nums.iter().copied().collect(),
+        }));
+    }
+
+    if let Some(Location::Within(circle)) = &expression.location {
+        tests.push(Test::Location(GeoTest {
+            center: Coordinates {
+                latitude: circle.latitude,
+                longitude: circle.longitude,
+            },
+            radius_km: circle.radius_km,
+        }));
+    }
+
+    if let Some(lang) = &expression.lang {
+        tests.push(Test::Lang(match lang {
+            Lang::EQ(str) => TextTest::EQ(intern(str)),
+            Lang::IN(array) => TextTest::IN(intern_set(array)),
+        }));
+    }
+
+    if let Some(ua) = &expression.ua {
+        tests.push(Test::Ua(text_test!(UA, ua)));
+    }
+
+    if let Some(os) = &expression.os {
+        tests.push(Test::Os(text_test!(OS, os)));
+    }
+
+    if let Some(device) = &expression.device {
+        tests.push(Test::Device(text_test!(Device, device)));
+    }
+
+    if let Some(rnd) = &expression.rnd {
+        tests.push(Test::Rnd(number_test!(RND, RND_RANGE, rnd)));
+    }
+
+    if let Some(day) = &expression.day_of_month {
+        tests.push(Test::DayOfMonth(number_test!(
+            DayOfMonth,
+            DAY_OF_MONTH_RANGE,
+            day
+        )));
+    }
+
+    if let Some(day) = &expression.day_of_week {
+        tests.push(Test::DayOfWeek(number_test!(
+            DayOfWeek,
+            DAY_OF_WEEK_RANGE,
+            day
+        )));
+    }
+
+    if let Some(month) = &expression.month {
+        tests.push(Test::Month(number_test!(Month, MONTH_RANGE, month)));
+    }
+
+    if let Some(hour) = &expression.hour {
+        tests.push(Test::Hour(hour_test(hour)));
+    }
+
+    if let Some(datetime) = &expression.datetime {
+        tests.push(Test::DateTime(match datetime {
+            DateTimeExpr::GT(str) => DateTimeTest::GT(parse_datetime(str)?),
+            DateTimeExpr::LT(str) => DateTimeTest::LT(parse_datetime(str)?),
+            DateTimeExpr::Between(from, to) => {
+                DateTimeTest::Between(parse_datetime(from)?, parse_datetime(to)?)
+            }
+        }));
+    }
+
+    if let Some(date) = &expression.date {
+        tests.push(Test::Date(match date {
+            Date::EQ(str) => DateTest::EQ(parse_date(str)?),
+            Date::GT(str) => DateTest::GT(parse_date(str)?),
+            Date::LT(str) => DateTest::LT(parse_date(str)?),
+            Date::IN(array) => DateTest::IN(
+                array
+                    .iter()
+                    .map(|str| parse_date(str))
+                    .collect::<Result<Vec<NaiveDate>>>()?,
+            ),
+        }));
+    }
+
+    if let Some(and) = &expression.and {
+        tests.push(Test::All(compile_children(and)?));
+    }
+
+    if let Some(or) = &expression.or {
+        tests.push(Test::Any(compile_children(or)?));
+    }
+
+    if let Some(not) = &expression.not {
+        tests.push(Test::Not(Box::new(compile_node(not)?)));
+    }
+
+    //or by default
+    let all = matches!(expression.default_operator, Some(DefaultOperator::And));
+
+    Ok(PlanNode { tests, all })
+}
+
+///
+/// Hours of the condition, which unlike the older ordered fields compare as "request OP value".
+///
+fn hour_test(hour: &Hour) -> BitSet {
+    match hour {
+        Hour::EQ(num) => BitSet::from_fn(HOUR_RANGE, |request| request == *num),
+        Hour::GT(num) => BitSet::from_fn(HOUR_RANGE, |request| request > *num),
+        Hour::LT(num) => BitSet::from_fn(HOUR_RANGE, |request| request < *num),
+        Hour::IN(nums) => BitSet::from_fn(HOUR_RANGE, |request| nums.contains(&request)),
+        Hour::Between(from, to) => hours_between(*from, *to),
+    }
+}
+
+///
+/// Hours from `from` up to, but excluding, `to`; `(22, 6)` wraps around midnight.
+///
+fn hours_between(from: u32, to: u32) -> BitSet {
+    if from <= to {
+        return BitSet::from_fn(HOUR_RANGE, |request| from <= request && request < to);
+    }
+
+    BitSet::from_fn(HOUR_RANGE, |request| from <= request || request < to)
+}
+
+fn version_test(name: &str, version: &VersionExpr) -> Result<VersionTest> {
+    let parse = |value: &String| {
+        Version::parse(value)
+            .ok_or_else(|| anyhow!("invalid version '{}' of field '{}'", value, name))
+    };
+
+    Ok(match version {
+        VersionExpr::EQ(value) => VersionTest::EQ(parse(value)?),
+        VersionExpr::GT(value) => VersionTest::GT(parse(value)?),
+        VersionExpr::GE(value) => VersionTest::GE(parse(value)?),
+        VersionExpr::LT(value) => VersionTest::LT(parse(value)?),
+        VersionExpr::LE(value) => VersionTest::LE(parse(value)?),
+        VersionExpr::IN(values) => {
+            VersionTest::IN(values.iter().map(parse).collect::<Result<Vec<Version>>>()?)
+        }
+    })
+}
+
+fn compile_children(children: &Vec<Box<Expression>>) -> Result<Vec<PlanNode>> {
+    children.iter().map(|child| compile_node(child)).collect()
+}
+
+fn intern(value: &str) -> Box<str> {
+    value.to_lowercase().into_boxed_str()
+}
+
+fn intern_set(values: &Vec<String>) -> HashSet<Box<str>> {
+    values.iter().map(|value| intern(value)).collect()
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .map_err(|_| anyhow!("invalid date '{}', expected YYYYMMDD", value))
+}
+
+fn parse_datetime(value: &str) -> Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, DATETIME_FORMAT)
+        .map_err(|_| anyhow!("invalid datetime '{}', expected YYYY-MM-DDTHH:MM", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    fn facts() -> ClientFacts {
+        //a wednesday
+        let utc = Utc.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap();
+        let mut facts = ClientFacts::new(&utc);
+
+        facts.ua = Some("firefox".to_string());
+        facts.os = Some("mac os x".to_string());
+        facts.country = Some("us".to_string());
+        facts.lang = Some("en".to_string());
+        facts.continent = Some("na".to_string());
+        facts.region = Some("us-ca".to_string());
+        facts.city = Some("san francisco".to_string());
+        facts.asn = Some(15169);
+        facts.os_version = Version::parse("17.2");
+        facts.ua_version = Version::parse("120.0.6099");
+        facts.device_class = Some(DeviceClass::Mobile);
+        facts.coordinates = Some(Coordinates {
+            latitude: 37.7749,
+            longitude: -122.4194,
+        });
+
+        facts
+    }
+
+    fn compiled(json: &str) -> Result<ExpressionPlan> {
+        ExpressionPlan::compile(&serde_json::from_str::<Expression>(json).unwrap())
+    }
+
+    #[test]
+    fn should_match_text_case_insensitive() {
+        let plan =
+            compiled(r#"{ "ua": { "IN": ["Chrome", "FireFox"] }, "os": { "starts": "Mac" } }"#)
+                .unwrap();
+
+        assert!(plan.eval(&facts()));
+        assert_eq!(
+            plan.requirements(),
+            &Requirements {
+                ua: true,
+                os: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn should_combine_nested_nodes() {
+        let plan = compiled(
+            r#"{
+                "DEFAULT_OPERATOR": "AND",
+                "country": { "EQ": "US" },
+                "OR": [{ "lang": { "EQ": "de" } }, { "day_of_week": { "IN": [3] } }],
+                "NOT": { "month": { "EQ": 5 } }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(!plan.eval(&facts()));
+
+        let without_not = compiled(
+            r#"{
+                "DEFAULT_OPERATOR": "AND",
+                "country": { "EQ": "US" },
+                "OR": [{ "lang": { "EQ": "de" } }, { "day_of_week": { "IN": [3] } }]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(without_not.eval(&facts()));
+    }
+
+    #[test]
+    fn should_keep_ordered_semantics() {
+        let matches = |json: &str| compiled(json).unwrap().eval(&facts());
+
+        //"value OP request": day_of_month GT 20 matches the 15th
+        assert!(matches(r#"{ "day_of_month": { "GT": 20 } }"#));
+        assert!(!matches(r#"{ "day_of_month": { "LT": 20 } }"#));
+        assert!(matches(r#"{ "date": { "GT": "20240515" } }"#));
+        assert!(matches(r#"{ "date": { "LT": "20240515" } }"#));
+        assert!(!matches(r#"{ "date": { "GT": "20240514" } }"#));
+        assert!(!matches(r#"{ "month": { "GT": 4 } }"#));
+        assert!(matches(r#"{ "rnd": { "GT": 100 } }"#));
+        assert!(!matches(r#"{ "rnd": { "LT": 100 } }"#));
+
+        //hours are new and compare as "request OP value"
+        assert!(matches(r#"{ "hour": { "LT": 13 } }"#));
+        assert!(!matches(r#"{ "hour": { "GT": 12 } }"#));
+    }
+
+    #[test]
+    fn should_match_hours_and_datetimes() {
+        let matches = |json: &str| compiled(json).unwrap().eval(&facts());
+
+        assert!(matches(r#"{ "hour": { "BETWEEN": [9, 17] } }"#));
+        assert!(!matches(r#"{ "hour": { "BETWEEN": [9, 12] } }"#));
+        assert!(!matches(r#"{ "hour": { "BETWEEN": [22, 6] } }"#));
+        assert!(matches(r#"{ "hour": { "BETWEEN": [12, 6] } }"#));
+        assert!(matches(r#"{ "hour": { "GT": 11 } }"#));
+        assert!(matches(r#"{ "datetime": { "GT": "2024-05-15T11:59" } }"#));
+        assert!(!matches(r#"{ "datetime": { "LT": "2024-05-15T12:00" } }"#));
+        assert!(matches(
+            r#"{ "datetime": { "BETWEEN": ["2024-05-15T12:00", "2024-05-15T12:01"] } }"#
+        ));
+        assert!(
+            compiled(r#"{ "hour": { "IN": [12] } }"#)
+                .unwrap()
+                .requirements()
+                .time
+        );
+    }
+
+    #[test]
+    fn should_evaluate_in_schedule_zone() {
+        //a friday night in UTC is a saturday morning in tokyo
+        let utc = Utc.with_ymd_and_hms(2024, 5, 17, 23, 30, 0).unwrap();
+
+        let tokyo = ScheduleZone::parse(Some("Asia/Tokyo")).unwrap();
+        let local = tokyo.local_time(&utc, None);
+
+        assert_eq!(local.hour(), 8);
+        assert_eq!(ClientFacts::at(local).day_of_week, 6);
+
+        let visitor = ScheduleZone::parse(Some("Visitor")).unwrap();
+
+        assert_eq!(
+            visitor.local_time(&utc, Some("America/Los_Angeles")).hour(),
+            16
+        );
+        assert_eq!(visitor.local_time(&utc, Some("Mars/Olympus")).hour(), 23);
+        assert_eq!(ScheduleZone::parse(None).unwrap(), ScheduleZone::Utc);
+        assert!(ScheduleZone::parse(Some("Europe/Atlantis")).is_err());
+    }
+
+    #[test]
+    fn should_not_match_missing_facts() {
+        let mut facts = facts();
+        facts.country = None;
+
+        assert!(!compiled(r#"{ "country": { "EQ": "us" } }"#)
+            .unwrap()
+            .eval(&facts));
+        assert!(compiled(r#"{ "NOT": { "country": { "EQ": "us" } } }"#)
+            .unwrap()
+            .eval(&facts));
+    }
+
+    #[test]
+    fn should_match_location_fields() {
+        let plan = compiled(
+            r#"{
+                "DEFAULT_OPERATOR": "AND",
+                "continent": { "EQ": "NA" },
+                "region": { "IN": ["us-ca", "us-nv"] },
+                "city": { "starts": "San" },
+                "asn": { "IN": [15169, 13335] }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(plan.eval(&facts()));
+        assert!(plan.requirements().location);
+
+        //san jose is ~68km away from san francisco
+        let near = r#"{ "location": { "WITHIN": { "latitude": 37.3382, "longitude": -121.8863, "radius_km": 70 } } }"#;
+        let far = r#"{ "location": { "WITHIN": { "latitude": 37.3382, "longitude": -121.8863, "radius_km": 60 } } }"#;
+
+        assert!(compiled(near).unwrap().eval(&facts()));
+        assert!(!compiled(far).unwrap().eval(&facts()));
+        assert!(!compiled(near).unwrap().eval(&ClientFacts::default()));
+    }
+
+    #[test]
+    fn should_compare_versions() {
+        let matches = |json: &str| compiled(json).unwrap().eval(&facts());
+
+        assert!(matches(r#"{ "os_version": { "GE": "17" } }"#));
+        assert!(matches(r#"{ "os_version": { "EQ": "17" } }"#));
+        assert!(!matches(r#"{ "os_version": { "GT": "17" } }"#));
+        assert!(matches(r#"{ "os_version": { "LT": "17.3" } }"#));
+        assert!(matches(r#"{ "ua_version": { "IN": ["119", "120"] } }"#));
+        assert!(matches(
+            r#"{ "device_class": { "IN": ["tablet", "mobile"] } }"#
+        ));
+        assert!(!matches(r#"{ "device_class": { "EQ": "desktop" } }"#));
+
+        let requirements = compiled(r#"{ "device_class": { "EQ": "tv" } }"#)
+            .unwrap()
+            .requirements;
+
+        assert!(requirements.device_class);
+    }
+
+    #[test]
+    fn should_reject_invalid_conditions() {
+        assert!(compiled(r#"{ "date": { "EQ": "2024-05-15" } }"#).is_err());
+        assert!(compiled(r#"{ "day_of_week": { "IN": [1, 7] } }"#).is_err());
+        assert!(compiled(r#"{ "month": { "EQ": 0 } }"#).is_err());
+        assert!(compiled(r#"{ "country": { "IN": [] } }"#).is_err());
+        assert!(compiled(r#"{ "os_version": { "GE": "17.x" } }"#).is_err());
+        assert!(compiled(r#"{ "device_class": { "EQ": "fridge" } }"#).is_err());
+        assert!(compiled(r#"{ "AND": [{ "rnd": { "LT": 101 } }] }"#).is_err());
+        assert!(compiled(r#"{ "hour": { "BETWEEN": [24, 6] } }"#).is_err());
+        assert!(compiled(
+            r#"{ "datetime": { "BETWEEN": ["2024-05-15T12:00", "2024-05-15T12:00"] } }"#
+        )
+        .is_err());
+        assert!(compiled(
+            r#"{ "location": { "WITHIN": { "latitude": 91, "longitude": 0, "radius_km": 1 } } }"#
+        )
+        .is_err());
+        assert!(
+            compiled(r#"{ "country": { "EQ": "us" }, "OR": [], "DEFAULT_OPERATOR": "AND" }"#)
+                .is_err()
+        );
+        assert!(compiled(r#"{ "NOT": {} }"#).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//!
+//! What the router answers when a request fails: a redirect to the last known
+//! destination of the link when failing open, the error page otherwise.
+//!
+use std::fs;
+
+use anyhow::{Context, Result};
+use http::Uri;
+use moka::future::Cache;
+use tracing::{error, info, warn};
+
+use crate::settings::Redirect;
+
+use super::{
+    flow_error::FlowError,
+    flow_router::{FlowRouterResult, RedirectType},
+};
+
+const LAST_KNOWN_CAPACITY: u64 = 100_000;
+
+const DEFAULT_ERROR_PAGE: &'static str = r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>{status} {reason}</title></head>
+<body><h1>{status} {reason}</h1><p>This link is temporarily unavailable, please try again later.</p></body>
+</html>"#;
+
+///
+/// HTML error page, `{status}` and `{reason}` are replaced with the HTTP status.
+///
+#[derive(Clone, Debug)]
+pub struct ErrorPage {
+    template: String,
+}
+
+impl ErrorPage {
+    pub fn new(template: String) -> Self {
+        Self { template }
+    }
+
+    pub fn render(&self, error: &FlowError) -> String {
+        let status = error.status_code();
+
+        self.template
+            .replace("{status}", status.as_str())
+            .replace("{reason}", status.canonical_reason().unwrap_or_default())
+    }
+}
+
+impl Default for ErrorPage {
+    fn default() -> Self {
+        Self::new(DEFAULT_ERROR_PAGE.to_string())
+    }
+}
+
+#[derive(Clone)]
+pub struct Fallback {
+    fail_open: bool,
+    last_known: Cache<String, Uri>,
+    error_page: ErrorPage,
+}
+
+impl Fallback {
+    pub fn new(fail_open: bool, error_page: ErrorPage) -> Self {
+        Self {
+            fail_open,
+            last_known: Cache::new(LAST_KNOWN_CAPACITY),
+            error_page,
+        }
+    }
+
+    pub fn from_settings(settings: &Redirect) -> Result<Self> {
+        let error_page = match &settings.error_page {
+            Some(path) => {
+                info!("  error page -> {}", path);
+
+                let template = fs::read_to_string(path)
+                    .with_context(|| format!("can not read the error page '{}'", path))?;
+
+                ErrorPage::new(template)
+            }
+            None => ErrorPage::default(),
+        };
+
+        Ok(Self::new(settings.fail_open, error_page))
+    }
+
+    pub fn error_page(&self) -> &ErrorPage {
+        &self.error_page
+    }
+
+    ///
+    /// Keeps the destination of a successful redirect, to fail open to it later.
+    ///
+    pub async fn remember(&self, key: &str, result: &FlowRouterResult) {
+        if !self.fail_open {
+            return;
+        }
+
+        if let FlowRouterResult::Redirect(uri, _) = result {
+            self.last_known.insert(key.to_string(), uri.clone()).await;
+        }
+    }
+
+    pub async fn recover(&self, key: &str, error: FlowError) -> FlowRouterResult {
+        if self.fail_open && error.is_transient() {
+            if let Some(uri) = self.last_known.get(key).await {
+                warn!("failing open for {} to {}: {}", key, uri, error);
+
+                return FlowRouterResult::Redirect(uri, RedirectType::Temporary);
+            }
+        }
+
+        error!("request for {} failed: {}", key, error);
+
+        FlowRouterResult::Error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect(uri: &str) -> FlowRouterResult {
+        FlowRouterResult::Redirect(uri.parse().unwrap(), RedirectType::Temporary)
+    }
+
+    #[tokio::test]
+    async fn should_fail_open_to_last_known_destination() {
+        let fallback = Fallback::new(true, ErrorPage::default());
+
+        fallback
+            .remember("test.com/a", &redirect("https://example.com/a"))
+            .await;
+
+        let result = fallback
+            .recover(
+                "test.com/a",
+                FlowError::RoutesUnavailable("timeout".to_string()),
+            )
+            .await;
+
+        assert!(
+            matches!(result, FlowRouterResult::Redirect(uri, _) if uri == "https://example.com/a")
+        );
+    }
+
+    #[tokio::test]
+    async fn should_render_error_without_last_known_destination() {
+        let fallback = Fallback::new(true, ErrorPage::default());
+
+        let result = fallback
+            .recover(
+                "test.com/b",
+                FlowError::RoutesUnavailable("timeout".to_string()),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            FlowRouterResult::Error(FlowError::RoutesUnavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_not_fail_open_for_broken_routes_or_when_disabled() {
+        let fallback = Fallback::new(true, ErrorPage::default());
+
+        fallback
+            .remember("test.com/a", &redirect("https://example.com/a"))
+            .await;
+
+        let result = fallback
+            .recover("test.com/a", FlowError::MissingDestination)
+            .await;
+
+        assert!(matches!(
+            result,
+            FlowRouterResult::Error(FlowError::MissingDestination)
+        ));
+
+        let disabled = Fallback::new(false, ErrorPage::default());
+
+        disabled
+            .remember("test.com/a", &redirect("https://example.com/a"))
+            .await;
+
+        let result = disabled
+            .recover(
+                "test.com/a",
+                FlowError::RoutesUnavailable("timeout".to_string()),
+            )
+            .await;
+
+        assert!(matches!(result, FlowRouterResult::Error(_)));
+    }
+
+    #[test]
+    fn should_fail_on_a_missing_error_page() {
+        let error = Fallback::from_settings(&Redirect {
+            error_page: Some("missing/error.html".to_string()),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "can not read the error page 'missing/error.html'"
+        );
+    }
+
+    #[test]
+    fn should_render_branded_error_page() {
+        let page = ErrorPage::new("<h1>Oops {status}</h1><p>{reason}</p>".to_string());
+
+        assert_eq!(
+            page.render(&FlowError::RoutesUnavailable("timeout".to_string())),
+            "<h1>Oops 503</h1><p>Service Unavailable</p>"
+        );
+    }
+}
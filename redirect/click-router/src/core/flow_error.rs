@@ -0,0 +1,95 @@
+use http::StatusCode;
+use thiserror::Error;
+
+///
+/// Failures of the request path, rendered as an error page (or a fail open redirect)
+/// instead of panicking the handler.
+///
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum FlowError {
+    #[error("routes are unavailable: {0}")]
+    RoutesUnavailable(String),
+    #[error("invalid route: {0}")]
+    InvalidRoute(String),
+    #[error("route has no destination")]
+    MissingDestination,
+    #[error("invalid destination '{0}'")]
+    InvalidDestination(String),
+    #[error("invalid url '{0}'")]
+    InvalidUrl(String),
+    #[error("missing host")]
+    MissingHost,
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl FlowError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            FlowError::RoutesUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            FlowError::MissingHost | FlowError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            FlowError::InvalidRoute(_)
+            | FlowError::MissingDestination
+            | FlowError::InvalidDestination(_)
+            | FlowError::InvalidUrl(_) => StatusCode::BAD_GATEWAY,
+            FlowError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    ///
+    /// Only transient failures fall back to the last known destination;
+    /// a broken route stays broken until it is fixed.
+    ///
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            FlowError::RoutesUnavailable(_) | FlowError::Internal(_)
+        )
+    }
+}
+
+impl From<anyhow::Error> for FlowError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<FlowError>() {
+            Ok(error) => error,
+            Err(error) => FlowError::Internal(error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn should_keep_flow_errors_through_anyhow() {
+        let error: anyhow::Error = FlowError::InvalidDestination("::".to_string()).into();
+
+        assert_eq!(
+            FlowError::from(error),
+            FlowError::InvalidDestination("::".to_string())
+        );
+        assert_eq!(
+            FlowError::from(anyhow!("boom")),
+            FlowError::Internal("boom".to_string())
+        );
+    }
+
+    #[test]
+    fn should_map_status_codes() {
+        assert_eq!(
+            FlowError::RoutesUnavailable("timeout".to_string()).status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            FlowError::MissingDestination.status_code(),
+            StatusCode::BAD_GATEWAY
+        );
+        assert!(FlowError::RoutesUnavailable("timeout".to_string()).is_transient());
+        assert!(!FlowError::InvalidDestination("::".to_string()).is_transient());
+    }
+}
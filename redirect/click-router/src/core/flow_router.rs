@@ -3,17 +3,21 @@ use async_recursion::async_recursion;
 use chrono::{DateTime, Utc};
 use cookie::{Cookie, CookieJar};
 use http::{
-    header::IntoHeaderName, uri::Scheme, Extensions, HeaderMap, HeaderValue, Method, StatusCode,
-    Uri, Version,
+    header::{IntoHeaderName, REFERER},
+    uri::Scheme,
+    Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri, Version,
 };
 use indexmap::IndexMap;
+use link_keys::LinkKeys;
 use multimap::MultiMap;
 use std::{
     self,
     collections::HashMap,
     fmt::{self, Display, Formatter, Result as FmtResult},
     net::SocketAddr,
+    time::Instant,
 };
+use tracing::warn;
 use ulid::Ulid;
 
 use crate::{
@@ -22,24 +26,31 @@ use crate::{
         UserAgentDetectorType, UserSettingsCacheType,
     },
     model::{
-        hit::{Click, HitRoute},
+        hit::{Click, HitClient, HitRequest, HitRoute, HitRouting},
+        user_settings::SKIP_COOKIES,
         Hit, Route, UserSettings,
     },
 };
 
 use super::{
+    device_class::DeviceClass,
+    explain::{is_explain_requested, Explain, ExplainClient},
+    fallback::Fallback,
+    flow_error::FlowError,
     flow_module::{FlowModule, FlowStepContinuation},
     hits_register::HitRegistrar,
     host::{HostExtractor, HostInfo},
     ip::{IPExtractor, IPInfo},
     language::{Language, LanguageExtractor},
-    location::{Country, LocationDetector},
+    location::{Country, Location, LocationDetector},
+    metrics::Metrics,
     modules::FlowModules,
     protocol::{ProtoInfo, ProtocolExtractor},
     routes::RoutesManager,
     user_agent::{Device, UserAgent, UserAgentDetector, OS},
     user_agent_string::UserAgentStringExtractor,
     user_settings::UserSettingsManager,
+    visitor::opted_out,
     InitOnce,
 };
 
@@ -49,15 +60,74 @@ pub enum RedirectType {
     Temporary,
 }
 
+impl RedirectType {
+    ///
+    /// Permanent only for the route code 308, answered as is so that POST requests keep their
+    /// method and body. The other codes, 301 included, stay temporary as they always were:
+    /// browsers cache permanent redirects, a stored 301 must not stop a link from being changed.
+    ///
+    pub fn of_code(code: Option<u16>) -> Self {
+        match code {
+            Some(308) => RedirectType::Permanent,
+            _ => RedirectType::Temporary,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum FlowRouterResult {
     Empty(StatusCode),
     Json(String, StatusCode),
     PlainText(String, StatusCode),
+    Html(String, StatusCode),
     Proxied(Uri, StatusCode),
     Redirect(Uri, RedirectType),
     Retargeting(Uri, Vec<Uri>),
-    Error,
+    /// Empty body with the given headers, e.g. CORS preflight answers.
+    Headers(HeaderMap, StatusCode),
+    /// Generated content with its content type, e.g. QR codes.
+    Binary(Vec<u8>, &'static str, StatusCode),
+    Error(FlowError),
+}
+
+impl FlowRouterResult {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FlowRouterResult::Empty(_) => "empty",
+            FlowRouterResult::Json(_, _) => "json",
+            FlowRouterResult::PlainText(_, _) => "plain_text",
+            FlowRouterResult::Html(_, _) => "html",
+            FlowRouterResult::Proxied(_, _) => "proxied",
+            FlowRouterResult::Redirect(_, _) => "redirect",
+            FlowRouterResult::Retargeting(_, _) => "retargeting",
+            FlowRouterResult::Headers(_, _) => "headers",
+            FlowRouterResult::Binary(_, _, _) => "binary",
+            FlowRouterResult::Error(_) => "error",
+        }
+    }
+
+    ///
+    /// Status the result is answered with, proxied results with the one of the destination.
+    ///
+    pub fn status(&self) -> StatusCode {
+        match self {
+            FlowRouterResult::Empty(status)
+            | FlowRouterResult::Json(_, status)
+            | FlowRouterResult::PlainText(_, status)
+            | FlowRouterResult::Html(_, status)
+            | FlowRouterResult::Proxied(_, status)
+            | FlowRouterResult::Headers(_, status)
+            | FlowRouterResult::Binary(_, _, status) => *status,
+            FlowRouterResult::Redirect(_, RedirectType::Permanent) => {
+                StatusCode::PERMANENT_REDIRECT
+            }
+            FlowRouterResult::Redirect(_, RedirectType::Temporary) => {
+                StatusCode::TEMPORARY_REDIRECT
+            }
+            FlowRouterResult::Retargeting(_, _) => StatusCode::OK,
+            FlowRouterResult::Error(error) => error.status_code(),
+        }
+    }
 }
 
 impl Display for FlowRouterResult {
@@ -77,6 +147,19 @@ pub enum FlowStep {
     End,
 }
 
+impl FlowStep {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlowStep::Initial => "init",
+            FlowStep::Start => "start",
+            FlowStep::UrlExtract => "url_extract",
+            FlowStep::Register => "register",
+            FlowStep::BuildResult => "build_result",
+            FlowStep::End => "end",
+        }
+    }
+}
+
 impl Display for FlowStep {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "{:?}", self)
@@ -127,6 +210,16 @@ impl<'a> FlowRouterContext<'a> {
         false
     }
 
+    ///
+    /// Gets a string value of the context's data
+    ///
+    pub fn get_string(&self, string_key: &'static str) -> Option<&'static str> {
+        match self.data.get(&string_key) {
+            Some(FlowRouterData::String(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
     ///
     /// Adds a bool value to the context's data
     ///
@@ -177,7 +270,9 @@ pub struct FlowRouterContext<'a> {
     pub client_os: InitOnce<Option<OS>>,
     pub client_ua: InitOnce<Option<UserAgent>>,
     pub client_device: InitOnce<Option<Device>>,
+    pub client_device_class: InitOnce<Option<DeviceClass>>,
     pub client_country: InitOnce<Option<Country>>,
+    pub client_location: InitOnce<Option<Location>>,
     pub current_step: FlowStep,
     pub host: Option<HostInfo>,
     pub client_ip: Option<IPInfo>,
@@ -186,18 +281,23 @@ pub struct FlowRouterContext<'a> {
     pub protocol: Option<ProtoInfo>,
     pub out_route: Option<Route>,
     pub main_route: Option<Route>,
+    /// Key of the condition the out route was chosen by.
+    pub condition: Option<String>,
+    /// Id of the visitor cookie, set when registering the hit.
+    pub visitor: Option<String>,
     pub in_route: FlowInRoute,
     pub request: &'a RequestType<'a>,
-    pub response: &'a ResponseType<'a>,
+    pub response: &'a mut ResponseType<'a>,
 
     pub result: Option<FlowRouterResult>,
+    pub explain: Option<Explain>,
 }
 
 impl<'a> FlowRouterContext<'a> {
     pub fn new(
         in_route: FlowInRoute,
         request: &'a RequestType<'a>,
-        response: &'a ResponseType<'a>,
+        response: &'a mut ResponseType<'a>,
     ) -> Self {
         Self {
             id: Ulid::new().to_string(),
@@ -206,7 +306,9 @@ impl<'a> FlowRouterContext<'a> {
             client_os: InitOnce::default(None),
             client_ua: InitOnce::default(None),
             client_device: InitOnce::default(None),
+            client_device_class: InitOnce::default(None),
             client_country: InitOnce::default(None),
+            client_location: InitOnce::default(None),
             current_step: FlowStep::Initial,
             in_route,
             user_agent: None,
@@ -216,7 +318,10 @@ impl<'a> FlowRouterContext<'a> {
             protocol: None,
             out_route: None,
             main_route: None,
+            condition: None,
+            visitor: None,
             result: None,
+            explain: None,
             request,
             response,
         }
@@ -282,6 +387,7 @@ pub trait Request {
     fn headers(&self) -> &HeaderMap;
     fn method(&self) -> &Method;
     fn scheme(&self) -> &Scheme;
+    fn version(&self) -> Version;
     fn params(&self) -> &IndexMap<String, String>;
     fn queries(&self) -> &MultiMap<String, String>;
     fn remote_addr(&self) -> Option<SocketAddr>;
@@ -321,6 +427,10 @@ pub struct TlsInfo {
 }
 
 const MAIN_SWITCH: &'static str = "main";
+/// Context data key of the hit source, e.g. `qr` for scanned codes.
+pub const HIT_SOURCE: &'static str = "hit_source";
+/// Context data key set once the hit of the click was handed to the registrar.
+pub const HIT_REGISTERED: &'static str = "hit_registered";
 
 pub struct FlowRouter {
     routes_manager: RoutesManager,
@@ -334,6 +444,8 @@ pub struct FlowRouter {
     user_agent_detector: UserAgentDetectorType,
     location_detector: LocationDetectorType,
     modules: Vec<FlowModules>,
+    fallback: Fallback,
+    metrics: Metrics,
 }
 
 impl FlowRouter {
@@ -344,9 +456,12 @@ impl FlowRouter {
         location_detector: LocationDetectorType,
         hit_registrar: HitRegistrarType,
         modules: Vec<FlowModules>,
+        fallback: Fallback,
+        link_keys: LinkKeys,
+        metrics: Metrics,
     ) -> Self {
         FlowRouter {
-            routes_manager: RoutesManager::new(routes_cache),
+            routes_manager: RoutesManager::new(routes_cache, link_keys),
             settings_manager: UserSettingsManager::new(user_settings_cache),
             hit_registrar,
             host_extractor: HostExtractor::new(),
@@ -357,28 +472,49 @@ impl FlowRouter {
             user_agent_detector,
             location_detector,
             modules,
+            fallback,
+            metrics,
         }
     }
 
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    ///
+    /// Registers the hits still queued, once the server stopped accepting requests.
+    ///
+    pub async fn shutdown(&self) {
+        self.hit_registrar.close().await;
+    }
+
     #[async_recursion()]
     pub async fn router_to(&self, context: &mut FlowRouterContext, step: FlowStep) -> Result<()> {
         context.current_step = step;
 
+        if let Some(explain) = context.explain.as_mut() {
+            explain.visit(&context.current_step);
+        }
+
         match context.current_step {
             FlowStep::Start => self.handle_start(context).await,
             FlowStep::UrlExtract => self.handle_url_extract(context).await,
             FlowStep::Register => self.handle_register(context).await,
             FlowStep::BuildResult => self.handle_build_result(context).await,
             FlowStep::End => self.handle_end(context).await,
-            _ => panic!("Initial step set not allowed."),
+            _ => Err(FlowError::Internal("Initial step set not allowed.".to_string()).into()),
         }
     }
 
     async fn handle_start(&self, context: &mut FlowRouterContext<'_>) -> Result<()> {
         for module in &self.modules {
+            let _timer = self
+                .metrics
+                .module_timer(module.name(), context.current_step.name());
             let result = module.handle_start(context, &self).await?;
 
             if result == FlowStepContinuation::Break {
+                self.trace_break(context, module);
                 return Ok(());
             }
         }
@@ -388,9 +524,13 @@ impl FlowRouter {
 
     async fn handle_url_extract(&self, context: &mut FlowRouterContext<'_>) -> Result<()> {
         for module in &self.modules {
+            let _timer = self
+                .metrics
+                .module_timer(module.name(), context.current_step.name());
             let result = module.handle_url_extract(context, &self).await?;
 
             if result == FlowStepContinuation::Break {
+                self.trace_break(context, module);
                 return Ok(());
             }
         }
@@ -399,7 +539,15 @@ impl FlowRouter {
     }
 
     async fn handle_register(&self, context: &mut FlowRouterContext<'_>) -> Result<()> {
+        //explained requests never register hits
+        if context.explain.is_some() {
+            return self.router_to(context, FlowStep::BuildResult).await;
+        }
+
         for module in &self.modules {
+            let _timer = self
+                .metrics
+                .module_timer(module.name(), context.current_step.name());
             let result = module.handle_register(context, &self).await?;
 
             if result == FlowStepContinuation::Break {
@@ -407,48 +555,91 @@ impl FlowRouter {
             }
         }
 
-        self.hit_registrar
-            .register(&Hit::click(
-                &context.id,
-                context.utc,
-                context.user_agent.as_deref(),
-                Some(context.client_ip.clone().unwrap().address),
-                &Click::new(
-                    context
-                        .out_route
-                        .as_ref()
-                        .unwrap()
-                        .dest
-                        .as_ref()
-                        .unwrap()
-                        .as_str(),
-                ),
-                HitRoute::from_route(&context.main_route),
-            ))
-            .await?;
+        //nothing to register, the missing destination is reported when building the result
+        let destination = match context
+            .out_route
+            .as_ref()
+            .and_then(|route| route.dest.clone())
+        {
+            Some(destination) => destination,
+            None => return self.router_to(context, FlowStep::BuildResult).await,
+        };
+
+        let allowed_params = match context
+            .main_route
+            .as_ref()
+            .and_then(|route| route.properties.owner_id.as_deref())
+        {
+            Some(owner_id) => self
+                .get_user_settings(owner_id)
+                .await
+                .ok()
+                .flatten()
+                .map(|settings| settings.allowed_request_params)
+                .unwrap_or_default(),
+            None => vec![],
+        };
+
+        //only known when a condition needed it, clicks do not wait for a GeoIP lookup
+        let location = if context.client_location.has_value() {
+            context.client_location.value().clone()
+        } else {
+            None
+        };
+
+        let hit = Hit::click(
+            context.id.clone(),
+            context.utc,
+            context.user_agent.clone(),
+            context.client_ip.as_ref().map(|ip| ip.address),
+            location,
+            Click::new(destination).with_source(context.get_string(HIT_SOURCE)),
+            HitRoute::from_route(&context.main_route),
+        )
+        .with_request(hit_request(context, &allowed_params))
+        .with_client(hit_client(context))
+        .with_routing(HitRouting {
+            switch: context.out_route.as_ref().map(|route| route.switch.clone()),
+            condition: context.condition.clone(),
+        })
+        .with_visitor(context.visitor.clone());
+
+        self.register_hit(hit).await;
+        context.add_bool(HIT_REGISTERED, true);
 
         self.router_to(context, FlowStep::BuildResult).await
     }
 
+    ///
+    /// Registers a hit of the flow or of a module, e.g. a conversion.
+    ///
+    pub async fn register_hit(&self, hit: Hit) {
+        let id = hit.id.clone();
+
+        //a tracking outage must not break redirects
+        if let Err(error) = self.hit_registrar.register(hit).await {
+            warn!("hit {} was not registered: {}", id, error);
+        }
+    }
+
     async fn handle_build_result(&self, context: &mut FlowRouterContext<'_>) -> Result<()> {
         for module in &self.modules {
+            let _timer = self
+                .metrics
+                .module_timer(module.name(), context.current_step.name());
             let result = module.handle_build_result(context, &self).await?;
 
             if result == FlowStepContinuation::Break {
+                self.trace_break(context, module);
                 return Ok(());
             }
         }
 
         let result = match &context.out_route {
-            Some(route) => {
-                let destination = &route
-                    .dest
-                    .as_ref()
-                    .unwrap_or(&String::from("http://test.com"))
-                    .to_string();
-
-                FlowRouterResult::Redirect(destination.parse().unwrap(), RedirectType::Temporary)
-            }
+            Some(route) => FlowRouterResult::Redirect(
+                destination_uri(route)?,
+                RedirectType::of_code(route.code),
+            ),
             None => FlowRouterResult::Empty(StatusCode::NOT_FOUND),
         };
 
@@ -459,9 +650,13 @@ impl FlowRouter {
 
     async fn handle_end(&self, context: &mut FlowRouterContext<'_>) -> Result<()> {
         for module in &self.modules {
+            let _timer = self
+                .metrics
+                .module_timer(module.name(), context.current_step.name());
             let result = module.handle_end(context, &self).await?;
 
             if result == FlowStepContinuation::Break {
+                self.trace_break(context, module);
                 return Ok(());
             }
         }
@@ -475,6 +670,29 @@ impl FlowRouter {
         Ok(user_settings)
     }
 
+    ///
+    /// Whether first-party cookies may be set on the short domain: not for the visitors who
+    /// opted out with DNT or GPC, nor on the links of the owners who skip cookies.
+    ///
+    pub async fn are_cookies_allowed(&self, context: &FlowRouterContext<'_>) -> Result<bool> {
+        if opted_out(context.request.headers()) {
+            return Ok(false);
+        }
+
+        let owner_id = match context
+            .main_route
+            .as_ref()
+            .and_then(|route| route.properties.owner_id.as_deref())
+        {
+            Some(owner_id) => owner_id,
+            None => return Ok(false),
+        };
+
+        let settings = self.get_user_settings(owner_id).await?;
+
+        Ok(!settings.is_some_and(|settings| settings.skip.contains(&SKIP_COOKIES.to_string())))
+    }
+
     pub async fn get_route(
         &self,
         switch: &str,
@@ -494,12 +712,16 @@ impl FlowRouter {
 
     async fn start<'a>(
         &self,
+        in_route: FlowInRoute,
         req: &'a RequestType<'a>,
-        res: &'a ResponseType<'a>,
+        res: &'a mut ResponseType<'a>,
     ) -> Result<FlowRouterContext<'a>> {
-        let mut context = self.build_context(req, res);
+        let mut context = self.build_context(in_route, req, res);
 
         for module in &self.modules {
+            let _timer = self
+                .metrics
+                .module_timer(module.name(), FlowStep::Initial.name());
             let result = module.init(&mut context, &self).await?;
 
             if result == FlowStepContinuation::Break {
@@ -516,27 +738,99 @@ impl FlowRouter {
 
         self.router_to(&mut context, FlowStep::Start).await?;
 
+        self.build_explain(&mut context)?;
+
         Ok(context)
     }
 
-    fn build_route_uri(&self, request: &RequestType) -> FlowInRoute {
-        let path = &request.uri().path()[1..];
+    fn trace_break(&self, context: &mut FlowRouterContext, module: &FlowModules) {
+        if let Some(explain) = context.explain.as_mut() {
+            explain.break_by(module.name());
+        }
+    }
+
+    ///
+    /// Replaces the result of an explained request with its trace.
+    ///
+    fn build_explain(&self, context: &mut FlowRouterContext) -> Result<()> {
+        if context.explain.is_none() {
+            return Ok(());
+        }
+
+        //the trace lists every client attribute, not only the ones the route needed
+        self.load_ua(context);
+        self.load_os(context);
+        self.load_device(context);
+        self.load_device_class(context);
+        self.load_location(context);
+
+        let client = ExplainClient {
+            ip: context.client_ip.as_ref().map(|ip| ip.address.to_string()),
+            user_agent: context.user_agent.clone(),
+            langs: context.client_langs.as_ref().map_or(Vec::new(), |langs| {
+                langs.iter().map(|lang| lang.name.clone()).collect()
+            }),
+            ua: context.client_ua.value().clone(),
+            os: context.client_os.value().clone(),
+            device: context.client_device.value().clone(),
+            device_class: *context.client_device_class.value(),
+            location: context.client_location.value().clone(),
+        };
+
+        let mut explain = match context.explain.take() {
+            Some(explain) => explain,
+            None => return Ok(()),
+        };
+
+        explain.client = client;
+        explain.time_zone = context
+            .main_route
+            .as_ref()
+            .and_then(|route| route.properties.time_zone.clone());
+        explain.destination = context
+            .out_route
+            .as_ref()
+            .and_then(|route| route.dest.clone());
+        explain.result = context.result.as_ref().map(|result| result.to_string());
+
+        //the conditional module sets the switch when a condition matched
+        if explain.switch.is_none() && context.out_route.is_some() {
+            explain.switch = Some(MAIN_SWITCH.to_string());
+        }
+
+        context.result = Some(FlowRouterResult::Json(
+            serde_json::to_string_pretty(&explain)?,
+            StatusCode::OK,
+        ));
+
+        Ok(())
+    }
+
+    fn build_route_uri(&self, request: &RequestType) -> Result<FlowInRoute, FlowError> {
+        let path = request.uri().path();
+        let path = path.strip_prefix('/').unwrap_or(path);
 
-        let host_info = self.host_extractor.detect(&request, false).unwrap();
+        let host_info = self
+            .host_extractor
+            .detect(&request, false)
+            .ok_or(FlowError::MissingHost)?;
 
         let query = request.uri().query().unwrap_or_default();
 
         let scheme = request.uri().scheme().unwrap_or(&Scheme::HTTP).to_string();
 
+        //paths keep their case only on the domains with case sensitive links
+        let path = self.routes_manager.link_keys().path(&host_info.host, path);
+
         let in_route = FlowInRoute {
             host: host_info.host,
             port: host_info.port,
-            path: path.to_ascii_lowercase(),
+            path,
             query: query.to_ascii_lowercase(),
             scheme: scheme.to_ascii_lowercase(),
         };
 
-        in_route
+        Ok(in_route)
     }
 
     fn allow_debug(&self, context: &mut FlowRouterContext) -> bool {
@@ -559,13 +853,54 @@ impl FlowRouter {
             return;
         }
 
+        //the location already holds the country
+        if context.client_location.has_value() {
+            let country = context
+                .client_location
+                .value()
+                .as_ref()
+                .and_then(|location| location.country.clone());
+
+            context.client_country.init_with(country);
+            return;
+        }
+
+        let timer = self.metrics.dependency_timer("geoip", "country");
         let country = self
             .location_detector
             .detect_country(&context.client_ip.clone().unwrap().address);
+        timer.observe_duration();
 
         context.client_country.init_with(country);
     }
 
+    pub fn load_location(&self, context: &mut FlowRouterContext) {
+        if context.client_location.has_value() {
+            return;
+        }
+
+        if context.client_ip.is_none() {
+            context.client_location.init_with(None);
+            return;
+        }
+
+        let timer = self.metrics.dependency_timer("geoip", "location");
+        let location = self
+            .location_detector
+            .detect_location(&context.client_ip.clone().unwrap().address);
+        timer.observe_duration();
+
+        if !context.client_country.has_value() {
+            context.client_country.init_with(
+                location
+                    .as_ref()
+                    .and_then(|location| location.country.clone()),
+            );
+        }
+
+        context.client_location.init_with(location);
+    }
+
     pub fn load_os(&self, context: &mut FlowRouterContext) {
         if context.client_os.has_value() {
             return;
@@ -617,6 +952,37 @@ impl FlowRouter {
         context.client_device.init_with(Some(device));
     }
 
+    pub fn load_device_class(&self, context: &mut FlowRouterContext) {
+        if context.client_device_class.has_value() {
+            return;
+        }
+
+        if context.user_agent.is_none() {
+            context.client_device_class.init_with(None);
+            return;
+        }
+
+        self.load_ua(context);
+        self.load_os(context);
+        self.load_device(context);
+
+        let device_class = match (
+            context.client_device.value(),
+            context.client_os.value(),
+            context.client_ua.value(),
+        ) {
+            (Some(device), Some(os), Some(ua)) => Some(DeviceClass::classify(
+                context.user_agent.as_ref().unwrap(),
+                device,
+                os,
+                ua,
+            )),
+            _ => None,
+        };
+
+        context.client_device_class.init_with(device_class);
+    }
+
     fn replace_debug_data(&self, context: &mut FlowRouterContext) {
         if !self.allow_debug(context) {
             return;
@@ -629,12 +995,17 @@ impl FlowRouter {
             .user_agent_string_extractor
             .detect(&context.request, true);
         context.client_langs = self.language_extractor.detect(&context.request, true);
+
+        if is_explain_requested(context.request) {
+            context.explain = Some(Explain::new(&context.id, context.utc));
+        }
     }
 
     fn build_context<'a>(
         &self,
+        in_route: FlowInRoute,
         req: &'a RequestType<'a>,
-        res: &'a ResponseType<'a>,
+        res: &'a mut ResponseType<'a>,
     ) -> FlowRouterContext<'a> {
         let mut context = FlowRouterContext {
             id: Ulid::new().to_string(),
@@ -643,9 +1014,11 @@ impl FlowRouter {
             client_os: InitOnce::default(None),
             client_ua: InitOnce::default(None),
             client_device: InitOnce::default(None),
+            client_device_class: InitOnce::default(None),
             client_country: InitOnce::default(None),
+            client_location: InitOnce::default(None),
             current_step: FlowStep::Initial,
-            in_route: self.build_route_uri(req),
+            in_route,
             user_agent: None,
             client_ip: None,
             client_langs: None,
@@ -653,7 +1026,10 @@ impl FlowRouter {
             protocol: None,
             out_route: None,
             main_route: None,
+            condition: None,
+            visitor: None,
             result: None,
+            explain: None,
             request: req,
             response: res,
         };
@@ -669,12 +1045,499 @@ impl FlowRouter {
         context
     }
 
+    ///
+    /// Routes a request; failures end up as `FlowRouterResult::Error`,
+    /// or as a redirect to the last known destination when failing open.
+    ///
     pub async fn handle<'a>(
         &self,
         req: &'a RequestType<'a>,
-        res: &'a ResponseType<'a>,
-    ) -> Result<FlowRouterResult> {
-        let context = self.start(req, res).await?;
-        Ok(context.result.unwrap())
+        res: &'a mut ResponseType<'a>,
+    ) -> FlowRouterResult {
+        let started = Instant::now();
+
+        let result = self.route(req, res).await;
+
+        self.metrics
+            .observe_request(result.kind(), result.status(), started.elapsed());
+
+        result
+    }
+
+    async fn route<'a>(
+        &self,
+        req: &'a RequestType<'a>,
+        res: &'a mut ResponseType<'a>,
+    ) -> FlowRouterResult {
+        let in_route = match self.build_route_uri(req) {
+            Ok(in_route) => in_route,
+            Err(error) => return FlowRouterResult::Error(error),
+        };
+
+        let key = format!("{}/{}", in_route.host, in_route.path);
+
+        let result = match self.start(in_route, req, res).await {
+            Ok(context) => {
+                if let Some(last_known) = last_known(&context) {
+                    self.fallback.remember(&key, &last_known).await;
+                }
+
+                context
+                    .result
+                    .ok_or_else(|| FlowError::Internal("no result was built".to_string()))
+            }
+            Err(error) => Err(FlowError::from(error)),
+        };
+
+        match result {
+            Ok(result) => result,
+            Err(error) => self.fallback.recover(&key, error).await,
+        }
+    }
+
+    pub fn render_error(&self, error: &FlowError) -> String {
+        self.fallback.error_page().render(error)
+    }
+}
+
+fn hit_request(context: &FlowRouterContext, allowed_params: &[String]) -> HitRequest {
+    let request = context.request;
+    let tls = context
+        .protocol
+        .as_ref()
+        .is_some_and(|protocol| protocol.ssl_on);
+
+    let alpn = match (tls, request.version()) {
+        (true, Version::HTTP_2) => Some("h2"),
+        (true, Version::HTTP_3) => Some("h3"),
+        (true, Version::HTTP_10 | Version::HTTP_11) => Some("http/1.1"),
+        _ => None,
+    };
+
+    HitRequest {
+        referer: request
+            .headers()
+            .get(REFERER)
+            .and_then(|referer| referer.to_str().ok())
+            .map(str::to_string),
+        host: context.host.as_ref().map(|host| host.host.clone()),
+        domain_id: context
+            .main_route
+            .as_ref()
+            .and_then(|route| route.properties.domain_id.clone()),
+        scheme: context
+            .protocol
+            .as_ref()
+            .map(|protocol| protocol.proto.clone()),
+        tls,
+        alpn: alpn.map(str::to_string),
+        languages: context
+            .client_langs
+            .iter()
+            .flatten()
+            .map(|language| language.name.clone())
+            .collect(),
+        query: allowed_params
+            .iter()
+            .filter_map(|param| {
+                request
+                    .queries()
+                    .get(param)
+                    .map(|value| (param.clone(), value.clone()))
+            })
+            .collect(),
+    }
+}
+
+///
+/// Client attributes some module already detected, nothing is detected for the hits.
+///
+fn hit_client(context: &FlowRouterContext) -> HitClient {
+    fn loaded<T: Clone>(value: &InitOnce<Option<T>>) -> Option<T> {
+        match value.has_value() {
+            true => value.value().clone(),
+            false => None,
+        }
+    }
+
+    HitClient {
+        user_agent: loaded(&context.client_ua),
+        os: loaded(&context.client_os),
+        device: loaded(&context.client_device),
+        //the location has the country already
+        country: match context.client_location.value() {
+            Some(_) => None,
+            None => loaded(&context.client_country),
+        },
+    }
+}
+
+///
+/// Main destination of a redirected link, replayed to the other visitors when failing open.
+/// What this visitor got may be theirs alone: the switch of their conditions, their click id.
+///
+fn last_known(context: &FlowRouterContext) -> Option<FlowRouterResult> {
+    if !matches!(context.result, Some(FlowRouterResult::Redirect(_, _))) {
+        return None;
+    }
+
+    let route = context.main_route.as_ref()?;
+
+    Some(FlowRouterResult::Redirect(
+        destination_uri(route).ok()?,
+        RedirectType::of_code(route.code),
+    ))
+}
+
+fn destination_uri(route: &Route) -> Result<Uri, FlowError> {
+    let destination = route.dest.as_ref().ok_or(FlowError::MissingDestination)?;
+
+    destination
+        .parse()
+        .map_err(|_| FlowError::InvalidDestination(destination.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, fs};
+
+    use sqlx::SqlitePool;
+
+    use crate::{
+        adapters::{
+            file::{hit_registrar::FileHitRegistrar, settings::FileHits},
+            memory::user_settings_store::MemoryUserSettingsStore,
+            moka::{
+                routes_cache::MokaRoutesCache, settings::CacheSettings,
+                user_settings_cache::MokaUserSettingsCache,
+            },
+            sqlite::{database::connect, routes_store::SqliteRoutesStore, settings::Sqlite},
+            RoutesStoreType, UserSettingsStoreType,
+        },
+        core::{fallback::ErrorPage, modules::conditional::ConditionalModule, routes::RoutesCache},
+    };
+
+    use super::*;
+
+    const ROUTES: &'static str = r#"INSERT INTO routes (switch, link, dest, policy, properties) VALUES
+        ('main', 'go.test.com%2fpromo', 'https://shop.com/promo',
+            '{"Conditional":[{"key":"b","condition":"rnd < 100"}]}', '{}'),
+        ('b', 'go.test.com%2fpromo', 'https://shop.com/b', '"Basic"', '{}'),
+        ('main', 'go.test.com%2fdebug', 'https://shop.com/debug',
+            '{"Conditional":[{"key":"b","condition":"rnd < 100"}]}', '{"allow_debug":true}'),
+        ('b', 'go.test.com%2fdebug', 'https://shop.com/debug-b', '"Basic"', '{}'),
+        ('main', 'go.test.com%2fempty', NULL, '"Basic"', '{}'),
+        ('main', 'go.test.com%2fbroken', 'https://exa mple.com', '"Basic"', '{}')"#;
+
+    async fn database() -> SqlitePool {
+        let pool = connect(&Sqlite {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+
+        sqlx::query(ROUTES).execute(&pool).await.unwrap();
+
+        pool
+    }
+
+    fn router(
+        pool: &SqlitePool,
+        fail_open: bool,
+        hit_registrar: HitRegistrarType,
+    ) -> (FlowRouter, RoutesCacheType) {
+        let settings = CacheSettings {
+            max_capacity: 100,
+            time_to_live_minutes: 60,
+            time_to_idle_minutes: 60,
+            stale_minutes: 0,
+            negative_ttl_seconds: 30,
+        };
+
+        let routes_cache = RoutesCacheType::Moka(MokaRoutesCache::new(
+            RoutesStoreType::Sqlite(SqliteRoutesStore::new(pool.clone())),
+            settings.clone(),
+        ));
+        let user_settings_cache = UserSettingsCacheType::Moka(MokaUserSettingsCache::new(
+            UserSettingsStoreType::Memory(MemoryUserSettingsStore::new(vec![])),
+            settings,
+        ));
+
+        let router = FlowRouter::default(
+            routes_cache.clone(),
+            user_settings_cache,
+            UserAgentDetectorType::None(),
+            LocationDetectorType::None(),
+            hit_registrar,
+            vec![FlowModules::Conditional(ConditionalModule::new())],
+            Fallback::new(fail_open, ErrorPage::default()),
+            LinkKeys::default(),
+            Metrics::default(),
+        );
+
+        (router, routes_cache)
+    }
+
+    async fn get(router: &FlowRouter, uri: &str) -> FlowRouterResult {
+        send(
+            router,
+            RequestData {
+                uri: uri.parse().unwrap(),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    async fn explain(router: &FlowRouter, uri: &str) -> FlowRouterResult {
+        let mut request_data = RequestData {
+            uri: uri.parse().unwrap(),
+            ..Default::default()
+        };
+        request_data
+            .queries
+            .insert("x_debug_explain".to_string(), "1".to_string());
+
+        send(router, request_data).await
+    }
+
+    async fn send(router: &FlowRouter, request_data: RequestData) -> FlowRouterResult {
+        let request = RequestType::Test(request_data);
+        let mut response = ResponseType::Test(ResponseData::default());
+
+        router.handle(&request, &mut response).await
+    }
+
+    fn route(dest: Option<&str>) -> Route {
+        Route {
+            dest: dest.map(|dest| dest.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_build_the_hit_request() {
+        let mut request_data = RequestData {
+            version: Version::HTTP_2,
+            ..Default::default()
+        };
+        request_data
+            .headers
+            .insert(REFERER, HeaderValue::from_static("https://news.com/"));
+        request_data
+            .queries
+            .insert("utm_source".to_string(), "news".to_string());
+        request_data
+            .queries
+            .insert("token".to_string(), "secret".to_string());
+
+        let request = RequestType::Test(request_data);
+        let mut response = ResponseType::Test(ResponseData::default());
+
+        let mut context = FlowRouterContext::new(
+            FlowInRoute::new(
+                "https".to_string(),
+                "go.brand.com".to_string(),
+                443,
+                "promo".to_string(),
+                String::new(),
+            ),
+            &request,
+            &mut response,
+        );
+        context.protocol = Some(ProtoInfo {
+            proto: "https".to_string(),
+            ssl_on: true,
+        });
+
+        let hit_request = hit_request(&context, &["utm_source".to_string()]);
+
+        assert_eq!(hit_request.referer.as_deref(), Some("https://news.com/"));
+        assert_eq!(hit_request.alpn.as_deref(), Some("h2"));
+        assert!(hit_request.tls);
+        assert_eq!(
+            hit_request.query,
+            BTreeMap::from([("utm_source".to_string(), "news".to_string())])
+        );
+    }
+
+    #[test]
+    fn should_parse_destination() {
+        assert_eq!(
+            destination_uri(&route(Some("https://example.com/a?b=c"))).unwrap(),
+            "https://example.com/a?b=c"
+        );
+    }
+
+    #[test]
+    fn should_map_route_code_to_redirect_type() {
+        assert!(matches!(
+            RedirectType::of_code(Some(301)),
+            RedirectType::Temporary
+        ));
+        assert!(matches!(
+            RedirectType::of_code(Some(308)),
+            RedirectType::Permanent
+        ));
+        assert!(matches!(
+            RedirectType::of_code(Some(302)),
+            RedirectType::Temporary
+        ));
+        assert!(matches!(
+            RedirectType::of_code(None),
+            RedirectType::Temporary
+        ));
+    }
+
+    #[test]
+    fn should_reject_missing_or_malformed_destination() {
+        assert_eq!(
+            destination_uri(&route(None)),
+            Err(FlowError::MissingDestination)
+        );
+        assert_eq!(
+            destination_uri(&route(Some("https://exa mple.com"))),
+            Err(FlowError::InvalidDestination(
+                "https://exa mple.com".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_label_the_results_with_their_status() {
+        let redirect = FlowRouterResult::Redirect(
+            Uri::from_static("https://shop.com/"),
+            RedirectType::Permanent,
+        );
+        let error = FlowRouterResult::Error(FlowError::MissingHost);
+
+        assert_eq!(
+            (redirect.kind(), redirect.status()),
+            ("redirect", StatusCode::PERMANENT_REDIRECT)
+        );
+        assert_eq!(
+            (error.kind(), error.status()),
+            ("error", StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_fail_open_to_the_main_destination_when_the_store_fails() {
+        let pool = database().await;
+        let (router, routes_cache) = router(&pool, true, HitRegistrarType::None());
+
+        //the visitor is sent to the switch of the condition, the main destination is remembered
+        assert!(matches!(
+            get(&router, "http://go.test.com/promo").await,
+            FlowRouterResult::Redirect(uri, _) if uri == "https://shop.com/b"
+        ));
+
+        routes_cache
+            .invalidate(MAIN_SWITCH, "go.test.com%2fpromo")
+            .await
+            .unwrap();
+        pool.close().await;
+
+        assert!(matches!(
+            get(&router, "http://go.test.com/promo").await,
+            FlowRouterResult::Redirect(uri, RedirectType::Temporary) if uri == "https://shop.com/promo"
+        ));
+
+        //without a last known destination the error page is rendered
+        let error = match get(&router, "http://go.test.com/other").await {
+            FlowRouterResult::Error(error) => error,
+            result => panic!("expected an error, got {}", result),
+        };
+
+        assert!(matches!(error, FlowError::RoutesUnavailable(_)));
+        assert!(router
+            .render_error(&error)
+            .contains("503 Service Unavailable"));
+    }
+
+    #[tokio::test]
+    async fn should_not_fail_open_when_disabled() {
+        let pool = database().await;
+        let (router, routes_cache) = router(&pool, false, HitRegistrarType::None());
+
+        get(&router, "http://go.test.com/promo").await;
+
+        routes_cache
+            .invalidate(MAIN_SWITCH, "go.test.com%2fpromo")
+            .await
+            .unwrap();
+        pool.close().await;
+
+        assert!(matches!(
+            get(&router, "http://go.test.com/promo").await,
+            FlowRouterResult::Error(FlowError::RoutesUnavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_answer_broken_routes_with_an_error() {
+        let pool = database().await;
+        let (router, _) = router(&pool, true, HitRegistrarType::None());
+
+        assert!(matches!(
+            get(&router, "http://go.test.com/empty").await,
+            FlowRouterResult::Error(FlowError::MissingDestination)
+        ));
+        assert!(matches!(
+            get(&router, "http://go.test.com/broken").await,
+            FlowRouterResult::Error(FlowError::InvalidDestination(dest)) if dest == "https://exa mple.com"
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_explain_debug_routes_without_registering_hits() {
+        let dir = tempfile::tempdir().unwrap();
+        let hits = dir.path().join("hits.jsonl");
+        let registrar = FileHitRegistrar::new(&FileHits {
+            path: hits.to_string_lossy().to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let pool = database().await;
+        let (router, _) = router(&pool, true, HitRegistrarType::File(registrar));
+
+        let content = match explain(&router, "http://go.test.com/debug").await {
+            FlowRouterResult::Json(content, StatusCode::OK) => content,
+            result => panic!("expected the explain trace, got {}", result),
+        };
+        let trace: serde_json::Value = serde_json::from_str(&content).unwrap();
+
+        let steps: Vec<&str> = trace["steps"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|step| step["step"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(steps.first(), Some(&"Start"));
+        assert!(steps.contains(&"Register"));
+        assert_eq!(trace["conditions"][0]["key"], "b");
+        assert_eq!(trace["conditions"][0]["condition"], "rnd < 100");
+        assert_eq!(trace["conditions"][0]["matched"], true);
+        assert_eq!(trace["switch"], "b");
+        assert_eq!(trace["destination"], "https://shop.com/debug-b");
+        assert_eq!(trace["hit_registered"], false);
+        assert_eq!(fs::read_to_string(&hits).unwrap().lines().count(), 0);
+
+        //the same route without the parameter redirects and registers its hit
+        assert!(matches!(
+            get(&router, "http://go.test.com/debug").await,
+            FlowRouterResult::Redirect(uri, _) if uri == "https://shop.com/debug-b"
+        ));
+        assert_eq!(fs::read_to_string(&hits).unwrap().lines().count(), 1);
+
+        //routes without allow_debug ignore the parameter
+        assert!(matches!(
+            explain(&router, "http://go.test.com/promo").await,
+            FlowRouterResult::Redirect(uri, _) if uri == "https://shop.com/b"
+        ));
+        assert_eq!(fs::read_to_string(&hits).unwrap().lines().count(), 2);
     }
 }
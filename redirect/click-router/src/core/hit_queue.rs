@@ -0,0 +1,122 @@
+//!
+//! Hits registered in batches by background consumers, so that redirects never wait on the
+//! message broker.
+//!
+use anyhow::Result;
+use tracing::{info, warn};
+
+use crate::adapters::HitRegistrarType;
+use crate::core::metrics::Metrics;
+use crate::model::Hit;
+use crate::utils::async_queue::{
+    AsyncQueue, BatchProcess, OverflowPolicy, QueueMetrics, QueueSettings, Spill,
+};
+
+use super::hit_spool::{HitSpool, SpoolMetrics};
+use super::hits_register::HitRegistrar;
+
+struct HitBatch {
+    registrar: HitRegistrarType,
+    spool: Option<HitSpool>,
+    metrics: Metrics,
+}
+
+impl HitBatch {
+    async fn register(&self, batch: Vec<Hit>) -> Result<()> {
+        let _timer = self.metrics.dependency_timer("hits", "register_batch");
+
+        self.registrar.register_batch(batch).await
+    }
+}
+
+#[async_trait::async_trait()]
+impl BatchProcess<Hit> for HitBatch {
+    async fn process(&self, batch: Vec<Hit>) -> Result<()> {
+        let spool = match &self.spool {
+            Some(spool) => spool,
+            None => return self.register(batch).await,
+        };
+
+        //the hits already registered of a failed batch are spooled again, replays are at least once
+        let retry = batch.clone();
+
+        if let Err(error) = self.register(batch).await {
+            warn!("{} hits spooled: {}", retry.len(), error);
+
+            spool.spool(retry).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct HitQueue {
+    queue: AsyncQueue<Hit>,
+    spool: Option<HitSpool>,
+}
+
+impl HitQueue {
+    ///
+    /// Failed batches and, with the spill overflow policy, the hits of a full queue go to the
+    /// spool, replayed to the registrar in the background.
+    ///
+    pub fn new(
+        registrar: HitRegistrarType,
+        settings: &QueueSettings,
+        spool: Option<HitSpool>,
+        metrics: Metrics,
+    ) -> Self {
+        info!(
+            "  hit queue -> {} hits, batches of {}, {:?} on overflow",
+            settings.capacity, settings.batch_size, settings.overflow
+        );
+
+        match &spool {
+            Some(spool) => spool.spawn_replay(registrar.clone(), settings.batch_size),
+            None if settings.overflow == OverflowPolicy::Spill => {
+                warn!("no hit spool is set up, the hits of a full queue are dropped")
+            }
+            None => {}
+        }
+
+        let spill = spool
+            .clone()
+            .map(|spool| Box::new(spool) as Box<dyn Spill<Hit>>);
+
+        Self {
+            queue: AsyncQueue::new(
+                Box::new(HitBatch {
+                    registrar,
+                    spool: spool.clone(),
+                    metrics,
+                }),
+                spill,
+                settings,
+            ),
+            spool,
+        }
+    }
+
+    ///
+    /// Registers the hits still in the queue, the hits enqueued afterwards fail.
+    ///
+    pub async fn close(&self) {
+        self.queue.close().await
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        self.queue.metrics()
+    }
+
+    pub fn spool_metrics(&self) -> Option<SpoolMetrics> {
+        self.spool.as_ref().map(HitSpool::metrics)
+    }
+}
+
+#[async_trait::async_trait()]
+impl HitRegistrar for HitQueue {
+    async fn register(&self, hit: Hit) -> Result<()> {
+        self.queue.enqueue(hit).await
+    }
+}
@@ -0,0 +1,524 @@
+//!
+//! Write-ahead spool of the hits that could not be registered, replayed once the hit stream is
+//! healthy again. Segments are append-only files of `{length}{crc32}{json}` records, the oldest
+//! segment is replayed first and deleted once all its hits are registered.
+//!
+//! Replays are at least once, hits of a segment replayed in part are registered again.
+//!
+//! Every append is synced to disk before it returns, a crash or a power failure only loses the
+//! hits being appended. The file IO runs on blocking threads, never on the async workers.
+//!
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{Error, Result};
+use tracing::{info, warn};
+
+use crate::model::Hit;
+use crate::settings::Spool;
+use crate::utils::async_queue::Spill;
+
+use super::hits_register::HitRegistrar;
+
+const SEGMENT_EXTENSION: &'static str = "spool";
+const HEADER_LEN: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpoolMetrics {
+    pub segments: usize,
+    pub bytes: u64,
+    /// Hits waiting to be replayed.
+    pub depth: u64,
+    pub spooled: u64,
+    pub replayed: u64,
+    /// Hits dropped because the spool was full.
+    pub rejected: u64,
+    /// Records dropped because their checksum or content was invalid.
+    pub corrupted: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct SegmentInfo {
+    records: u64,
+    bytes: u64,
+}
+
+struct SpoolState {
+    segments: BTreeMap<u64, SegmentInfo>,
+    //segment being written, the others are sealed
+    active: Option<(u64, File)>,
+    next_seq: u64,
+}
+
+#[derive(Debug, Default)]
+struct SpoolCounters {
+    spooled: AtomicU64,
+    replayed: AtomicU64,
+    rejected: AtomicU64,
+    corrupted: AtomicU64,
+}
+
+#[derive(Clone)]
+pub struct HitSpool {
+    path: PathBuf,
+    segment_bytes: u64,
+    max_bytes: u64,
+    replay_interval: Duration,
+    state: Arc<Mutex<SpoolState>>,
+    counters: Arc<SpoolCounters>,
+}
+
+impl HitSpool {
+    ///
+    /// Opens the spool directory, the segments left by a previous run are replayed first.
+    ///
+    pub fn open(settings: &Spool) -> Result<Self> {
+        let path = PathBuf::from(&settings.path);
+
+        fs::create_dir_all(&path)?;
+
+        let mut segments = BTreeMap::new();
+
+        //corrupted records are counted when replayed
+        for seq in list_segments(&path)? {
+            let content = fs::read(segment_path(&path, seq))?;
+            let (hits, _) = decode_segment(&content);
+
+            segments.insert(
+                seq,
+                SegmentInfo {
+                    records: hits.len() as u64,
+                    bytes: content.len() as u64,
+                },
+            );
+        }
+
+        let next_seq = segments.keys().last().map_or(0, |seq| seq + 1);
+
+        info!(
+            "  hit spool -> {}, {} segments left",
+            settings.path,
+            segments.len()
+        );
+
+        Ok(Self {
+            path,
+            segment_bytes: settings.segment_bytes,
+            max_bytes: settings.max_bytes,
+            replay_interval: Duration::from_millis(settings.replay_millis),
+            state: Arc::new(Mutex::new(SpoolState {
+                segments,
+                active: None,
+                next_seq,
+            })),
+            counters: Arc::new(SpoolCounters::default()),
+        })
+    }
+
+    ///
+    /// Appends the hits and syncs them, the hits of a call share one sync. Blocks the thread, see
+    /// `spool` from async code.
+    ///
+    pub fn append(&self, hits: &[Hit]) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let mut rejected = 0;
+
+        for hit in hits {
+            let record = encode_record(hit)?;
+            let len = record.len() as u64;
+
+            let bytes: u64 = state.segments.values().map(|segment| segment.bytes).sum();
+
+            if bytes + len > self.max_bytes {
+                rejected += 1;
+                continue;
+            }
+
+            let rotate = match &state.active {
+                Some((seq, _)) => {
+                    let written = state.segments.get(seq).map_or(0, |segment| segment.bytes);
+
+                    written > 0 && written + len > self.segment_bytes
+                }
+                None => true,
+            };
+
+            if rotate {
+                self.seal(&mut state)?;
+
+                let seq = state.next_seq;
+                let file = OpenOptions::new()
+                    .create_new(true)
+                    .append(true)
+                    .open(segment_path(&self.path, seq))?;
+
+                state.next_seq += 1;
+                state.segments.insert(seq, SegmentInfo::default());
+                state.active = Some((seq, file));
+            }
+
+            let seq = match state.active.as_mut() {
+                Some((seq, file)) => {
+                    file.write_all(&record)?;
+                    *seq
+                }
+                None => return Err(Error::msg("the hit spool has no segment")),
+            };
+
+            if let Some(segment) = state.segments.get_mut(&seq) {
+                segment.records += 1;
+                segment.bytes += len;
+            }
+
+            self.counters.spooled.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some((_, file)) = &state.active {
+            file.sync_data()?;
+        }
+
+        if rejected > 0 {
+            self.counters
+                .rejected
+                .fetch_add(rejected, Ordering::Relaxed);
+
+            return Err(Error::msg(format!(
+                "the hit spool is full, {} hits were dropped",
+                rejected
+            )));
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Appends the hits on a blocking thread.
+    ///
+    pub async fn spool(&self, hits: Vec<Hit>) -> Result<()> {
+        let spool = self.clone();
+
+        tokio::task::spawn_blocking(move || spool.append(&hits)).await?
+    }
+
+    ///
+    /// Registers the spooled hits segment by segment, stops at the first batch that fails.
+    ///
+    pub async fn replay<R: HitRegistrar>(&self, registrar: &R, batch_size: usize) -> Result<u64> {
+        let mut replayed = 0;
+
+        while let Some(seq) = self.next_sealed()? {
+            let path = segment_path(&self.path, seq);
+            let (hits, corrupted) = read_segment(path.clone()).await?;
+
+            let count = hits.len() as u64;
+            let mut hits = hits.into_iter().peekable();
+
+            while hits.peek().is_some() {
+                let batch = hits.by_ref().take(batch_size.max(1)).collect();
+
+                registrar.register_batch(batch).await?;
+            }
+
+            let removed = path.clone();
+            tokio::task::spawn_blocking(move || fs::remove_file(removed)).await??;
+
+            self.state.lock().unwrap().segments.remove(&seq);
+            self.counters.replayed.fetch_add(count, Ordering::Relaxed);
+
+            if corrupted > 0 {
+                warn!("{} corrupted hits dropped from {:?}", corrupted, path);
+
+                self.counters
+                    .corrupted
+                    .fetch_add(corrupted, Ordering::Relaxed);
+            }
+
+            replayed += count;
+        }
+
+        Ok(replayed)
+    }
+
+    ///
+    /// Replays the spool to the registrar until the router exits.
+    ///
+    pub fn spawn_replay<R: HitRegistrar + Send + 'static>(&self, registrar: R, batch_size: usize) {
+        let spool = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(spool.replay_interval);
+
+            loop {
+                interval.tick().await;
+
+                match spool.replay(&registrar, batch_size).await {
+                    Ok(0) => {}
+                    Ok(replayed) => info!("{} spooled hits replayed", replayed),
+                    Err(error) => warn!("spooled hits not replayed yet: {}", error),
+                }
+            }
+        });
+    }
+
+    pub fn metrics(&self) -> SpoolMetrics {
+        let state = self.state.lock().unwrap();
+
+        SpoolMetrics {
+            segments: state.segments.len(),
+            bytes: state.segments.values().map(|segment| segment.bytes).sum(),
+            depth: state.segments.values().map(|segment| segment.records).sum(),
+            spooled: self.counters.spooled.load(Ordering::Relaxed),
+            replayed: self.counters.replayed.load(Ordering::Relaxed),
+            rejected: self.counters.rejected.load(Ordering::Relaxed),
+            corrupted: self.counters.corrupted.load(Ordering::Relaxed),
+        }
+    }
+
+    ///
+    /// Oldest sealed segment, seals the active one when it is the only one left.
+    ///
+    fn next_sealed(&self) -> Result<Option<u64>> {
+        let mut state = self.state.lock().unwrap();
+
+        let active = state.active.as_ref().map(|(seq, _)| *seq);
+
+        match state.segments.keys().find(|seq| Some(**seq) != active) {
+            Some(seq) => Ok(Some(*seq)),
+            None => match active {
+                Some(seq) if state.segments.get(&seq).map_or(0, |s| s.records) > 0 => {
+                    //already synced by its appends
+                    state.active = None;
+
+                    Ok(Some(seq))
+                }
+                _ => Ok(None),
+            },
+        }
+    }
+
+    fn seal(&self, state: &mut SpoolState) -> Result<()> {
+        if let Some((_, file)) = state.active.take() {
+            file.sync_data()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait()]
+impl Spill<Hit> for HitSpool {
+    async fn spill(&self, hit: Hit) -> Result<()> {
+        self.spool(vec![hit]).await
+    }
+}
+
+fn encode_record(hit: &Hit) -> Result<Vec<u8>> {
+    let payload = serde_json::to_vec(hit)?;
+
+    let mut record = Vec::with_capacity(HEADER_LEN + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    record.extend_from_slice(&payload);
+
+    Ok(record)
+}
+
+async fn read_segment(path: PathBuf) -> Result<(Vec<Hit>, u64)> {
+    let content = tokio::task::spawn_blocking(move || fs::read(path)).await??;
+
+    Ok(decode_segment(&content))
+}
+
+fn segment_path(path: &Path, seq: u64) -> PathBuf {
+    path.join(format!("{:020}.{}", seq, SEGMENT_EXTENSION))
+}
+
+fn list_segments(path: &Path) -> Result<Vec<u64>> {
+    let mut segments = Vec::new();
+
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+
+        if path.extension().and_then(|ext| ext.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+
+        if let Some(seq) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+        {
+            segments.push(seq);
+        }
+    }
+
+    segments.sort();
+
+    Ok(segments)
+}
+
+///
+/// Hits of a segment and the number of records dropped. A record failing its checksum ends the
+/// segment, its length can't be trusted, as does a record torn by a crash.
+///
+fn decode_segment(content: &[u8]) -> (Vec<Hit>, u64) {
+    let mut hits = Vec::new();
+    let mut corrupted = 0;
+    let mut offset = 0;
+
+    while offset < content.len() {
+        let header = match content.get(offset..offset + HEADER_LEN) {
+            Some(header) => header,
+            None => {
+                corrupted += 1;
+                break;
+            }
+        };
+
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let crc = u32::from_le_bytes(header[4..].try_into().unwrap());
+
+        let payload = match content.get(offset + HEADER_LEN..offset + HEADER_LEN + len) {
+            Some(payload) if crc32fast::hash(payload) == crc => payload,
+            _ => {
+                corrupted += 1;
+                break;
+            }
+        };
+
+        match serde_json::from_slice(payload) {
+            Ok(hit) => hits.push(hit),
+            Err(_) => corrupted += 1,
+        }
+
+        offset += HEADER_LEN + len;
+    }
+
+    (hits, corrupted)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use chrono::Utc;
+
+    use crate::model::hit::Click;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Broker {
+        down: AtomicBool,
+        hits: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait()]
+    impl HitRegistrar for Broker {
+        async fn register(&self, hit: Hit) -> Result<()> {
+            if self.down.load(Ordering::Relaxed) {
+                return Err(Error::msg("broker is down"));
+            }
+
+            self.hits.lock().unwrap().push(hit.id);
+
+            Ok(())
+        }
+    }
+
+    fn hit(id: &str) -> Hit {
+        Hit::click(
+            id.to_string(),
+            Utc::now(),
+            None,
+            None,
+            None,
+            Click::new("https://shop.com"),
+            None,
+        )
+    }
+
+    fn settings(dir: &tempfile::TempDir, segment_bytes: u64, max_bytes: u64) -> Spool {
+        Spool {
+            path: dir.path().to_string_lossy().to_string(),
+            segment_bytes,
+            max_bytes,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn should_replay_spooled_hits_once_the_broker_is_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = HitSpool::open(&settings(&dir, 300, 1_000_000)).unwrap();
+
+        for id in ["1", "2", "3", "4"] {
+            spool.append(&[hit(id)]).unwrap();
+        }
+
+        assert!(spool.metrics().segments > 1);
+        assert_eq!(spool.metrics().depth, 4);
+
+        let broker = Broker::default();
+        broker.down.store(true, Ordering::Relaxed);
+
+        assert!(spool.replay(&broker, 10).await.is_err());
+        assert_eq!(spool.metrics().depth, 4);
+
+        broker.down.store(false, Ordering::Relaxed);
+
+        assert_eq!(spool.replay(&broker, 10).await.unwrap(), 4);
+        assert_eq!(*broker.hits.lock().unwrap(), vec!["1", "2", "3", "4"]);
+        assert_eq!(spool.metrics().depth, 0);
+        assert_eq!(list_segments(dir.path()).unwrap(), Vec::<u64>::new());
+    }
+
+    #[tokio::test]
+    async fn should_drop_hits_beyond_the_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = HitSpool::open(&settings(&dir, 1_000_000, 200)).unwrap();
+
+        spool.append(&[hit("1")]).unwrap();
+
+        assert!(spool.spool(vec![hit("2"), hit("3")]).await.is_err());
+        assert_eq!(spool.metrics().rejected, 2);
+        assert_eq!(spool.metrics().depth, 1);
+    }
+
+    #[tokio::test]
+    async fn should_recover_the_segments_of_a_previous_run() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let spool = HitSpool::open(&settings(&dir, 1_000_000, 1_000_000)).unwrap();
+
+            spool.append(&[hit("1"), hit("2")]).unwrap();
+        }
+
+        //a crash tore the last record
+        let path = segment_path(dir.path(), 0);
+        let content = fs::read(&path).unwrap();
+        fs::write(&path, &content[..content.len() - 5]).unwrap();
+
+        let spool = HitSpool::open(&settings(&dir, 1_000_000, 1_000_000)).unwrap();
+
+        assert_eq!(spool.metrics().depth, 1);
+
+        spool.spool(vec![hit("3")]).await.unwrap();
+
+        let broker = Broker::default();
+
+        assert_eq!(spool.replay(&broker, 1).await.unwrap(), 2);
+        assert_eq!(*broker.hits.lock().unwrap(), vec!["1", "3"]);
+        assert_eq!(spool.metrics().corrupted, 1);
+    }
+}
@@ -1,7 +1,29 @@
 use crate::model::Hit;
-use anyhow::Result;
+use anyhow::{Error, Result};
 
 #[async_trait::async_trait()]
-pub trait HitRegistrar {
-    async fn register(&self, hit: &Hit) -> Result<()>;
+pub trait HitRegistrar: Sync {
+    async fn register(&self, hit: Hit) -> Result<()>;
+
+    ///
+    /// Registers every hit of the batch, fails when any of them failed.
+    ///
+    async fn register_batch(&self, hits: Vec<Hit>) -> Result<()> {
+        let count = hits.len();
+        let mut failed = 0;
+
+        for hit in hits {
+            if self.register(hit).await.is_err() {
+                failed += 1;
+            }
+        }
+
+        match failed {
+            0 => Ok(()),
+            _ => Err(Error::msg(format!(
+                "{} of {} hits were not registered",
+                failed, count
+            ))),
+        }
+    }
 }
@@ -0,0 +1,108 @@
+//!
+//! Evicts cached routes, user settings and certificates as soon as they change.
+//!
+use anyhow::Result;
+use tracing::{debug, warn};
+
+use crate::{
+    adapters::{CryptoCacheType, RoutesCacheType, UserSettingsCacheType},
+    model::ChangeEvent,
+};
+
+use super::{crypto::CryptoCache, routes::RoutesCache, user_settings::UserSettingsCache};
+
+#[async_trait::async_trait()]
+pub trait ChangeSubscriber {
+    /// Applies the change events of the stream until it ends.
+    async fn listen(&self, invalidator: Invalidator) -> Result<()>;
+}
+
+#[derive(Clone)]
+pub struct Invalidator {
+    routes_cache: RoutesCacheType,
+    user_settings_cache: UserSettingsCacheType,
+    crypto_cache: Option<CryptoCacheType>,
+}
+
+impl Invalidator {
+    pub fn new(
+        routes_cache: RoutesCacheType,
+        user_settings_cache: UserSettingsCacheType,
+        crypto_cache: Option<CryptoCacheType>,
+    ) -> Self {
+        Self {
+            routes_cache,
+            user_settings_cache,
+            crypto_cache,
+        }
+    }
+
+    pub async fn apply(&self, event: &ChangeEvent) -> Result<()> {
+        debug!("invalidating {:?}", event);
+
+        match event {
+            ChangeEvent::Route { switch, link } => self.routes_cache.invalidate(switch, link).await,
+            ChangeEvent::UserSettings { user_id } => {
+                self.user_settings_cache.invalidate(user_id).await
+            }
+            ChangeEvent::Certificate { server_name } => match &self.crypto_cache {
+                Some(crypto_cache) => crypto_cache.invalidate(server_name).await,
+                None => Ok(()),
+            },
+        }
+    }
+
+    ///
+    /// Applies a raw event, a malformed one is skipped so that the stream keeps going.
+    ///
+    pub async fn apply_payload(&self, payload: &[u8]) {
+        let result = match decode(payload) {
+            Ok(event) => self.apply(&event).await,
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = result {
+            warn!("change event skipped: {}", error);
+        }
+    }
+}
+
+pub fn decode(payload: &[u8]) -> Result<ChangeEvent> {
+    Ok(serde_json::from_slice(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_decode_change_events() {
+        assert_eq!(
+            decode(br#"{"entity":"route","switch":"main","link":"go.brand.com%2fpromo"}"#).unwrap(),
+            ChangeEvent::Route {
+                switch: "main".to_string(),
+                link: "go.brand.com%2fpromo".to_string()
+            }
+        );
+
+        assert_eq!(
+            decode(br#"{"entity":"user_settings","user_id":"u1"}"#).unwrap(),
+            ChangeEvent::UserSettings {
+                user_id: "u1".to_string()
+            }
+        );
+
+        assert_eq!(
+            decode(br#"{"entity":"certificate","server_name":"go.brand.com"}"#).unwrap(),
+            ChangeEvent::Certificate {
+                server_name: "go.brand.com".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_events() {
+        assert!(decode(br#"{"entity":"domain","name":"go.brand.com"}"#).is_err());
+        assert!(decode(b"go.brand.com").is_err());
+    }
+}
@@ -7,17 +7,65 @@ pub struct Country {
     pub iso_code: String,
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+
+    ///
+    /// Great-circle (haversine) distance between two points in kilometers.
+    ///
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let lat1 = self.latitude.to_radians();
+        let lat2 = other.latitude.to_radians();
+        let d_lat = (other.latitude - self.latitude).to_radians();
+        let d_lon = (other.longitude - self.longitude).to_radians();
+
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+
+        2.0 * Self::EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, Hash, PartialEq)]
+pub struct Asn {
+    pub number: u32,
+    pub organization: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Location {
     pub country: Option<Country>,
+    /// Two letter continent code, lowercase (`eu`, `na`, ...).
+    pub continent: Option<String>,
+    /// ISO 3166-2 code of the top level subdivision, lowercase (`us-ca`).
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub coordinates: Option<Coordinates>,
+    /// IANA time zone name (`Europe/Berlin`).
+    pub time_zone: Option<String>,
+    pub asn: Option<Asn>,
 }
 
 impl Default for Location {
     fn default() -> Self {
-        Self { country: None }
+        Self {
+            country: None,
+            continent: None,
+            region: None,
+            city: None,
+            coordinates: None,
+            time_zone: None,
+            asn: None,
+        }
     }
 }
 
 pub trait LocationDetector {
     fn detect_country(&self, ip_addr: &IpAddr) -> Option<Country>;
+    fn detect_location(&self, ip_addr: &IpAddr) -> Option<Location>;
 }
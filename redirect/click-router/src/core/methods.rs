@@ -0,0 +1,204 @@
+//!
+//! HTTP methods accepted by a route and its answer to CORS preflight requests.
+//!
+use http::{
+    header::{
+        ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+        ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ALLOW, ORIGIN, VARY,
+    },
+    HeaderMap, HeaderValue, Method,
+};
+
+use crate::model::route::RouteProperties;
+
+const ANY_ORIGIN: &'static str = "*";
+const PREFLIGHT_MAX_AGE: &'static str = "86400";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MethodPolicy {
+    methods: Vec<Method>,
+    cors_origins: Vec<String>,
+}
+
+impl MethodPolicy {
+    ///
+    /// GET, HEAD and OPTIONS are always accepted, unknown methods are ignored.
+    ///
+    pub fn of(properties: &RouteProperties) -> Self {
+        let mut methods = vec![Method::GET, Method::HEAD, Method::OPTIONS];
+
+        for method in properties.methods.iter().flatten() {
+            if let Ok(method) = Method::from_bytes(method.trim().to_ascii_uppercase().as_bytes()) {
+                if !methods.contains(&method) {
+                    methods.push(method);
+                }
+            }
+        }
+
+        Self {
+            methods,
+            cors_origins: properties.cors_origins.clone().unwrap_or_default(),
+        }
+    }
+
+    pub fn allows(&self, method: &Method) -> bool {
+        self.methods.contains(method)
+    }
+
+    pub fn allow_header(&self) -> String {
+        self.methods
+            .iter()
+            .map(|method| method.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn allowed_origin<'a>(&'a self, origin: &'a str) -> Option<&'a str> {
+        if self
+            .cors_origins
+            .iter()
+            .any(|allowed| allowed == ANY_ORIGIN)
+        {
+            return Some(ANY_ORIGIN);
+        }
+
+        self.cors_origins
+            .iter()
+            .find(|allowed| allowed.eq_ignore_ascii_case(origin))
+            .map(|_| origin)
+    }
+
+    ///
+    /// Headers of a rejected method, see RFC 9110 `405 Method Not Allowed`.
+    ///
+    pub fn not_allowed(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        if let Ok(allow) = HeaderValue::from_str(&self.allow_header()) {
+            headers.insert(ALLOW, allow);
+        }
+
+        headers
+    }
+
+    ///
+    /// Headers of an OPTIONS answer, with the CORS ones when the origin is allowed.
+    ///
+    pub fn preflight(&self, request: &HeaderMap) -> HeaderMap {
+        let mut headers = self.not_allowed();
+
+        let origin = request
+            .get(ORIGIN)
+            .and_then(|origin| origin.to_str().ok())
+            .and_then(|origin| self.allowed_origin(origin))
+            .and_then(|origin| HeaderValue::from_str(origin).ok());
+
+        if let Some(origin) = origin {
+            if origin != ANY_ORIGIN {
+                headers.insert(VARY, HeaderValue::from_static("Origin"));
+            }
+
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+
+            if let Some(methods) = headers.get(ALLOW).cloned() {
+                headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+            }
+
+            if let Some(requested) = request.get(ACCESS_CONTROL_REQUEST_HEADERS) {
+                headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, requested.clone());
+            }
+
+            headers.insert(
+                ACCESS_CONTROL_MAX_AGE,
+                HeaderValue::from_static(PREFLIGHT_MAX_AGE),
+            );
+        }
+
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(methods: &[&str], cors_origins: &[&str]) -> MethodPolicy {
+        MethodPolicy::of(&RouteProperties {
+            methods: Some(methods.iter().map(|method| method.to_string()).collect()),
+            cors_origins: Some(
+                cors_origins
+                    .iter()
+                    .map(|origin| origin.to_string())
+                    .collect(),
+            ),
+            ..Default::default()
+        })
+    }
+
+    fn request(origin: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(ORIGIN, HeaderValue::from_str(origin).unwrap());
+        headers.insert(
+            ACCESS_CONTROL_REQUEST_HEADERS,
+            HeaderValue::from_static("content-type"),
+        );
+
+        headers
+    }
+
+    #[test]
+    fn should_allow_safe_methods_by_default() {
+        let policy = MethodPolicy::of(&RouteProperties::default());
+
+        assert!(policy.allows(&Method::GET));
+        assert!(policy.allows(&Method::HEAD));
+        assert!(policy.allows(&Method::OPTIONS));
+        assert!(!policy.allows(&Method::POST));
+        assert_eq!(policy.allow_header(), "GET, HEAD, OPTIONS");
+    }
+
+    #[test]
+    fn should_allow_route_methods() {
+        let policy = policy(&["post", "GET", "not a method"], &[]);
+
+        assert!(policy.allows(&Method::POST));
+        assert!(!policy.allows(&Method::PUT));
+        assert_eq!(policy.allow_header(), "GET, HEAD, OPTIONS, POST");
+        assert_eq!(policy.not_allowed()[ALLOW], "GET, HEAD, OPTIONS, POST");
+    }
+
+    #[test]
+    fn should_answer_preflight_of_allowed_origin() {
+        let shop = policy(&["POST"], &["https://shop.example.com"]);
+
+        let headers = shop.preflight(&request("https://shop.example.com"));
+
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://shop.example.com"
+        );
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_METHODS],
+            "GET, HEAD, OPTIONS, POST"
+        );
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_HEADERS], "content-type");
+        assert_eq!(headers[VARY], "Origin");
+
+        assert_eq!(
+            policy(&[], &["*"]).preflight(&request("https://other.com"))
+                [ACCESS_CONTROL_ALLOW_ORIGIN],
+            "*"
+        );
+    }
+
+    #[test]
+    fn should_not_answer_cors_of_other_origin() {
+        let policy = policy(&["POST"], &["https://shop.example.com"]);
+
+        let headers = policy.preflight(&request("https://evil.com"));
+
+        assert_eq!(headers[ALLOW], "GET, HEAD, OPTIONS, POST");
+        assert!(headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+}
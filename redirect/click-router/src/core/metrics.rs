@@ -0,0 +1,429 @@
+//!
+//! Prometheus metrics of the router, served by the admin listener, telling apart the time spent
+//! in the modules, the stores, GeoIP and the hit stream.
+//!
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use http::StatusCode;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    Encoder, HistogramOpts, HistogramTimer, HistogramVec, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::{
+    adapters::{
+        moka::swr_cache::CacheMetrics, CryptoCacheType, HitRegistrarType, RoutesCacheType,
+        UserSettingsCacheType,
+    },
+    settings::Admin,
+};
+
+const NAMESPACE: &'static str = "click_router";
+/// Label of the TLS handshakes without a server name.
+const NO_SERVER_NAME: &'static str = "none";
+/// Label of the server names beyond `max_server_names`.
+const OTHER_SERVER_NAMES: &'static str = "other";
+
+/// Redirects are answered in a few milliseconds, a store miss in tens of them.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    module_duration: HistogramVec,
+    dependency_duration: HistogramVec,
+    tls_handshakes: IntCounterVec,
+    server_names: Arc<Mutex<HashSet<String>>>,
+    max_server_names: usize,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new(&Admin::default())
+    }
+}
+
+impl Debug for Metrics {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Metrics").finish_non_exhaustive()
+    }
+}
+
+impl Metrics {
+    pub fn new(settings: &Admin) -> Self {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)
+            .expect("Invalid metrics namespace");
+
+        let requests = counter(
+            "requests_total",
+            "Requests answered, by result and status.",
+            &["result", "status"],
+        );
+        let request_duration = histogram(
+            "request_duration_seconds",
+            "Time to build the result of the requests, by result and status.",
+            &["result", "status"],
+        );
+        let module_duration = histogram(
+            "module_duration_seconds",
+            "Time spent in the flow modules, by module and flow step.",
+            &["module", "step"],
+        );
+        let dependency_duration = histogram(
+            "dependency_duration_seconds",
+            "Time spent in the stores and detectors, e.g. dynamo and geoip, by operation.",
+            &["dependency", "operation"],
+        );
+        let tls_handshakes = counter(
+            "tls_handshakes_total",
+            "TLS handshakes, by server name.",
+            &["server_name"],
+        );
+
+        for collector in [
+            Box::new(requests.clone()) as Box<dyn Collector>,
+            Box::new(request_duration.clone()),
+            Box::new(module_duration.clone()),
+            Box::new(dependency_duration.clone()),
+            Box::new(tls_handshakes.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("Failed to register the router metrics");
+        }
+
+        Self {
+            registry,
+            requests,
+            request_duration,
+            module_duration,
+            dependency_duration,
+            tls_handshakes,
+            server_names: Arc::new(Mutex::new(HashSet::new())),
+            max_server_names: settings.max_server_names,
+        }
+    }
+
+    pub fn observe_request(&self, result: &str, status: StatusCode, duration: Duration) {
+        let labels = [result, status.as_str()];
+
+        self.requests.with_label_values(&labels).inc();
+        self.request_duration
+            .with_label_values(&labels)
+            .observe(duration.as_secs_f64());
+    }
+
+    ///
+    /// Observes the time spent in a module when dropped.
+    ///
+    pub fn module_timer(&self, module: &str, step: &str) -> HistogramTimer {
+        self.module_duration
+            .with_label_values(&[module, step])
+            .start_timer()
+    }
+
+    ///
+    /// Observes the time spent in a store or detector when dropped.
+    ///
+    pub fn dependency_timer(&self, dependency: &str, operation: &str) -> HistogramTimer {
+        self.dependency_duration
+            .with_label_values(&[dependency, operation])
+            .start_timer()
+    }
+
+    ///
+    /// Counts a handshake, the server names are sent by the clients so only the first
+    /// `max_server_names` get their own label.
+    ///
+    pub fn tls_handshake(&self, server_name: Option<&str>) {
+        let server_name = match server_name {
+            Some(server_name) => server_name.to_ascii_lowercase(),
+            None => NO_SERVER_NAME.to_string(),
+        };
+
+        let labeled = {
+            let mut server_names = self.server_names.lock().unwrap();
+
+            server_names.contains(&server_name)
+                || (server_names.len() < self.max_server_names
+                    && server_names.insert(server_name.clone()))
+        };
+
+        let label = match labeled {
+            true => server_name.as_str(),
+            false => OTHER_SERVER_NAMES,
+        };
+
+        self.tls_handshakes.with_label_values(&[label]).inc();
+    }
+
+    pub fn register(&self, collector: impl Collector + 'static) -> Result<()> {
+        self.registry.register(Box::new(collector))?;
+
+        Ok(())
+    }
+
+    ///
+    /// Metrics in the Prometheus text format, along with its content type.
+    ///
+    pub fn render(&self) -> Result<(String, String)> {
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+
+        encoder.encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok((String::from_utf8(buffer)?, encoder.format_type().to_string()))
+    }
+}
+
+fn counter(name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    IntCounterVec::new(Opts::new(name, help), labels).expect("Invalid counter")
+}
+
+fn histogram(name: &str, help: &str, labels: &[&str]) -> HistogramVec {
+    HistogramVec::new(
+        HistogramOpts::new(name, help).buckets(LATENCY_BUCKETS.to_vec()),
+        labels,
+    )
+    .expect("Invalid histogram")
+}
+
+fn gauge(name: &str, help: &str) -> IntGauge {
+    IntGauge::with_opts(Opts::new(name, help)).expect("Invalid gauge")
+}
+
+///
+/// Counters kept by the caches and the hit queue themselves, read when scraped.
+///
+pub struct StateCollector {
+    routes_cache: RoutesCacheType,
+    user_settings_cache: UserSettingsCacheType,
+    crypto_cache: Option<CryptoCacheType>,
+    hit_registrar: HitRegistrarType,
+    cache_lookups: IntCounterVec,
+    cache_loads: IntCounterVec,
+    cache_evictions: IntCounterVec,
+    cache_refreshes: IntCounterVec,
+    queue_depth: IntGauge,
+    queue_hits: IntCounterVec,
+    spool_depth: IntGauge,
+    spool_bytes: IntGauge,
+    spool_hits: IntCounterVec,
+    //collecting resets the counters, concurrent scrapes must not interleave
+    collecting: Mutex<()>,
+}
+
+impl StateCollector {
+    pub fn new(
+        routes_cache: RoutesCacheType,
+        user_settings_cache: UserSettingsCacheType,
+        crypto_cache: Option<CryptoCacheType>,
+        hit_registrar: HitRegistrarType,
+    ) -> Self {
+        Self {
+            routes_cache,
+            user_settings_cache,
+            crypto_cache,
+            hit_registrar,
+            cache_lookups: counter(
+                "cache_lookups_total",
+                "Cache lookups, by cache and result: hit, stale or miss.",
+                &["cache", "result"],
+            ),
+            cache_loads: counter(
+                "cache_loads_total",
+                "Store loads of the cache misses, by cache.",
+                &["cache"],
+            ),
+            cache_evictions: counter(
+                "cache_evictions_total",
+                "Entries evicted to stay within the cache capacity, by cache.",
+                &["cache"],
+            ),
+            cache_refreshes: counter(
+                "cache_refreshes_total",
+                "Background refreshes of the expired entries, by cache and result.",
+                &["cache", "result"],
+            ),
+            queue_depth: gauge("hit_queue_depth", "Hits waiting to be registered."),
+            queue_hits: counter(
+                "hit_queue_hits_total",
+                "Hits of the queue, by outcome: enqueued, dropped, spilled, processed or failed.",
+                &["outcome"],
+            ),
+            spool_depth: gauge("hit_spool_depth", "Spooled hits waiting to be replayed."),
+            spool_bytes: gauge("hit_spool_bytes", "Size of the spool segment files."),
+            spool_hits: counter(
+                "hit_spool_hits_total",
+                "Hits of the spool, by outcome: spooled, replayed, rejected or corrupted.",
+                &["outcome"],
+            ),
+            collecting: Mutex::new(()),
+        }
+    }
+
+    fn caches(&self) -> Vec<(&'static str, CacheMetrics)> {
+        let mut caches = vec![
+            ("routes", self.routes_cache.metrics()),
+            ("route_patterns", self.routes_cache.patterns_metrics()),
+            ("user_settings", self.user_settings_cache.metrics()),
+        ];
+
+        if let Some(crypto_cache) = &self.crypto_cache {
+            caches.push(("crypto", crypto_cache.metrics()));
+        }
+
+        caches
+    }
+
+    fn counters(&self) -> [&IntCounterVec; 6] {
+        [
+            &self.cache_lookups,
+            &self.cache_loads,
+            &self.cache_evictions,
+            &self.cache_refreshes,
+            &self.queue_hits,
+            &self.spool_hits,
+        ]
+    }
+
+    fn gauges(&self) -> [&IntGauge; 3] {
+        [&self.queue_depth, &self.spool_depth, &self.spool_bytes]
+    }
+}
+
+impl Collector for StateCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.counters()
+            .into_iter()
+            .flat_map(|counter| counter.desc())
+            .chain(self.gauges().into_iter().flat_map(|gauge| gauge.desc()))
+            .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let _collecting = self.collecting.lock().unwrap();
+
+        self.counters().into_iter().for_each(IntCounterVec::reset);
+
+        for (cache, metrics) in self.caches() {
+            for (result, count) in [
+                ("hit", metrics.hits),
+                ("stale", metrics.stale_hits),
+                ("miss", metrics.misses),
+            ] {
+                self.cache_lookups
+                    .with_label_values(&[cache, result])
+                    .inc_by(count);
+            }
+
+            let refreshed = metrics.refreshes.saturating_sub(metrics.failed_refreshes);
+
+            for (result, count) in [("ok", refreshed), ("failed", metrics.failed_refreshes)] {
+                self.cache_refreshes
+                    .with_label_values(&[cache, result])
+                    .inc_by(count);
+            }
+
+            self.cache_loads
+                .with_label_values(&[cache])
+                .inc_by(metrics.loads);
+            self.cache_evictions
+                .with_label_values(&[cache])
+                .inc_by(metrics.evictions);
+        }
+
+        if let Some(queue) = self.hit_registrar.queue_metrics() {
+            self.queue_depth.set(queue.depth as i64);
+
+            for (outcome, count) in [
+                ("enqueued", queue.enqueued),
+                ("dropped", queue.dropped),
+                ("spilled", queue.spilled),
+                ("processed", queue.processed),
+                ("failed", queue.failed),
+            ] {
+                self.queue_hits.with_label_values(&[outcome]).inc_by(count);
+            }
+        }
+
+        if let Some(spool) = self.hit_registrar.spool_metrics() {
+            self.spool_depth.set(spool.depth as i64);
+            self.spool_bytes.set(spool.bytes as i64);
+
+            for (outcome, count) in [
+                ("spooled", spool.spooled),
+                ("replayed", spool.replayed),
+                ("rejected", spool.rejected),
+                ("corrupted", spool.corrupted),
+            ] {
+                self.spool_hits.with_label_values(&[outcome]).inc_by(count);
+            }
+        }
+
+        self.counters()
+            .into_iter()
+            .flat_map(|counter| counter.collect())
+            .chain(self.gauges().into_iter().flat_map(|gauge| gauge.collect()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(max_server_names: usize) -> Metrics {
+        Metrics::new(&Admin {
+            max_server_names,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn should_render_the_request_metrics() {
+        let metrics = metrics(10);
+
+        metrics.observe_request(
+            "redirect",
+            StatusCode::TEMPORARY_REDIRECT,
+            Duration::from_millis(2),
+        );
+        drop(metrics.module_timer("conditional", "url_extract"));
+
+        let (text, content_type) = metrics.render().unwrap();
+
+        assert!(content_type.starts_with("text/plain"));
+        assert!(text.contains(r#"click_router_requests_total{result="redirect",status="307"} 1"#));
+        assert!(text.contains(
+            r#"click_router_module_duration_seconds_count{module="conditional",step="url_extract"} 1"#
+        ));
+    }
+
+    #[test]
+    fn should_bound_the_server_name_labels() {
+        let metrics = metrics(1);
+
+        metrics.tls_handshake(Some("Go.Brand.com"));
+        metrics.tls_handshake(Some("go.brand.com"));
+        metrics.tls_handshake(Some("random.example"));
+        metrics.tls_handshake(None);
+
+        let (text, _) = metrics.render().unwrap();
+
+        assert!(text.contains(r#"click_router_tls_handshakes_total{server_name="go.brand.com"} 2"#));
+        assert!(text.contains(r#"click_router_tls_handshakes_total{server_name="other"} 2"#));
+    }
+}
@@ -3,19 +3,36 @@ pub mod crypto;
 pub mod routes;
 pub mod user_settings;
 
+pub mod click_id;
+pub mod conversion;
+pub mod device_class;
 pub mod expression;
+pub mod expression_plan;
+pub mod explain;
+pub mod fallback;
+pub mod flow_error;
 pub mod flow_module;
 pub mod flow_router;
 pub mod host;
+pub mod invalidation;
 pub mod ip;
 pub mod language;
+pub mod methods;
 pub mod modules;
+pub mod preview;
 pub mod protocol;
+pub mod qr;
+pub mod route_patterns;
 pub mod user_agent;
 pub mod user_agent_string;
+pub mod version;
+pub mod visitor;
 
+pub mod hit_queue;
+pub mod hit_spool;
 pub mod hits_register;
 pub mod location;
+pub mod metrics;
 
 pub use crypto::CryptoStore;
 pub use routes::RoutesStore;
@@ -47,4 +64,8 @@ impl<T> InitOnce<T> {
     pub fn get_value(self) -> T {
         self.value
     }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
 }
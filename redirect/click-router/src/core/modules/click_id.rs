@@ -0,0 +1,59 @@
+use anyhow::{Ok, Result};
+
+use crate::core::{
+    click_id::{click_id_cookie, with_click_id},
+    flow_module::{FlowModule, FlowStepContinuation},
+    flow_router::{FlowRouter, FlowRouterContext, Response, HIT_REGISTERED},
+};
+
+#[derive(Clone)]
+pub struct ClickIdModule {}
+
+impl ClickIdModule {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait::async_trait()]
+impl FlowModule for ClickIdModule {
+    async fn handle_build_result(
+        &self,
+        context: &mut FlowRouterContext,
+        flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        //the ids of unregistered clicks would not convert
+        if !context.is_data_true(HIT_REGISTERED) {
+            return Ok(FlowStepContinuation::Continue);
+        }
+
+        let param = match context
+            .main_route
+            .as_ref()
+            .and_then(|route| route.properties.click_id.clone())
+        {
+            Some(param) => param,
+            None => return Ok(FlowStepContinuation::Continue),
+        };
+
+        if let Some(route) = context.out_route.as_mut() {
+            route.dest = route
+                .dest
+                .as_deref()
+                .map(|dest| with_click_id(dest, &param.name, &context.id));
+        }
+
+        if param.cookie && flow_router.are_cookies_allowed(context).await? {
+            let secure = context
+                .protocol
+                .as_ref()
+                .is_some_and(|protocol| protocol.ssl_on);
+
+            context
+                .response
+                .add_cookie(click_id_cookie(&param.name, &context.id, secure));
+        }
+
+        Ok(FlowStepContinuation::Continue)
+    }
+}
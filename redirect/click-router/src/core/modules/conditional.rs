@@ -1,6 +1,8 @@
 use crate::{
     core::{
+        explain::ExplainCondition,
         expression::ExpressionEvaluator,
+        expression_plan::{Requirements, ScheduleZone},
         flow_module::{FlowModule, FlowStepContinuation},
         flow_router::{FlowRouter, FlowRouterContext},
     },
@@ -30,42 +32,49 @@ impl FlowModule for ConditionalModule {
         context: &mut FlowRouterContext,
         router: &FlowRouter,
     ) -> Result<FlowStepContinuation> {
-        if context.main_route.is_none() {
-            return Ok(FlowStepContinuation::Continue);
-        }
+        let route = match context.main_route.as_ref() {
+            Some(route) => route,
+            None => return Ok(FlowStepContinuation::Continue),
+        };
 
-        //preload heavy stuff if needed
-        if let RoutingPolicy::Conditional(conditions) =
-            &context.main_route.as_ref().unwrap().policy.clone()
-        {
-            if conditions
-                .iter()
-                .any(|routing| routing.condition.needs_ua())
-            {
-                router.load_ua(context);
-            }
-            if conditions
-                .iter()
-                .any(|routing| routing.condition.needs_os())
-            {
-                router.load_os(context);
-            }
-            if conditions
-                .iter()
-                .any(|routing| routing.condition.needs_device())
-            {
-                router.load_device(context);
-            }
-            if conditions
-                .iter()
-                .any(|routing| routing.condition.needs_country())
-            {
-                router.load_country(context);
+        let requirements = match &route.policy {
+            RoutingPolicy::Conditional(conditions) => {
+                conditions
+                    .iter()
+                    .fold(Requirements::default(), |mut requirements, routing| {
+                        requirements.merge(&Requirements::of(routing));
+                        requirements
+                    })
             }
+            _ => return Ok(FlowStepContinuation::Continue),
+        };
 
-            //println!("IS_CONDITIONAL");
-            context.add_bool(IS_CONDITIONAL, true);
+        //schedules in the visitor time zone need the GeoIP time zone
+        let needs_time_zone =
+            requirements.time && ScheduleZone::of(&route.properties).needs_location();
+
+        //preload heavy stuff if needed
+        if requirements.ua {
+            router.load_ua(context);
+        }
+        if requirements.os {
+            router.load_os(context);
+        }
+        if requirements.device {
+            router.load_device(context);
+        }
+        if requirements.device_class {
+            router.load_device_class(context);
         }
+        if requirements.location || needs_time_zone {
+            router.load_location(context);
+        }
+        if requirements.country {
+            router.load_country(context);
+        }
+
+        //println!("IS_CONDITIONAL");
+        context.add_bool(IS_CONDITIONAL, true);
 
         return Ok(FlowStepContinuation::Continue);
     }
@@ -75,15 +84,42 @@ impl FlowModule for ConditionalModule {
         context: &mut FlowRouterContext,
         flow_router: &FlowRouter,
     ) -> Result<FlowStepContinuation> {
-        if let RoutingPolicy::Conditional(conditions) = &context.main_route.as_ref().unwrap().policy
-        {
-            if let Some(matching) = &self.evaluator.find(context, conditions) {
+        let route = match context.main_route.as_ref() {
+            Some(route) => route,
+            None => return Ok(FlowStepContinuation::Continue),
+        };
+
+        if let RoutingPolicy::Conditional(conditions) = &route.policy {
+            let zone = ScheduleZone::of(&route.properties);
+
+            if context.explain.is_some() {
+                let results = self.evaluator.eval_all(context, conditions, &zone);
+
+                if let Some(explain) = context.explain.as_mut() {
+                    explain.conditions = conditions
+                        .iter()
+                        .zip(results)
+                        .map(|(routing, matched)| ExplainCondition {
+                            key: routing.key.clone(),
+                            condition: routing.condition.to_string(),
+                            matched,
+                        })
+                        .collect();
+                }
+            }
+
+            if let Some(matching) = &self.evaluator.find(context, conditions, &zone) {
                 let out_route = flow_router
                     .get_route(matching.key.as_str(), context)
                     .await?;
 
                 if let Some(route) = out_route {
+                    if let Some(explain) = context.explain.as_mut() {
+                        explain.switch = Some(matching.key.clone());
+                    }
+
                     context.out_route = Some(route);
+                    context.condition = Some(matching.key.clone());
 
                     return Ok(FlowStepContinuation::Continue);
                 }
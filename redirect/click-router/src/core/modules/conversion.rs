@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use http::StatusCode;
+use tracing::warn;
+
+use crate::{
+    core::{
+        conversion::{
+            verify_signature, ClickRef, ClickRegistry, Conversion, PIXEL, PIXEL_CONTENT_TYPE,
+            SIGNATURE_PARAM,
+        },
+        flow_module::{FlowModule, FlowStepContinuation},
+        flow_router::{FlowRouter, FlowRouterContext, FlowRouterResult, Request},
+    },
+    model::{hit::HitRoute, Hit},
+    settings::Conversions,
+};
+
+#[derive(Clone)]
+pub struct ConversionModule {
+    path: String,
+    postback_max_age: Duration,
+    clicks: ClickRegistry,
+}
+
+impl ConversionModule {
+    pub fn new(settings: &Conversions, clicks: ClickRegistry) -> Self {
+        Self {
+            path: settings.path.clone(),
+            postback_max_age: Duration::from_secs(settings.postback_max_age_secs),
+            clicks,
+        }
+    }
+
+    ///
+    /// Registers the conversion of the request, answers the status of a postback: accepted when
+    /// registered, ok when the click already converted with this name.
+    ///
+    async fn convert(
+        &self,
+        context: &mut FlowRouterContext<'_>,
+        flow_router: &FlowRouter,
+        postback: bool,
+    ) -> Result<StatusCode> {
+        let Some(conversion) = Conversion::parse(context.request.queries()) else {
+            return Ok(StatusCode::BAD_REQUEST);
+        };
+
+        let Some(click) = self.clicks.find(&conversion.click).await else {
+            return Ok(StatusCode::NOT_FOUND);
+        };
+
+        if postback {
+            let owner_id = click
+                .route
+                .as_ref()
+                .and_then(|route| route.owner_id.as_deref());
+
+            let api_key = match owner_id {
+                Some(owner_id) => flow_router
+                    .get_user_settings(owner_id)
+                    .await?
+                    .and_then(|settings| settings.api_key),
+                None => None,
+            };
+
+            let query = context.request.uri().query().unwrap_or_default();
+
+            if !api_key.is_some_and(|api_key| verify_signature(query, &api_key)) {
+                return Ok(StatusCode::FORBIDDEN);
+            }
+
+            //the signature covers the ts, a captured postback is only valid for a short while
+            if !conversion.is_fresh(context.utc, self.postback_max_age) {
+                return Ok(StatusCode::FORBIDDEN);
+            }
+        }
+
+        //retried postbacks and reloaded pixels are answered without being registered again
+        match self.clicks.first_conversion(&conversion).await {
+            Ok(true) => {}
+            Ok(false) => return Ok(StatusCode::OK),
+            Err(error) => {
+                warn!(
+                    "conversion of click '{}' dropped: {:#}",
+                    conversion.click, error
+                );
+
+                //the servers of the owner retry the postback, a pixel is not loaded again
+                return Ok(match postback {
+                    true => StatusCode::SERVICE_UNAVAILABLE,
+                    false => StatusCode::OK,
+                });
+            }
+        }
+
+        //postbacks come from the servers of the owner, not from the visitor
+        let hit = match postback {
+            true => Hit::event(
+                context.id.clone(),
+                context.utc,
+                None,
+                None,
+                None,
+                conversion.to_event(true),
+                click.route,
+            ),
+            false => {
+                flow_router.load_location(context);
+
+                Hit::event(
+                    context.id.clone(),
+                    context.utc,
+                    context.user_agent.clone(),
+                    context.client_ip.as_ref().map(|ip| ip.address),
+                    context.client_location.value().clone(),
+                    conversion.to_event(false),
+                    click.route,
+                )
+            }
+        };
+
+        flow_router.register_hit(hit).await;
+
+        Ok(StatusCode::ACCEPTED)
+    }
+}
+
+#[async_trait::async_trait()]
+impl FlowModule for ConversionModule {
+    async fn init(
+        &self,
+        context: &mut FlowRouterContext,
+        flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        if context.request.uri().path() != self.path {
+            return Ok(FlowStepContinuation::Continue);
+        }
+
+        let postback = context.request.queries().get(SIGNATURE_PARAM).is_some();
+        let status = self.convert(context, flow_router, postback).await?;
+
+        //pixels never break the page that loads them
+        context.result = Some(match postback {
+            true => FlowRouterResult::Empty(status),
+            false => FlowRouterResult::Binary(PIXEL.to_vec(), PIXEL_CONTENT_TYPE, StatusCode::OK),
+        });
+
+        Ok(FlowStepContinuation::Break)
+    }
+
+    async fn handle_register(
+        &self,
+        context: &mut FlowRouterContext,
+        _flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        //only the clicks registered as hits convert
+        let registered = context
+            .out_route
+            .as_ref()
+            .is_some_and(|route| route.dest.is_some());
+
+        if registered {
+            let click = ClickRef {
+                route: HitRoute::from_route(&context.main_route),
+                utc: context.utc,
+            };
+
+            self.clicks.remember(&context.id, click).await;
+        }
+
+        Ok(FlowStepContinuation::Continue)
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::{Ok, Result};
+use http::{Method, StatusCode};
+
+use crate::core::{
+    flow_module::{FlowModule, FlowStepContinuation},
+    flow_router::{FlowRouter, FlowRouterContext, FlowRouterResult, FlowStep, Request},
+    methods::MethodPolicy,
+};
+
+#[derive(Clone)]
+pub struct MethodsModule {}
+
+impl MethodsModule {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait::async_trait()]
+impl FlowModule for MethodsModule {
+    async fn handle_start(
+        &self,
+        context: &mut FlowRouterContext,
+        flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        //unknown links are answered by the not found module
+        let route = match context.main_route.as_ref() {
+            Some(route) => route,
+            None => return Ok(FlowStepContinuation::Continue),
+        };
+
+        let policy = MethodPolicy::of(&route.properties);
+        let method = context.request.method();
+
+        let result = if method == Method::OPTIONS {
+            FlowRouterResult::Headers(
+                policy.preflight(context.request.headers()),
+                StatusCode::NO_CONTENT,
+            )
+        } else if !policy.allows(method) {
+            FlowRouterResult::Headers(policy.not_allowed(), StatusCode::METHOD_NOT_ALLOWED)
+        } else {
+            return Ok(FlowStepContinuation::Continue);
+        };
+
+        //neither is a click, no hit is registered
+        context.result = Some(result);
+
+        flow_router.router_to(context, FlowStep::End).await?;
+
+        Ok(FlowStepContinuation::Break)
+    }
+}
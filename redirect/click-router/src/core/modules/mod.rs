@@ -1,23 +1,49 @@
+use std::str::FromStr;
+
 use anyhow::Result;
+use click_id::ClickIdModule;
 use conditional::ConditionalModule;
+use conversion::ConversionModule;
+use http::Uri;
+use methods::MethodsModule;
 use not_found::NotFoundModule;
+use preview::PreviewModule;
+use qr::QrModule;
 use redirect_only::RedirectOnlyModule;
 use root::RootModule;
+use string_format::*;
+use visitor::VisitorModule;
 
 use super::{
+    flow_error::FlowError,
     flow_module::{FlowModule, FlowStepContinuation},
     flow_router::{FlowRouter, FlowRouterContext},
 };
 
+pub mod click_id;
 pub mod redirect_only;
 // pub mod abuse_module;
 // pub mod full_path_module;
 pub mod conditional;
+pub mod conversion;
+pub mod methods;
 pub mod not_found;
 // pub mod open_graph_module;
 // pub mod paused_module;
+pub mod preview;
+pub mod qr;
 // pub mod robots_module;
 pub mod root;
+pub mod visitor;
+
+///
+/// Builds the URI of a configured page (root, not found...) for the requested host.
+///
+fn template_uri(template: &str, host: &str) -> Result<Uri, FlowError> {
+    let uri = string_format!(template.to_string(), host.to_string());
+
+    Uri::from_str(&uri).map_err(|_| FlowError::InvalidUrl(uri))
+}
 
 #[derive(Clone)]
 pub enum FlowModules {
@@ -25,6 +51,29 @@ pub enum FlowModules {
     Conditional(ConditionalModule),
     NotFound(NotFoundModule),
     RedirectOnly(RedirectOnlyModule),
+    Methods(MethodsModule),
+    Qr(QrModule),
+    Preview(PreviewModule),
+    ClickId(ClickIdModule),
+    Visitor(VisitorModule),
+    Conversion(ConversionModule),
+}
+
+impl FlowModules {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlowModules::Root(_) => "root",
+            FlowModules::Conditional(_) => "conditional",
+            FlowModules::NotFound(_) => "not_found",
+            FlowModules::RedirectOnly(_) => "redirect_only",
+            FlowModules::Methods(_) => "methods",
+            FlowModules::Qr(_) => "qr",
+            FlowModules::Preview(_) => "preview",
+            FlowModules::ClickId(_) => "click_id",
+            FlowModules::Visitor(_) => "visitor",
+            FlowModules::Conversion(_) => "conversion",
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -36,9 +85,15 @@ impl FlowModule for FlowModules {
     ) -> Result<FlowStepContinuation> {
         match self {
             FlowModules::Root(module) => module.init(context, flow_router).await,
+            FlowModules::Methods(module) => module.init(context, flow_router).await,
+            FlowModules::Qr(module) => module.init(context, flow_router).await,
+            FlowModules::Preview(module) => module.init(context, flow_router).await,
             FlowModules::Conditional(module) => module.init(context, flow_router).await,
             FlowModules::NotFound(module) => module.init(context, flow_router).await,
             FlowModules::RedirectOnly(module) => module.init(context, flow_router).await,
+            FlowModules::ClickId(module) => module.init(context, flow_router).await,
+            FlowModules::Visitor(module) => module.init(context, flow_router).await,
+            FlowModules::Conversion(module) => module.init(context, flow_router).await,
         }
     }
 
@@ -49,9 +104,15 @@ impl FlowModule for FlowModules {
     ) -> Result<FlowStepContinuation> {
         match self {
             FlowModules::Root(module) => module.handle_start(context, flow_router).await,
+            FlowModules::Methods(module) => module.handle_start(context, flow_router).await,
+            FlowModules::Qr(module) => module.handle_start(context, flow_router).await,
+            FlowModules::Preview(module) => module.handle_start(context, flow_router).await,
             FlowModules::Conditional(module) => module.handle_start(context, flow_router).await,
             FlowModules::NotFound(module) => module.handle_start(context, flow_router).await,
             FlowModules::RedirectOnly(module) => module.handle_start(context, flow_router).await,
+            FlowModules::ClickId(module) => module.handle_start(context, flow_router).await,
+            FlowModules::Visitor(module) => module.handle_start(context, flow_router).await,
+            FlowModules::Conversion(module) => module.handle_start(context, flow_router).await,
         }
     }
 
@@ -62,6 +123,9 @@ impl FlowModule for FlowModules {
     ) -> Result<FlowStepContinuation> {
         match self {
             FlowModules::Root(module) => module.handle_url_extract(context, flow_router).await,
+            FlowModules::Methods(module) => module.handle_url_extract(context, flow_router).await,
+            FlowModules::Qr(module) => module.handle_url_extract(context, flow_router).await,
+            FlowModules::Preview(module) => module.handle_url_extract(context, flow_router).await,
             FlowModules::Conditional(module) => {
                 module.handle_url_extract(context, flow_router).await
             }
@@ -69,6 +133,13 @@ impl FlowModule for FlowModules {
             FlowModules::RedirectOnly(module) => {
                 module.handle_url_extract(context, flow_router).await
             }
+            FlowModules::ClickId(module) => {
+                module.handle_url_extract(context, flow_router).await
+            }
+            FlowModules::Visitor(module) => module.handle_url_extract(context, flow_router).await,
+            FlowModules::Conversion(module) => {
+                module.handle_url_extract(context, flow_router).await
+            }
         }
     }
 
@@ -79,9 +150,15 @@ impl FlowModule for FlowModules {
     ) -> Result<FlowStepContinuation> {
         match self {
             FlowModules::Root(module) => module.handle_register(context, flow_router).await,
+            FlowModules::Methods(module) => module.handle_register(context, flow_router).await,
+            FlowModules::Qr(module) => module.handle_register(context, flow_router).await,
+            FlowModules::Preview(module) => module.handle_register(context, flow_router).await,
             FlowModules::Conditional(module) => module.handle_register(context, flow_router).await,
             FlowModules::NotFound(module) => module.handle_register(context, flow_router).await,
             FlowModules::RedirectOnly(module) => module.handle_register(context, flow_router).await,
+            FlowModules::ClickId(module) => module.handle_register(context, flow_router).await,
+            FlowModules::Visitor(module) => module.handle_register(context, flow_router).await,
+            FlowModules::Conversion(module) => module.handle_register(context, flow_router).await,
         }
     }
 
@@ -92,6 +169,9 @@ impl FlowModule for FlowModules {
     ) -> Result<FlowStepContinuation> {
         match self {
             FlowModules::Root(module) => module.handle_build_result(context, flow_router).await,
+            FlowModules::Methods(module) => module.handle_build_result(context, flow_router).await,
+            FlowModules::Qr(module) => module.handle_build_result(context, flow_router).await,
+            FlowModules::Preview(module) => module.handle_build_result(context, flow_router).await,
             FlowModules::Conditional(module) => {
                 module.handle_build_result(context, flow_router).await
             }
@@ -99,6 +179,13 @@ impl FlowModule for FlowModules {
             FlowModules::RedirectOnly(module) => {
                 module.handle_build_result(context, flow_router).await
             }
+            FlowModules::ClickId(module) => {
+                module.handle_build_result(context, flow_router).await
+            }
+            FlowModules::Visitor(module) => module.handle_build_result(context, flow_router).await,
+            FlowModules::Conversion(module) => {
+                module.handle_build_result(context, flow_router).await
+            }
         }
     }
 
@@ -109,9 +196,38 @@ impl FlowModule for FlowModules {
     ) -> Result<FlowStepContinuation> {
         match self {
             FlowModules::Root(module) => module.handle_end(context, flow_router).await,
+            FlowModules::Methods(module) => module.handle_end(context, flow_router).await,
+            FlowModules::Qr(module) => module.handle_end(context, flow_router).await,
+            FlowModules::Preview(module) => module.handle_end(context, flow_router).await,
             FlowModules::Conditional(module) => module.handle_end(context, flow_router).await,
             FlowModules::NotFound(module) => module.handle_end(context, flow_router).await,
             FlowModules::RedirectOnly(module) => module.handle_end(context, flow_router).await,
+            FlowModules::ClickId(module) => module.handle_end(context, flow_router).await,
+            FlowModules::Visitor(module) => module.handle_end(context, flow_router).await,
+            FlowModules::Conversion(module) => module.handle_end(context, flow_router).await,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_build_template_uri() {
+        assert_eq!(
+            template_uri("https://{}/index.html", "test.com").unwrap(),
+            "https://test.com/index.html"
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_template_uri() {
+        assert_eq!(
+            template_uri("https://{}/index.html", "te st.com"),
+            Err(FlowError::InvalidUrl(
+                "https://te st.com/index.html".to_string()
+            ))
+        );
+    }
+}
@@ -1,8 +1,5 @@
-use std::str::FromStr;
-
 use anyhow::{Ok, Result};
-use http::{StatusCode, Uri};
-use string_format::*;
+use http::StatusCode;
 
 use crate::{
     core::{
@@ -14,6 +11,8 @@ use crate::{
     settings::Redirect,
 };
 
+use super::template_uri;
+
 const IS_404: &'static str = "is_404";
 
 #[derive(Debug, Clone)]
@@ -37,15 +36,9 @@ impl FlowModule for NotFoundModule {
         if let None = context.main_route {
             context.add_bool(IS_404, true);
 
-            let not_found_uri = string_format!(
-                self.redirect.not_found_url.clone(),
-                context.request.uri().host().unwrap().to_string()
-            );
+            let not_found_uri = template_uri(&self.redirect.not_found_url, &context.in_route.host)?;
 
-            context.result = Some(FlowRouterResult::Proxied(
-                Uri::from_str(&not_found_uri).unwrap(),
-                StatusCode::OK,
-            ));
+            context.result = Some(FlowRouterResult::Proxied(not_found_uri, StatusCode::OK));
 
             flow_router.router_to(context, FlowStep::End).await?;
 
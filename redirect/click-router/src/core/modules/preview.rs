@@ -0,0 +1,66 @@
+use anyhow::{Ok, Result};
+use http::StatusCode;
+
+use crate::core::{
+    flow_module::{FlowModule, FlowStepContinuation},
+    flow_router::{FlowRouter, FlowRouterContext, FlowRouterResult, FlowStep},
+    preview::{preview_request, PreviewPage},
+};
+
+const IS_PREVIEW: &'static str = "is_preview";
+
+#[derive(Clone)]
+pub struct PreviewModule {
+    suffix: String,
+    page: PreviewPage,
+}
+
+impl PreviewModule {
+    pub fn new(suffix: String, page: PreviewPage) -> Self {
+        Self { suffix, page }
+    }
+}
+
+#[async_trait::async_trait()]
+impl FlowModule for PreviewModule {
+    async fn init(
+        &self,
+        context: &mut FlowRouterContext,
+        _flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        //the route is looked up without the preview suffix
+        let link = preview_request(&context.in_route.path, &self.suffix).map(str::to_string);
+
+        if let Some(link) = link {
+            context.in_route.path = link;
+            context.add_bool(IS_PREVIEW, true);
+        }
+
+        Ok(FlowStepContinuation::Continue)
+    }
+
+    async fn handle_start(
+        &self,
+        context: &mut FlowRouterContext,
+        flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        if !context.is_data_true(IS_PREVIEW) {
+            return Ok(FlowStepContinuation::Continue);
+        }
+
+        //unknown links are answered by the not found module
+        let route = match context.main_route.as_ref() {
+            Some(route) => route,
+            None => return Ok(FlowStepContinuation::Continue),
+        };
+
+        let page = self.page.render(&context.in_route, route)?;
+
+        //a preview is not a click, no hit is registered
+        context.result = Some(FlowRouterResult::Html(page, StatusCode::OK));
+
+        flow_router.router_to(context, FlowStep::End).await?;
+
+        Ok(FlowStepContinuation::Break)
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{Ok, Result};
+use http::StatusCode;
+
+use crate::core::{
+    flow_module::{FlowModule, FlowStepContinuation},
+    flow_router::{FlowRouter, FlowRouterContext, FlowRouterResult, FlowStep, Request, HIT_SOURCE},
+    qr::{qr_request, scan_url, QrFormat, QrRenderer, QR_SOURCE, SOURCE_PARAM},
+};
+
+const QR_FORMAT: &'static str = "qr_format";
+
+#[derive(Clone)]
+pub struct QrModule {
+    renderer: QrRenderer,
+}
+
+impl QrModule {
+    pub fn new(renderer: QrRenderer) -> Self {
+        Self { renderer }
+    }
+}
+
+#[async_trait::async_trait()]
+impl FlowModule for QrModule {
+    async fn init(
+        &self,
+        context: &mut FlowRouterContext,
+        _flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        //the route is looked up without the `.qr` suffix
+        let request = qr_request(&context.in_route.path, context.request.queries())
+            .map(|(link, format)| (link.to_string(), format));
+
+        if let Some((link, format)) = request {
+            context.in_route.path = link;
+            context.add_string(QR_FORMAT, format.as_str());
+        }
+
+        Ok(FlowStepContinuation::Continue)
+    }
+
+    async fn handle_start(
+        &self,
+        context: &mut FlowRouterContext,
+        flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        let format = match context.get_string(QR_FORMAT).and_then(QrFormat::parse) {
+            Some(format) => format,
+            None => {
+                let scanned = context
+                    .request
+                    .queries()
+                    .get(SOURCE_PARAM)
+                    .is_some_and(|source| source == QR_SOURCE);
+
+                if scanned {
+                    context.add_string(HIT_SOURCE, QR_SOURCE);
+                }
+
+                return Ok(FlowStepContinuation::Continue);
+            }
+        };
+
+        //codes are only drawn for existing links, the not found module answers the others
+        if context.main_route.is_none() {
+            return Ok(FlowStepContinuation::Continue);
+        }
+
+        let options = self.renderer.options(context.request.queries())?;
+
+        let code = self
+            .renderer
+            .render(&scan_url(&context.in_route), &options, format)
+            .await?;
+
+        context.result = Some(FlowRouterResult::Binary(
+            code.to_vec(),
+            format.content_type(),
+            StatusCode::OK,
+        ));
+
+        flow_router.router_to(context, FlowStep::End).await?;
+
+        Ok(FlowStepContinuation::Break)
+    }
+}
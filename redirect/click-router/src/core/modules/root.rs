@@ -1,8 +1,5 @@
-use std::str::FromStr;
-
 use anyhow::Result;
-use http::{StatusCode, Uri};
-use string_format::*;
+use http::StatusCode;
 
 use crate::{
     core::{
@@ -12,6 +9,8 @@ use crate::{
     settings::Redirect,
 };
 
+use super::template_uri;
+
 static IS_ROOT: &str = "is_root";
 
 #[derive(Debug, Clone)]
@@ -33,15 +32,9 @@ impl FlowModule for RootModule {
         _flow_router: &FlowRouter,
     ) -> Result<FlowStepContinuation> {
         if context.request.uri().path() == "/" {
-            let root_uri = string_format!(
-                self.redirect.index_url.clone(),
-                context.request.uri().host().unwrap().to_string()
-            );
-
-            context.result = Some(FlowRouterResult::Proxied(
-                Uri::from_str(&root_uri).unwrap(),
-                StatusCode::OK,
-            ));
+            let root_uri = template_uri(&self.redirect.index_url, &context.in_route.host)?;
+
+            context.result = Some(FlowRouterResult::Proxied(root_uri, StatusCode::OK));
 
             context.add_bool(IS_ROOT, true);
 
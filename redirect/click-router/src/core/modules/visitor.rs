@@ -0,0 +1,59 @@
+use anyhow::{Ok, Result};
+
+use crate::core::{
+    flow_module::{FlowModule, FlowStepContinuation},
+    flow_router::{FlowRouter, FlowRouterContext, Request, Response},
+    visitor::VisitorIds,
+};
+
+#[derive(Clone)]
+pub struct VisitorModule {
+    ids: VisitorIds,
+}
+
+impl VisitorModule {
+    pub fn new(ids: VisitorIds) -> Self {
+        Self { ids }
+    }
+}
+
+#[async_trait::async_trait()]
+impl FlowModule for VisitorModule {
+    async fn handle_register(
+        &self,
+        context: &mut FlowRouterContext,
+        flow_router: &FlowRouter,
+    ) -> Result<FlowStepContinuation> {
+        //only the clicks registered as hits identify their visitor
+        let registered = context
+            .out_route
+            .as_ref()
+            .is_some_and(|route| route.dest.is_some());
+
+        if !registered || !flow_router.are_cookies_allowed(context).await? {
+            return Ok(FlowStepContinuation::Continue);
+        }
+
+        let known = context
+            .request
+            .cookie(self.ids.name())
+            .and_then(|cookie| self.ids.verify(cookie.value()));
+
+        //a new id only counts once it comes back, the first hit keeps the IP as its session key
+        match known {
+            Some(id) => context.visitor = Some(id),
+            None => {
+                let secure = context
+                    .protocol
+                    .as_ref()
+                    .is_some_and(|protocol| protocol.ssl_on);
+
+                context
+                    .response
+                    .add_cookie(self.ids.cookie(&self.ids.new_id(), secure));
+            }
+        }
+
+        Ok(FlowStepContinuation::Continue)
+    }
+}
@@ -0,0 +1,252 @@
+//!
+//! Preview page of a short link, `/{link}+`: where it leads, without redirecting.
+//!
+use std::fs;
+
+use anyhow::Context;
+use http::Uri;
+use tracing::info;
+
+use crate::{
+    model::{route::RoutingPolicy, Route},
+    settings::Preview,
+};
+
+use super::{flow_error::FlowError, flow_router::FlowInRoute};
+
+const DEFAULT_PREVIEW_PAGE: &'static str = r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><meta name="robots" content="noindex"><title>{link}</title></head>
+<body>
+<h1>{link}</h1>
+<p>This link on <strong>{domain}</strong> leads to <strong>{destination_host}</strong>:</p>
+<p><code>{destination}</code></p>
+<p>{notice}</p>
+<p>Created: {created}</p>
+<p><a href="{destination}" rel="noopener noreferrer nofollow">Continue</a></p>
+</body>
+</html>"#;
+
+const VARYING_NOTICE: &'static str = "The destination may vary by visitor.";
+
+///
+/// Link of a preview request, the path without the preview suffix.
+///
+pub fn preview_request<'a>(path: &'a str, suffix: &str) -> Option<&'a str> {
+    if suffix.is_empty() {
+        return None;
+    }
+
+    path.strip_suffix(suffix).filter(|link| !link.is_empty())
+}
+
+///
+/// HTML preview page, with `{link}`, `{domain}`, `{destination}`, `{destination_host}`,
+/// `{created}` and `{notice}` placeholders.
+///
+#[derive(Clone, Debug)]
+pub struct PreviewPage {
+    template: String,
+}
+
+impl PreviewPage {
+    pub fn new(template: String) -> Self {
+        Self { template }
+    }
+
+    pub fn from_settings(settings: &Preview) -> anyhow::Result<Self> {
+        match &settings.template {
+            Some(path) => {
+                info!("  preview page -> {}", path);
+
+                let template = fs::read_to_string(path)
+                    .with_context(|| format!("can not read the preview page '{}'", path))?;
+
+                Ok(Self::new(template))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn render(&self, in_route: &FlowInRoute, route: &Route) -> Result<String, FlowError> {
+        let destination = route.dest.as_ref().ok_or(FlowError::MissingDestination)?;
+
+        //only web destinations get a continue button
+        let destination_host = destination
+            .parse::<Uri>()
+            .ok()
+            .filter(|uri| matches!(uri.scheme_str(), Some("http") | Some("https")))
+            .and_then(|uri| uri.host().map(|host| host.to_string()))
+            .ok_or_else(|| FlowError::InvalidDestination(destination.clone()))?;
+
+        let created = route
+            .properties
+            .created
+            .map_or("unknown".to_string(), |created| {
+                created.format("%Y-%m-%d %H:%M UTC").to_string()
+            });
+
+        let notice = match route.policy {
+            RoutingPolicy::Conditional(_) => VARYING_NOTICE,
+            _ => "",
+        };
+
+        Ok(fill(
+            &self.template,
+            &[
+                ("link", format!("{}/{}", in_route.host, in_route.path)),
+                ("domain", in_route.host.clone()),
+                ("destination", destination.clone()),
+                ("destination_host", destination_host),
+                ("created", created),
+                ("notice", notice.to_string()),
+            ],
+        ))
+    }
+}
+
+impl Default for PreviewPage {
+    fn default() -> Self {
+        Self::new(DEFAULT_PREVIEW_PAGE.to_string())
+    }
+}
+
+///
+/// Replaces the placeholders in one pass, so that values are never read as placeholders.
+///
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut page = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        page.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let value = rest.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &rest[1..end])
+                .map(|(_, value)| (end, value))
+        });
+
+        match value {
+            Some((end, value)) => {
+                page.push_str(&escape_html(value));
+                rest = &rest[end + 1..];
+            }
+            None => {
+                page.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    page.push_str(rest);
+
+    page
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for char in value.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::model::route::RouteProperties;
+
+    use super::*;
+
+    fn in_route() -> FlowInRoute {
+        FlowInRoute::new(
+            "https".to_string(),
+            "go.brand.com".to_string(),
+            443,
+            "promo".to_string(),
+            String::new(),
+        )
+    }
+
+    fn route(dest: &str) -> Route {
+        Route {
+            dest: Some(dest.to_string()),
+            properties: RouteProperties {
+                created: Some(Utc.with_ymd_and_hms(2024, 5, 1, 10, 30, 0).unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_detect_preview_requests() {
+        assert_eq!(preview_request("promo+", "+"), Some("promo"));
+        assert_eq!(preview_request("promo", "+"), None);
+        assert_eq!(preview_request("+", "+"), None);
+        assert_eq!(preview_request("promo~info", "~info"), Some("promo"));
+        assert_eq!(preview_request("promo", ""), None);
+    }
+
+    #[test]
+    fn should_render_preview() {
+        let page = PreviewPage::new(
+            "{link}|{domain}|{destination_host}|{destination}|{created}|{notice}|{other}"
+                .to_string(),
+        );
+
+        assert_eq!(
+            page.render(&in_route(), &route("https://shop.com/a?b=1&c=2"))
+                .unwrap(),
+            "go.brand.com/promo|go.brand.com|shop.com|https://shop.com/a?b=1&amp;c=2|2024-05-01 10:30 UTC||{other}"
+        );
+    }
+
+    #[test]
+    fn should_escape_values_and_not_expand_them() {
+        assert_eq!(
+            fill(
+                "{a}|{b}|{",
+                &[("a", "{b}\"><script>".to_string()), ("b", "x".to_string())]
+            ),
+            "{b}&quot;&gt;&lt;script&gt;|x|{"
+        );
+    }
+
+    #[test]
+    fn should_reject_non_web_destinations() {
+        assert_eq!(
+            PreviewPage::default()
+                .render(&in_route(), &route("javascript:alert(1)"))
+                .unwrap_err(),
+            FlowError::InvalidDestination("javascript:alert(1)".to_string())
+        );
+    }
+
+    #[test]
+    fn should_fail_on_a_missing_template() {
+        let error = PreviewPage::from_settings(&Preview {
+            template: Some("missing/preview.html".to_string()),
+            ..Default::default()
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "can not read the preview page 'missing/preview.html'"
+        );
+    }
+}
@@ -0,0 +1,602 @@
+//!
+//! QR codes of short links, rendered as SVG or PNG and cached by content and options.
+//!
+use std::{fs, io::Cursor, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+use moka::future::Cache;
+use multimap::MultiMap;
+use qrcode::{Color, EcLevel, QrCode};
+use tracing::info;
+
+use crate::settings::Qr;
+
+use super::{flow_error::FlowError, flow_router::FlowInRoute};
+
+/// Path suffix of QR requests, `/{link}.qr`.
+const QR_SUFFIX: &'static str = ".qr";
+/// Query parameter of QR requests, `/{link}?qr=svg|png`.
+const QR_PARAM: &'static str = "qr";
+
+/// Query parameter added to the encoded link, so that scans can be told apart from clicks.
+pub const SOURCE_PARAM: &'static str = "src";
+pub const QR_SOURCE: &'static str = "qr";
+
+const MIN_SIZE: u32 = 64;
+const MAX_MARGIN: u32 = 16;
+/// Share of the code width covered by the logo, small enough for level H to recover.
+const LOGO_RATIO: u32 = 5;
+
+type Rgb = [u8; 3];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QrFormat {
+    Svg,
+    Png,
+}
+
+impl QrFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "svg" => Some(QrFormat::Svg),
+            "png" => Some(QrFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QrFormat::Svg => "svg",
+            QrFormat::Png => "png",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            QrFormat::Svg => "image/svg+xml",
+            QrFormat::Png => "image/png",
+        }
+    }
+}
+
+///
+/// Link and format of a QR request, SVG unless `qr=png` is asked for.
+///
+pub fn qr_request<'a>(
+    path: &'a str,
+    queries: &MultiMap<String, String>,
+) -> Option<(&'a str, QrFormat)> {
+    let format = queries
+        .get(QR_PARAM)
+        .map(|format| QrFormat::parse(format).unwrap_or(QrFormat::Svg));
+
+    match path.strip_suffix(QR_SUFFIX) {
+        Some(link) => Some((link, format.unwrap_or(QrFormat::Svg))),
+        None => format.map(|format| (path, format)),
+    }
+}
+
+///
+/// The link encoded in the code, marked as a QR scan.
+///
+pub fn scan_url(in_route: &FlowInRoute) -> String {
+    let host = match (in_route.scheme.as_str(), in_route.port) {
+        ("http", 80) | ("https", 443) | (_, 0) => in_route.host.clone(),
+        (_, port) => format!("{}:{}", in_route.host, port),
+    };
+
+    format!(
+        "{}://{}/{}?{}={}",
+        in_route.scheme, host, in_route.path, SOURCE_PARAM, QR_SOURCE
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QrOptions {
+    /// Width and height of PNG codes in pixels, SVG codes scale freely.
+    pub size: u32,
+    /// Quiet zone around the code, in modules.
+    pub margin: u32,
+    pub ec_level: EcLevel,
+    pub dark: Rgb,
+    pub light: Rgb,
+}
+
+impl QrOptions {
+    pub fn from_settings(settings: &Qr) -> Result<Self> {
+        Ok(Self {
+            size: settings.size,
+            margin: settings.margin,
+            ec_level: parse_ec_level(&settings.ec_level)
+                .ok_or_else(|| anyhow!("invalid QR error correction level"))?,
+            dark: parse_color(&settings.dark_color)
+                .ok_or_else(|| anyhow!("invalid QR dark color"))?,
+            light: parse_color(&settings.light_color)
+                .ok_or_else(|| anyhow!("invalid QR light color"))?,
+        })
+    }
+
+    ///
+    /// Overrides the options with the `size`, `margin`, `ec`, `dark` and `light` query parameters.
+    ///
+    pub fn with_queries(
+        &self,
+        queries: &MultiMap<String, String>,
+        max_size: u32,
+    ) -> Result<Self, FlowError> {
+        let mut options = self.clone();
+
+        if let Some(size) = queries.get("size") {
+            options.size = size
+                .parse::<u32>()
+                .ok()
+                .filter(|size| (MIN_SIZE..=max_size).contains(size))
+                .ok_or_else(|| invalid("size", size))?;
+        }
+
+        if let Some(margin) = queries.get("margin") {
+            options.margin = margin
+                .parse::<u32>()
+                .ok()
+                .filter(|margin| *margin <= MAX_MARGIN)
+                .ok_or_else(|| invalid("margin", margin))?;
+        }
+
+        if let Some(ec) = queries.get("ec") {
+            options.ec_level = parse_ec_level(ec).ok_or_else(|| invalid("ec", ec))?;
+        }
+
+        if let Some(dark) = queries.get("dark") {
+            options.dark = parse_color(dark).ok_or_else(|| invalid("dark", dark))?;
+        }
+
+        if let Some(light) = queries.get("light") {
+            options.light = parse_color(light).ok_or_else(|| invalid("light", light))?;
+        }
+
+        Ok(options)
+    }
+}
+
+fn invalid(param: &str, value: &str) -> FlowError {
+    FlowError::InvalidRequest(format!("invalid QR {} '{}'", param, value))
+}
+
+fn parse_ec_level(value: &str) -> Option<EcLevel> {
+    match value.to_ascii_uppercase().as_str() {
+        "L" => Some(EcLevel::L),
+        "M" => Some(EcLevel::M),
+        "Q" => Some(EcLevel::Q),
+        "H" => Some(EcLevel::H),
+        _ => None,
+    }
+}
+
+///
+/// Parses `#rrggbb` or `rrggbb`, the `#` has to be escaped in query parameters.
+///
+fn parse_color(value: &str) -> Option<Rgb> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn hex_color(color: &Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+#[derive(Clone)]
+pub struct QrRenderer {
+    defaults: QrOptions,
+    max_size: u32,
+    logo: Option<Arc<RgbaImage>>,
+    cache: Cache<String, Arc<Vec<u8>>>,
+}
+
+impl QrRenderer {
+    pub fn new(
+        defaults: QrOptions,
+        max_size: u32,
+        logo: Option<&[u8]>,
+        cache_bytes: u64,
+    ) -> Result<Self> {
+        let logo = match logo {
+            Some(logo) => Some(Arc::new(
+                image::load_from_memory_with_format(logo, ImageFormat::Png)?.to_rgba8(),
+            )),
+            None => None,
+        };
+
+        Ok(Self {
+            defaults,
+            max_size,
+            logo,
+            cache: Cache::builder()
+                .weigher(|key: &String, code: &Arc<Vec<u8>>| {
+                    (key.len() + code.len()).try_into().unwrap_or(u32::MAX)
+                })
+                .max_capacity(cache_bytes)
+                .build(),
+        })
+    }
+
+    pub fn from_settings(settings: &Qr) -> Result<Self> {
+        let logo = match &settings.logo {
+            Some(path) => {
+                info!("  qr logo -> {}", path);
+
+                Some(
+                    fs::read(path)
+                        .with_context(|| format!("can not read the QR logo '{}'", path))?,
+                )
+            }
+            None => None,
+        };
+
+        Self::new(
+            QrOptions::from_settings(settings)?,
+            settings.max_size,
+            logo.as_deref(),
+            settings.cache_bytes,
+        )
+        .context("can not load the QR logo, expected a PNG image")
+    }
+
+    pub fn options(&self, queries: &MultiMap<String, String>) -> Result<QrOptions, FlowError> {
+        self.defaults.with_queries(queries, self.max_size)
+    }
+
+    ///
+    /// Renders a code on a blocking thread, PNG encoding and logo scaling take milliseconds.
+    ///
+    pub async fn render(
+        &self,
+        content: &str,
+        options: &QrOptions,
+        format: QrFormat,
+    ) -> Result<Arc<Vec<u8>>, FlowError> {
+        let key = format!("{}|{}|{:?}", format.as_str(), content, options);
+
+        self.cache
+            .try_get_with(key, async {
+                //the renderer only holds shared handles, cloning it is cheap
+                let renderer = self.clone();
+                let content = content.to_string();
+                let options = options.clone();
+
+                tokio::task::spawn_blocking(move || renderer.draw(&content, &options, format))
+                    .await
+                    .map_err(|error| FlowError::Internal(error.to_string()))?
+                    .map(Arc::new)
+            })
+            .await
+            .map_err(|error| error.as_ref().clone())
+    }
+
+    fn draw(
+        &self,
+        content: &str,
+        options: &QrOptions,
+        format: QrFormat,
+    ) -> Result<Vec<u8>, FlowError> {
+        //the logo hides modules, only the highest level recovers them reliably
+        let ec_level = match self.logo {
+            Some(_) => EcLevel::H,
+            None => options.ec_level,
+        };
+
+        let code = QrCode::with_error_correction_level(content, ec_level)
+            .map_err(|error| FlowError::InvalidRequest(error.to_string()))?;
+
+        let modules = Modules::of(&code, options.margin);
+
+        match format {
+            QrFormat::Svg => Ok(self.draw_svg(&modules, options)?.into_bytes()),
+            QrFormat::Png => self.draw_png(&modules, options),
+        }
+    }
+
+    fn draw_svg(&self, modules: &Modules, options: &QrOptions) -> Result<String, FlowError> {
+        let total = modules.total;
+
+        let mut path = String::new();
+
+        for y in 0..total {
+            for x in 0..total {
+                if modules.is_dark(x, y) {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x, y));
+                }
+            }
+        }
+
+        let mut svg = format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" "#,
+                r#"width="{size}" height="{size}" viewBox="0 0 {total} {total}" shape-rendering="crispEdges">"#,
+                r#"<rect width="{total}" height="{total}" fill="{light}"/>"#,
+                r#"<path d="{path}" fill="{dark}"/>"#
+            ),
+            size = options.size,
+            total = total,
+            light = hex_color(&options.light),
+            dark = hex_color(&options.dark),
+            path = path
+        );
+
+        if let Some(logo) = &self.logo {
+            let png = encode_png(logo)?;
+            let side = total as f64 / LOGO_RATIO as f64;
+            let offset = (total as f64 - side) / 2.0;
+
+            svg.push_str(&format!(
+                concat!(
+                    r#"<rect x="{offset}" y="{offset}" width="{side}" height="{side}" fill="{light}"/>"#,
+                    r#"<image x="{offset}" y="{offset}" width="{side}" height="{side}" href="data:image/png;base64,{data}"/>"#
+                ),
+                offset = offset,
+                side = side,
+                light = hex_color(&options.light),
+                data = STANDARD.encode(png)
+            ));
+        }
+
+        svg.push_str("</svg>");
+
+        Ok(svg)
+    }
+
+    fn draw_png(&self, modules: &Modules, options: &QrOptions) -> Result<Vec<u8>, FlowError> {
+        let size = options.size;
+        let total = modules.total as u32;
+
+        let dark = Rgba([options.dark[0], options.dark[1], options.dark[2], 255]);
+        let light = Rgba([options.light[0], options.light[1], options.light[2], 255]);
+
+        //modules are mapped to pixels, so that the image gets exactly the asked size
+        let mut image = RgbaImage::from_fn(size, size, |x, y| {
+            let module_x = (x * total / size) as usize;
+            let module_y = (y * total / size) as usize;
+
+            if modules.is_dark(module_x, module_y) {
+                dark
+            } else {
+                light
+            }
+        });
+
+        if let Some(logo) = &self.logo {
+            let side = size / LOGO_RATIO;
+            let offset = ((size - side) / 2) as i64;
+
+            let background = RgbaImage::from_pixel(side, side, light);
+            let logo = imageops::resize(logo.as_ref(), side, side, imageops::FilterType::Triangle);
+
+            imageops::overlay(&mut image, &background, offset, offset);
+            imageops::overlay(&mut image, &logo, offset, offset);
+        }
+
+        encode_png(&image)
+    }
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, FlowError> {
+    let mut png = Cursor::new(Vec::new());
+
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|error| FlowError::Internal(error.to_string()))?;
+
+    Ok(png.into_inner())
+}
+
+///
+/// Dark/light modules of a code, quiet zone included.
+///
+struct Modules {
+    colors: Vec<Color>,
+    width: usize,
+    margin: usize,
+    total: usize,
+}
+
+impl Modules {
+    fn of(code: &QrCode, margin: u32) -> Self {
+        let width = code.width();
+        let margin = margin as usize;
+
+        Self {
+            colors: code.to_colors(),
+            width,
+            margin,
+            total: width + 2 * margin,
+        }
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        if x < self.margin || y < self.margin {
+            return false;
+        }
+
+        let (x, y) = (x - self.margin, y - self.margin);
+
+        if x >= self.width || y >= self.width {
+            return false;
+        }
+
+        self.colors[y * self.width + x] == Color::Dark
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queries(pairs: &[(&str, &str)]) -> MultiMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn defaults() -> QrOptions {
+        QrOptions::from_settings(&Qr::default()).unwrap()
+    }
+
+    #[test]
+    fn should_detect_qr_requests() {
+        assert_eq!(
+            qr_request("promo.qr", &queries(&[])),
+            Some(("promo", QrFormat::Svg))
+        );
+        assert_eq!(
+            qr_request("promo.qr", &queries(&[("qr", "png")])),
+            Some(("promo", QrFormat::Png))
+        );
+        assert_eq!(
+            qr_request("promo", &queries(&[("qr", "PNG")])),
+            Some(("promo", QrFormat::Png))
+        );
+        assert_eq!(qr_request("promo", &queries(&[("src", "qr")])), None);
+    }
+
+    #[test]
+    fn should_mark_scan_url() {
+        let in_route = FlowInRoute::new(
+            "https".to_string(),
+            "test.com".to_string(),
+            443,
+            "promo".to_string(),
+            "qr=png".to_string(),
+        );
+
+        assert_eq!(scan_url(&in_route), "https://test.com/promo?src=qr");
+
+        let in_route = FlowInRoute::new(
+            "http".to_string(),
+            "localhost".to_string(),
+            5800,
+            "promo".to_string(),
+            String::new(),
+        );
+
+        assert_eq!(scan_url(&in_route), "http://localhost:5800/promo?src=qr");
+    }
+
+    #[test]
+    fn should_override_options_with_queries() {
+        let options = defaults()
+            .with_queries(
+                &queries(&[
+                    ("size", "256"),
+                    ("margin", "2"),
+                    ("ec", "q"),
+                    ("dark", "1a2b3c"),
+                    ("light", "#FFFFFF"),
+                ]),
+                1024,
+            )
+            .unwrap();
+
+        assert_eq!(
+            options,
+            QrOptions {
+                size: 256,
+                margin: 2,
+                ec_level: EcLevel::Q,
+                dark: [0x1a, 0x2b, 0x3c],
+                light: [0xff, 0xff, 0xff],
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_options() {
+        for (param, value) in [
+            ("size", "4096"),
+            ("size", "10"),
+            ("margin", "100"),
+            ("ec", "x"),
+            ("dark", "red"),
+            ("light", "#fffff"),
+        ] {
+            assert!(
+                matches!(
+                    defaults().with_queries(&queries(&[(param, value)]), 1024),
+                    Err(FlowError::InvalidRequest(_))
+                ),
+                "{}={}",
+                param,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_invalid_settings() {
+        let settings = Qr {
+            logo: Some("./missing-logo.png".to_string()),
+            ..Qr::default()
+        };
+
+        let error = QrRenderer::from_settings(&settings).err().unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "can not read the QR logo './missing-logo.png'"
+        );
+
+        let settings = Qr {
+            dark_color: "black".to_string(),
+            ..Qr::default()
+        };
+
+        assert!(QrRenderer::from_settings(&settings).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_render_svg() {
+        let renderer = QrRenderer::new(defaults(), 1024, None, 1024 * 1024).unwrap();
+
+        let svg = renderer
+            .render("https://test.com/promo?src=qr", &defaults(), QrFormat::Svg)
+            .await
+            .unwrap();
+        let svg = String::from_utf8(svg.to_vec()).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r##"fill="#000000""##));
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[tokio::test]
+    async fn should_render_png_of_asked_size_with_logo() {
+        let logo = encode_png(&RgbaImage::from_pixel(32, 32, Rgba([255, 0, 0, 255]))).unwrap();
+        let renderer = QrRenderer::new(defaults(), 1024, Some(&logo), 1024 * 1024).unwrap();
+
+        let options = QrOptions {
+            size: 200,
+            ..defaults()
+        };
+
+        let png = renderer
+            .render("https://test.com/promo?src=qr", &options, QrFormat::Png)
+            .await
+            .unwrap();
+
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+
+        assert_eq!(image.dimensions(), (200, 200));
+        //quiet zone
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        //logo
+        assert_eq!(image.get_pixel(100, 100), &Rgba([255, 0, 0, 255]));
+    }
+}
@@ -0,0 +1,301 @@
+//!
+//! Links with `{name}` segments and a `{*name}` tail, e.g. `go.brand.com%2fp/{id}` or
+//! `docs.brand.com%2fdocs/{*rest}`, the fallback of paths without an exact route.
+//! The captured segments fill the same placeholders of the destination, percent-encoded for
+//! the part of the URL they land in.
+//!
+use link_keys::split_link;
+use matchit::Router;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use tracing::warn;
+
+use crate::model::Route;
+
+//characters that would end or split a path segment of the destination
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+pub fn is_pattern(link: &str) -> bool {
+    link.contains('{')
+}
+
+///
+/// Pattern routes of a domain, compiled into one matcher.
+///
+#[derive(Default)]
+pub struct RoutePatterns {
+    matcher: Router<usize>,
+    routes: Vec<Route>,
+}
+
+impl RoutePatterns {
+    pub fn new(routes: Vec<Route>) -> Self {
+        let mut patterns = Self::default();
+
+        for route in routes {
+            let Some((_, path)) = split_link(&route.link) else {
+                continue;
+            };
+
+            //a placeholder without a capture would end up as literal text in the destination
+            let captured = placeholders(path);
+
+            if let Some(missing) = route.dest.as_deref().and_then(|dest| {
+                placeholders(dest)
+                    .into_iter()
+                    .find(|name| !captured.iter().any(|c| c.eq_ignore_ascii_case(name)))
+            }) {
+                warn!(
+                    "link pattern '{}' does not capture '{{{}}}' of its destination",
+                    route.link, missing
+                );
+                continue;
+            }
+
+            //a broken pattern only disables its own route
+            match patterns
+                .matcher
+                .insert(format!("/{}", path), patterns.routes.len())
+            {
+                Ok(()) => patterns.routes.push(route),
+                Err(error) => warn!("invalid link pattern '{}': {}", route.link, error),
+            }
+        }
+
+        patterns
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    ///
+    /// Route of the most specific pattern matching the path, with the captured segments in its
+    /// destination. Static segments win over `{name}` segments, which win over a `{*name}` tail.
+    ///
+    /// The patterns match `key`, the path as stored in the links of the domain, the captures are
+    /// taken from the same bytes of the requested `path`, so that they keep their case.
+    ///
+    pub fn find(&self, key: &str, path: &str) -> Option<Route> {
+        let key = format!("/{}", key);
+        let path = format!("/{}", path);
+        let matched = self.matcher.at(&key).ok()?;
+
+        //ASCII lowercasing keeps the offsets, the captures are read from the requested path
+        let captures: Vec<(&str, &str)> = matched
+            .params
+            .iter()
+            .map(|(name, value)| {
+                let start = value.as_ptr() as usize - key.as_ptr() as usize;
+
+                match key.len() == path.len() {
+                    true => (name, path.get(start..start + value.len()).unwrap_or(value)),
+                    false => (name, value),
+                }
+            })
+            .collect();
+
+        let mut route = self.routes[*matched.value].clone();
+
+        route.dest = route.dest.map(|dest| fill(&dest, &captures));
+
+        Some(route)
+    }
+}
+
+///
+/// Names of the `{name}` and `{*name}` placeholders of a link or a destination.
+///
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+
+        if let Some(end) = rest.find('}') {
+            names.push(rest[..end].trim_start_matches('*'));
+            rest = &rest[end + 1..];
+        }
+    }
+
+    names
+}
+
+///
+/// Replaces the `{name}` placeholders of the captured segments. A capture in the path keeps its
+/// `/` separators, one in the query or the fragment is encoded as a whole value.
+///
+fn fill(dest: &str, captures: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(dest.len());
+    let mut rest = dest;
+
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let capture = rest.find('}').and_then(|end| {
+            captures
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(rest[1..end].trim_start_matches('*')))
+                .map(|(_, value)| (end, value))
+        });
+
+        match capture {
+            Some((end, value)) => {
+                let value = percent_decode_str(value).decode_utf8_lossy();
+
+                match filled.contains(['?', '#']) {
+                    true => filled.push_str(&urlencoding::encode(&value)),
+                    false => {
+                        let segments: Vec<String> = value
+                            .split('/')
+                            .map(|segment| utf8_percent_encode(segment, SEGMENT).to_string())
+                            .collect();
+
+                        filled.push_str(&segments.join("/"));
+                    }
+                }
+
+                rest = &rest[end + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    filled.push_str(rest);
+
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(link: &str, dest: &str) -> Route {
+        Route::new(
+            "main".to_string(),
+            link.to_string(),
+            Some(dest.to_string()),
+            Default::default(),
+        )
+    }
+
+    fn find(patterns: &RoutePatterns, path: &str) -> Option<Route> {
+        patterns.find(&path.to_ascii_lowercase(), path)
+    }
+
+    fn patterns() -> RoutePatterns {
+        RoutePatterns::new(vec![
+            route(
+                "docs.brand.com%2fdocs/{*rest}",
+                "https://site.com/docs/{rest}",
+            ),
+            route("docs.brand.com%2fdocs/api", "https://site.com/api"),
+            route(
+                "docs.brand.com%2fp/{id}",
+                "https://shop.com/product?id={id}",
+            ),
+            route(
+                "docs.brand.com%2fitem/{id}",
+                "https://shop.com/{id}?ref={ref}",
+            ),
+        ])
+    }
+
+    #[test]
+    fn should_detect_patterns() {
+        assert!(is_pattern("docs/{*rest}"));
+        assert!(!is_pattern("docs/rest"));
+    }
+
+    #[test]
+    fn should_capture_segments_into_the_destination() {
+        let patterns = patterns();
+
+        assert_eq!(
+            find(&patterns, "docs/guide/install")
+                .unwrap()
+                .dest
+                .as_deref(),
+            Some("https://site.com/docs/guide/install")
+        );
+        assert_eq!(
+            find(&patterns, "p/42").unwrap().dest.as_deref(),
+            Some("https://shop.com/product?id=42")
+        );
+    }
+
+    #[test]
+    fn should_keep_the_case_of_the_captured_segments() {
+        let patterns = patterns();
+
+        assert_eq!(
+            find(&patterns, "Docs/Guide/Install")
+                .unwrap()
+                .dest
+                .as_deref(),
+            Some("https://site.com/docs/Guide/Install")
+        );
+        assert_eq!(
+            find(&patterns, "P/AbC").unwrap().dest.as_deref(),
+            Some("https://shop.com/product?id=AbC")
+        );
+    }
+
+    #[test]
+    fn should_encode_the_captured_segments() {
+        let patterns = patterns();
+
+        assert_eq!(
+            find(&patterns, "p/42&ref=evil#x").unwrap().dest.as_deref(),
+            Some("https://shop.com/product?id=42%26ref%3Devil%23x")
+        );
+        assert_eq!(
+            find(&patterns, "p/a%20b").unwrap().dest.as_deref(),
+            Some("https://shop.com/product?id=a%20b")
+        );
+        assert_eq!(
+            find(&patterns, "docs/a?b=1/c#d").unwrap().dest.as_deref(),
+            Some("https://site.com/docs/a%3Fb=1/c%23d")
+        );
+    }
+
+    #[test]
+    fn should_reject_placeholders_without_capture() {
+        let patterns = patterns();
+
+        assert!(find(&patterns, "item/42").is_none());
+        assert_eq!(placeholders("p/{id}/{*rest}"), vec!["id", "rest"]);
+    }
+
+    #[test]
+    fn should_prefer_static_segments() {
+        assert_eq!(
+            find(&patterns(), "docs/api").unwrap().dest.as_deref(),
+            Some("https://site.com/api")
+        );
+    }
+
+    #[test]
+    fn should_not_match_other_paths() {
+        let patterns = patterns();
+
+        assert!(find(&patterns, "p/42/more").is_none());
+        assert!(find(&patterns, "other").is_none());
+        assert!(find(&RoutePatterns::default(), "docs/guide").is_none());
+    }
+}
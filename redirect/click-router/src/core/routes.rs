@@ -1,44 +1,71 @@
+use std::sync::Arc;
+
 use anyhow::Result;
+use link_keys::LinkKeys;
 
 use crate::adapters::RoutesCacheType;
 
 use crate::model::Route;
 
+use super::route_patterns::RoutePatterns;
+
 #[async_trait::async_trait()]
 pub trait RoutesStore {
     async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>>;
+    /// Routes of the domain with a link pattern, see `RoutePatterns`.
+    async fn get_pattern_routes(&self, switch: &str, domain: &str) -> Result<Vec<Route>>;
 }
 
 #[async_trait::async_trait()]
 pub trait RoutesCache {
     async fn get_route(&self, switch: &str, path: &str) -> Result<Option<Route>>;
+    async fn get_route_patterns(&self, switch: &str, domain: &str) -> Result<Arc<RoutePatterns>>;
+    /// Drops the route, and the patterns of its domain when the link is one.
     async fn invalidate(&self, switch: &str, path: &str) -> Result<()>;
 }
 
 #[derive(Clone)]
 pub struct RoutesManager {
     routes_cache: RoutesCacheType,
-}
-
-fn get_key(domain: &str, link: &str) -> String {
-    let domain_str = domain.to_ascii_lowercase();
-    let link_str = link.to_ascii_lowercase();
-
-    domain_str + "%2F" + &link_str
+    link_keys: LinkKeys,
 }
 
 impl RoutesManager {
+    ///
+    /// Route of the link, or of the domain pattern matching the path when there is none.
+    ///
     pub async fn get_route(&self, switch: &str, domain: &str, path: &str) -> Result<Option<Route>> {
-        let key = get_key(domain, path);
+        let key = self.link_keys.key(domain, path);
 
-        let route_result = self.routes_cache.get_route(switch, key.as_str()).await;
+        if let Some(route) = self.routes_cache.get_route(switch, key.as_str()).await? {
+            return Ok(Some(route));
+        }
+
+        //links stored before the domain became case sensitive
+        if let Some(key) = self.link_keys.lowercase_key(domain, path) {
+            if let Some(route) = self.routes_cache.get_route(switch, key.as_str()).await? {
+                return Ok(Some(route));
+            }
+        }
+
+        let patterns = self
+            .routes_cache
+            .get_route_patterns(switch, &domain.to_ascii_lowercase())
+            .await?;
+
+        Ok(patterns.find(&self.link_keys.path(domain, path), path))
+    }
 
-        Ok(route_result.unwrap())
+    pub fn link_keys(&self) -> &LinkKeys {
+        &self.link_keys
     }
 }
 
 impl RoutesManager {
-    pub fn new(routes_cache: RoutesCacheType) -> Self {
-        Self { routes_cache }
+    pub fn new(routes_cache: RoutesCacheType, link_keys: LinkKeys) -> Self {
+        Self {
+            routes_cache,
+            link_keys,
+        }
     }
 }
@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+///
+/// Dotted version number (`17`, `17.2`, `4.4.2`) as reported by the user agent detector.
+/// `precision` is the count of components that were given.
+///
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Version {
+    parts: [u32; 3],
+    precision: usize,
+}
+
+impl Version {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = [0; 3];
+        let mut precision = 0;
+
+        for part in value.trim().split('.') {
+            if precision == parts.len() {
+                return None;
+            }
+
+            parts[precision] = part.parse().ok()?;
+            precision += 1;
+        }
+
+        Some(Self { parts, precision })
+    }
+
+    ///
+    /// Builds a version out of `major`, `minor` and `patch`, stopping at the first missing
+    /// or non numeric component (uaparser reports e.g. `patch: "beta"`).
+    ///
+    pub fn from_parts(
+        major: Option<&str>,
+        minor: Option<&str>,
+        patch: Option<&str>,
+    ) -> Option<Self> {
+        let mut parts = [0; 3];
+        let mut precision = 0;
+
+        for part in [major, minor, patch] {
+            match part.and_then(|part| part.parse().ok()) {
+                Some(part) => {
+                    parts[precision] = part;
+                    precision += 1;
+                }
+                None => break,
+            }
+        }
+
+        if precision == 0 {
+            return None;
+        }
+
+        Some(Self { parts, precision })
+    }
+
+    ///
+    /// Compares semver-style, up to the precision of `other`:
+    /// `17.2` equals `17`, is greater than `16` and less than `17.3`.
+    ///
+    pub fn compare(&self, other: &Version) -> Ordering {
+        self.parts[..other.precision].cmp(&other.parts[..other.precision])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(value: &str) -> Version {
+        Version::parse(value).unwrap()
+    }
+
+    #[test]
+    fn should_parse_versions() {
+        assert_eq!(version("17").precision, 1);
+        assert_eq!(version("4.4.2").parts, [4, 4, 2]);
+        assert!(Version::parse("17.a").is_none());
+        assert!(Version::parse("1.2.3.4").is_none());
+        assert!(Version::parse("").is_none());
+    }
+
+    #[test]
+    fn should_build_from_parts() {
+        let from_parts = Version::from_parts(Some("17"), Some("2"), Some("beta")).unwrap();
+
+        assert_eq!(from_parts, version("17.2"));
+        assert!(Version::from_parts(None, Some("2"), None).is_none());
+    }
+
+    #[test]
+    fn should_compare_up_to_precision() {
+        assert_eq!(version("17.2").compare(&version("17")), Ordering::Equal);
+        assert_eq!(version("17.2").compare(&version("16")), Ordering::Greater);
+        assert_eq!(version("17.2").compare(&version("17.3")), Ordering::Less);
+        assert_eq!(version("17").compare(&version("17.0")), Ordering::Equal);
+        assert_eq!(version("4.4.2").compare(&version("5")), Ordering::Less);
+    }
+}
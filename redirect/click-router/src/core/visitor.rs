@@ -0,0 +1,132 @@
+//!
+//! First-party visitor ids of the short domains, so that unique visitors are told apart
+//! behind carrier NATs and rotating IPv6 privacy addresses.
+//!
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use cookie::{time::Duration, Cookie, SameSite};
+use http::HeaderMap;
+use ring::hmac;
+use ulid::Ulid;
+
+use crate::settings::Visitors;
+
+const DNT_HEADER: &'static str = "dnt";
+const GPC_HEADER: &'static str = "sec-gpc";
+
+///
+/// Issues and verifies the visitor cookies, `{ulid}.{base64url HMAC-SHA256 of the ulid}`.
+///
+#[derive(Clone)]
+pub struct VisitorIds {
+    name: String,
+    max_age: Duration,
+    key: hmac::Key,
+}
+
+impl VisitorIds {
+    ///
+    /// Visitor ids of the settings, none without a secret to sign them.
+    ///
+    pub fn from_settings(settings: &Visitors) -> Option<Self> {
+        let secret = settings
+            .secret
+            .as_ref()
+            .filter(|secret| !secret.is_empty())?;
+
+        Some(Self {
+            name: settings.cookie.clone(),
+            max_age: Duration::days(settings.max_age_days),
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn new_id(&self) -> String {
+        Ulid::new().to_string()
+    }
+
+    ///
+    /// Id of a cookie value, none when it was not signed with the secret.
+    ///
+    pub fn verify(&self, value: &str) -> Option<String> {
+        let (id, signature) = value.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        hmac::verify(&self.key, id.as_bytes(), &signature).ok()?;
+
+        Some(id.to_string())
+    }
+
+    pub fn cookie(&self, id: &str, secure: bool) -> Cookie<'static> {
+        let signature = hmac::sign(&self.key, id.as_bytes());
+        let value = format!("{}.{}", id, URL_SAFE_NO_PAD.encode(signature.as_ref()));
+
+        let mut cookie = Cookie::new(self.name.clone(), value);
+
+        cookie.set_path("/");
+        cookie.set_max_age(self.max_age);
+        cookie.set_same_site(SameSite::Lax);
+        cookie.set_http_only(true);
+        cookie.set_secure(secure);
+
+        cookie
+    }
+}
+
+///
+/// Whether the visitor asked not to be tracked, with `DNT: 1` or `Sec-GPC: 1`.
+///
+pub fn opted_out(headers: &HeaderMap) -> bool {
+    [DNT_HEADER, GPC_HEADER].iter().any(|name| {
+        headers
+            .get(*name)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.trim() == "1")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn visitor_ids(secret: &str) -> VisitorIds {
+        VisitorIds::from_settings(&Visitors {
+            secret: Some(secret.to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn should_only_issue_signed_visitor_ids() {
+        assert!(VisitorIds::from_settings(&Visitors::default()).is_none());
+
+        let ids = visitor_ids("secret");
+        let id = ids.new_id();
+        let cookie = ids.cookie(&id, true);
+
+        assert_eq!(cookie.name(), "_sv");
+        assert_eq!(cookie.max_age(), Some(Duration::days(365)));
+        assert_eq!(ids.verify(cookie.value()), Some(id.clone()));
+        assert_eq!(visitor_ids("other").verify(cookie.value()), None);
+        assert_eq!(ids.verify(&id), None);
+        assert_eq!(ids.verify(&format!("{}.forged", id)), None);
+    }
+
+    #[test]
+    fn should_respect_dnt_and_gpc() {
+        let mut headers = HeaderMap::new();
+        assert!(!opted_out(&headers));
+
+        headers.insert(DNT_HEADER, HeaderValue::from_static("0"));
+        assert!(!opted_out(&headers));
+
+        headers.insert(GPC_HEADER, HeaderValue::from_static("1"));
+        assert!(opted_out(&headers));
+    }
+}
@@ -10,11 +10,11 @@ use click_router::{
     },
     app::AppBuilder,
     core::flow_router::{FlowRouter, FlowRouterResult, RedirectType},
-    settings::Settings,
+    settings::{ChangeSubscriberKind, HitRegistrarKind, Settings},
 };
 
 use clap::Parser;
-use http::StatusCode;
+use http::{header::CONTENT_TYPE, StatusCode};
 use once_cell::sync::OnceCell;
 use rustls::server::ClientHello;
 use salvo::{
@@ -24,7 +24,7 @@ use salvo::{
         TcpListener,
     },
     prelude::Logger,
-    writing::Json,
+    writing::Text,
     Depot, FlowCtrl, Handler, Listener, Request, Response, Router, Server, Service,
 };
 use salvo_proxy::{hyper_client::HyperClient, Proxy};
@@ -36,6 +36,9 @@ pub struct Args {
     pub run_mode: String,
     #[arg(short, long, default_value_t = String::from("./config"), env("APP_CONFIG_PATH"))]
     pub config_path: String,
+    /// Directory of JSON, YAML or TOML route files, runs the router without any external service.
+    #[arg(short, long, env("APP_STORES_PATH"))]
+    pub stores_path: Option<String>,
 }
 
 static FLOW_ROUTER: OnceCell<FlowRouter> = OnceCell::new();
@@ -58,15 +61,17 @@ impl Handler for Redirect {
         let result = router
             .handle(
                 &RequestType::Salvo(&SalvoRequest::new(&req)),
-                &ResponseType::Salvo(&mut SalvoResponse::new(res)),
+                &mut ResponseType::Salvo(&mut SalvoResponse::new(res)),
             )
-            .await
-            .unwrap();
+            .await;
 
         match result {
             FlowRouterResult::Empty(statu_code) => res.status_code(statu_code).render(""),
             FlowRouterResult::Json(content, statu_code) => {
-                res.status_code(statu_code).render(Json(content))
+                res.status_code(statu_code).render(Text::Json(content))
+            }
+            FlowRouterResult::Html(content, statu_code) => {
+                res.status_code(statu_code).render(Text::Html(content))
             }
             FlowRouterResult::PlainText(content, statu_code) => {
                 res.status_code(statu_code).render(content)
@@ -87,9 +92,43 @@ impl Handler for Redirect {
                     .render("");
             }
             FlowRouterResult::Retargeting(url, _script_urls) => res.render(url.to_string()),
-            FlowRouterResult::Error => res
+            FlowRouterResult::Binary(content, content_type, statu_code) => {
+                res.add_header(CONTENT_TYPE, content_type, true)
+                    .unwrap()
+                    .status_code(statu_code)
+                    .body(content);
+            }
+            FlowRouterResult::Headers(headers, statu_code) => {
+                res.headers_mut().extend(headers);
+                res.status_code(statu_code).render("");
+            }
+            FlowRouterResult::Error(error) => res
+                .status_code(error.status_code())
+                .render(Text::Html(router.render_error(&error))),
+        }
+    }
+}
+
+struct MetricsEndpoint;
+
+#[async_trait]
+impl Handler for MetricsEndpoint {
+    async fn handle(
+        &self,
+        _req: &mut Request,
+        _depot: &mut Depot,
+        res: &mut Response,
+        _ctrl: &mut FlowCtrl,
+    ) {
+        match get_flow_router().metrics().render() {
+            Ok((metrics, content_type)) => {
+                res.add_header(CONTENT_TYPE, content_type, true)
+                    .unwrap()
+                    .body(metrics.into_bytes());
+            }
+            Err(error) => res
                 .status_code(StatusCode::INTERNAL_SERVER_ERROR)
-                .render(""),
+                .render(error.to_string()),
         }
     }
 }
@@ -103,7 +142,11 @@ struct ServerConfigResolverMock;
 
 #[async_trait]
 impl ResolvesServerConfig<IoError> for ServerConfigResolverMock {
-    async fn resolve(&self, _client_hello: ClientHello<'_>) -> IoResult<Arc<RustlsConfig>> {
+    async fn resolve(&self, client_hello: ClientHello<'_>) -> IoResult<Arc<RustlsConfig>> {
+        get_flow_router()
+            .metrics()
+            .tls_handshake(client_hello.server_name());
+
         let config = RustlsConfig::new(
             Keycert::new()
                 .cert(include_bytes!("../certs/cert.pem").as_ref())
@@ -132,19 +175,60 @@ async fn main() {
     )
     .unwrap();
 
-    let flow_router = AppBuilder::new(settings)
+    let with_sqlite = settings.sqlite.is_some();
+    let with_redis = settings.redis.is_some();
+    let listen_os_signals = settings.server.listen_os_signals;
+    let admin_listen = settings.admin.listen.clone();
+
+    let app_builder = AppBuilder::new(settings);
+
+    let app_builder = if with_redis {
+        app_builder.with_redis().await
+    } else {
+        app_builder
+    };
+
+    let app_builder = app_builder
         .with_default_modules()
         .with_geo_ip()
-        .with_ua_parser()
-        .with_fluvio()
-        .await
-        .with_dynamo()
-        .await
-        .build();
+        .with_ua_parser();
+
+    let app_builder = match args.stores_path.as_deref() {
+        Some(stores_path) => app_builder
+            .with_hit_registrar(HitRegistrarKind::None)
+            .await
+            .with_file_stores(stores_path),
+        None => {
+            let app_builder = app_builder
+                .with_hit_registrar(HitRegistrarKind::Fluvio)
+                .await;
+
+            let app_builder = if with_sqlite {
+                app_builder.with_sqlite().await
+            } else {
+                app_builder.with_dynamo().await
+            };
+
+            app_builder.with_invalidation(ChangeSubscriberKind::Fluvio)
+        }
+    };
+
+    let flow_router = app_builder.build();
 
     let _ = FLOW_ROUTER.set(flow_router);
 
-    let router = Router::with_path("{**rest_path}").get(Redirect);
+    //metrics are served on their own listener, never on the short domains
+    if let Some(admin_listen) = admin_listen {
+        tokio::spawn(async move {
+            let acceptor = TcpListener::new(admin_listen).bind().await;
+            let router = Router::with_path("metrics").get(MetricsEndpoint);
+
+            Server::new(acceptor).serve(router).await;
+        });
+    }
+
+    //every method reaches the flow router, which answers HEAD, OPTIONS and the route methods
+    let router = Router::with_path("{**rest_path}").goal(Redirect);
 
     println!("{:?}", router);
 
@@ -155,5 +239,44 @@ async fn main() {
 
     let service = Service::new(router).hoop(Logger::new());
     // let acceptor = TcpListener::new("127.0.0.1:5800").bind().await;
-    Server::new(acceptor).serve(service).await;
+    let server = Server::new(acceptor);
+
+    if listen_os_signals {
+        let handle = server.handle();
+
+        tokio::spawn(async move {
+            shutdown_signal().await;
+
+            handle.stop_graceful(None);
+        });
+    }
+
+    server.serve(service).await;
+
+    //the hits still queued are registered before exiting
+    get_flow_router().shutdown().await;
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen to ctrl+c");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen to SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
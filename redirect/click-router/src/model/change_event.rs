@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// Change of a stored entity, published by the router API on every write.
+///
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum ChangeEvent {
+    /// `link` is the stored key of the route, `{domain}%2f{path}`.
+    Route {
+        switch: String,
+        link: String,
+    },
+    UserSettings {
+        user_id: String,
+    },
+    Certificate {
+        server_name: String,
+    },
+}
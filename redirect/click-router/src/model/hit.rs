@@ -1,24 +1,50 @@
-use std::net::IpAddr;
+use std::{collections::BTreeMap, net::IpAddr};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::core::{
+    location::{Country, Location},
+    user_agent::{Device, UserAgent, OS},
+};
+
 use super::Route;
 
+///
+/// Version of the serialized hits, sent along with them to the hit stream. Fields are only ever
+/// added, optional, so consumers read the hits of older routers, version 1 having no version.
+///
+pub const HIT_SCHEMA_VERSION: u16 = 3;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Click<'a> {
-    pub dest: Option<&'a str>,
+pub struct Click {
+    pub dest: Option<String>,
+    /// How the link was reached when it was not a plain click, e.g. `qr`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
+///
+/// Conversion attributed to a click, from the pixel or from a postback.
+///
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Event<'a> {
-    pub click: &'a str,
+pub struct Event {
+    pub click: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Postbacks signed with the API key of the owner, pixels are not.
+    #[serde(default)]
+    pub signed: bool,
 }
 
-#[derive(Clone, Debug, Serialize)]
-pub enum HitData<'a> {
-    Click(&'a Click<'a>),
-    Event(&'a Event<'a>),
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum HitData {
+    Click(Click),
+    Event(Event),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -46,62 +72,216 @@ impl HitRoute {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
-pub struct Hit<'a> {
-    pub id: &'a str,
-    pub data: HitData<'a>,
+///
+/// Request of the hit, as received.
+///
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HitRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referer: Option<String>,
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain_id: Option<String>,
+    pub scheme: Option<String>,
+    pub tls: bool,
+    /// Protocol negotiated over TLS, `h2` or `http/1.1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpn: Option<String>,
+    /// Accept-Language tags, by quality.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    /// Query params allowed by the owner of the route.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub query: BTreeMap<String, String>,
+}
+
+///
+/// Client attributes detected while routing, the others are left to the consumers.
+///
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HitClient {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<UserAgent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<OS>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<Device>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<Country>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HitRouting {
+    /// Switch of the route the request was routed to.
+    pub switch: Option<String>,
+    /// Key of the condition that matched, for conditional routes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+fn first_schema_version() -> u16 {
+    1
+}
+
+///
+/// Owned, so that hits are registered after the request is answered.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Hit {
+    #[serde(default = "first_schema_version")]
+    pub version: u16,
+    pub id: String,
+    pub data: HitData,
     pub route: Option<HitRoute>,
-    pub user_agent: Option<&'a str>,
+    pub user_agent: Option<String>,
     pub ip: Option<IpAddr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
     pub utc: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<HitRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<HitClient>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing: Option<HitRouting>,
+    /// Signed first-party id of the visitor, when cookies are allowed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visitor: Option<String>,
 }
 
-impl<'a> Click<'a> {
-    pub fn new(dest: &'a str) -> Self {
-        Click { dest: Some(dest) }
+impl Click {
+    pub fn new(dest: impl Into<String>) -> Self {
+        Click {
+            dest: Some(dest.into()),
+            source: None,
+        }
+    }
+
+    pub fn with_source(mut self, source: Option<&str>) -> Self {
+        self.source = source.map(str::to_string);
+        self
     }
 }
 
-impl<'a> Event<'a> {
-    pub fn new(click: &'a str) -> Self {
-        Event { click }
+impl Event {
+    pub fn new(click: impl Into<String>) -> Self {
+        Event {
+            click: click.into(),
+            name: None,
+            value: None,
+            currency: None,
+            signed: false,
+        }
     }
 }
 
-impl<'a> Hit<'a> {
+impl Hit {
     pub fn click(
-        id: &'a str,
+        id: String,
         utc: DateTime<Utc>,
-        user_agent: Option<&'a str>,
+        user_agent: Option<String>,
         ip: Option<IpAddr>,
-        click: &'a Click,
+        location: Option<Location>,
+        click: Click,
         route: Option<HitRoute>,
     ) -> Self {
         Self {
+            version: HIT_SCHEMA_VERSION,
             id,
             utc,
             user_agent,
             ip,
+            location,
             route,
-            data: HitData::Click(&click),
+            data: HitData::Click(click),
+            request: None,
+            client: None,
+            routing: None,
+            visitor: None,
         }
     }
 
     pub fn event(
-        id: &'a str,
+        id: String,
         utc: DateTime<Utc>,
-        user_agent: Option<&'a str>,
+        user_agent: Option<String>,
         ip: Option<IpAddr>,
-        event: &'a Event,
+        location: Option<Location>,
+        event: Event,
         route: Option<HitRoute>,
     ) -> Self {
         Self {
+            version: HIT_SCHEMA_VERSION,
             id,
             utc,
             user_agent,
             ip,
+            location,
             route,
-            data: HitData::Event(&event),
+            data: HitData::Event(event),
+            request: None,
+            client: None,
+            routing: None,
+            visitor: None,
         }
     }
+
+    pub fn with_request(mut self, request: HitRequest) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    pub fn with_client(mut self, client: HitClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn with_routing(mut self, routing: HitRouting) -> Self {
+        self.routing = Some(routing);
+        self
+    }
+
+    pub fn with_visitor(mut self, visitor: Option<String>) -> Self {
+        self.visitor = visitor;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_the_hits_of_older_routers() {
+        let hit: Hit = serde_json::from_str(
+            r#"{"id":"1","data":{"Click":{"dest":"https://shop.com"}},"route":null,"user_agent":null,"ip":null,"utc":"2024-05-01T10:00:00Z"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(hit.version, 1);
+        assert!(hit.request.is_none());
+    }
+
+    #[test]
+    fn should_write_the_schema_version() {
+        let hit = Hit::click(
+            "1".to_string(),
+            Utc::now(),
+            None,
+            None,
+            None,
+            Click::new("https://shop.com"),
+            None,
+        )
+        .with_request(HitRequest {
+            referer: Some("https://news.com/".to_string()),
+            ..Default::default()
+        });
+
+        let json = serde_json::to_value(&hit).unwrap();
+
+        assert_eq!(json["version"], HIT_SCHEMA_VERSION);
+        assert_eq!(json["request"]["referer"], "https://news.com/");
+        assert!(json.get("client").is_none());
+    }
 }
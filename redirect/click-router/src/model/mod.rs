@@ -1,8 +1,10 @@
+pub mod change_event;
 pub mod expression;
 pub mod hit;
 pub mod keycert;
 pub mod route;
 pub mod user_settings;
+pub use change_event::ChangeEvent;
 pub use hit::Hit;
 pub use keycert::Keycert;
 pub use route::Route;
@@ -1,13 +1,19 @@
-#[derive(Default, Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ActiveStatus {
     #[default]
     Active,
     Blocked,
 }
 
-pub const SKIP_TRACKING: &'static str = "tracking"; 
+pub const SKIP_TRACKING: &'static str = "tracking";
+/// No visitor cookie is issued for the links of the user.
+pub const SKIP_COOKIES: &'static str = "cookies";
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UserSettings {
     pub user_id: String,
     pub user_email: String,
@@ -41,7 +47,7 @@ impl UserSettings {
             overflow,
             skip,
             allowed_request_params,
-            allowed_destination_params
+            allowed_destination_params,
         }
     }
 }
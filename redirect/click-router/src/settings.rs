@@ -1,16 +1,196 @@
 use config::{Config, ConfigError, Environment, File};
+use link_keys::Links;
 use serde_derive::Deserialize;
 
 use crate::adapters::aws::settings::AWS;
+use crate::adapters::file::settings::FileHits;
 use crate::adapters::fluvio::settings::Fluvio;
 use crate::adapters::geo_ip::settings::GeoIP;
 use crate::adapters::moka::settings::Moka;
+use crate::adapters::rdkafka::settings::Kafka;
+use crate::adapters::redis::settings::Redis;
+use crate::adapters::sqlite::settings::Sqlite;
 use crate::adapters::uaparser::settings::UAParser;
+use crate::utils::async_queue::QueueSettings;
 #[derive(Default, Debug, Deserialize, Clone)]
 #[allow(unused)]
 pub struct Redirect {
     pub not_found_url: String,
     pub index_url: String,
+    /// HTML file of the error page, with `{status}` and `{reason}` placeholders.
+    pub error_page: Option<String>,
+    /// Redirects to the last known main destination of a link when routes are unavailable.
+    #[serde(default)]
+    pub fail_open: bool,
+}
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Qr {
+    /// Width and height of PNG codes, in pixels.
+    pub size: u32,
+    /// Largest size a request may ask for.
+    pub max_size: u32,
+    /// Quiet zone around the code, in modules.
+    pub margin: u32,
+    /// Error correction level, L, M, Q or H. H is always used with a logo.
+    pub ec_level: String,
+    pub dark_color: String,
+    pub light_color: String,
+    /// PNG file drawn in the center of the codes.
+    pub logo: Option<String>,
+    /// Size of the rendered codes kept in memory, in bytes.
+    pub cache_bytes: u64,
+}
+
+impl Default for Qr {
+    fn default() -> Self {
+        Self {
+            size: 512,
+            max_size: 2048,
+            margin: 4,
+            ec_level: "M".to_string(),
+            dark_color: "#000000".to_string(),
+            light_color: "#ffffff".to_string(),
+            logo: None,
+            cache_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Preview {
+    /// Link suffix of the preview page, `/{link}+` by default.
+    pub suffix: String,
+    /// HTML file of the preview page, see `PreviewPage` for the placeholders.
+    pub template: Option<String>,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Self {
+            suffix: "+".to_string(),
+            template: None,
+        }
+    }
+}
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Spool {
+    /// Directory of the segment files.
+    pub path: String,
+    pub segment_bytes: u64,
+    /// Size cap of the spool, the hits beyond are dropped.
+    pub max_bytes: u64,
+    /// How often the spooled hits are replayed.
+    pub replay_millis: u64,
+}
+
+impl Default for Spool {
+    fn default() -> Self {
+        Self {
+            path: "./spool/hits".to_string(),
+            segment_bytes: 16 * 1024 * 1024,
+            max_bytes: 1024 * 1024 * 1024,
+            replay_millis: 5_000,
+        }
+    }
+}
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Conversions {
+    /// Path of the conversion pixel and of the postbacks, e.g. `/_e?click={id}&name=purchase`.
+    pub path: String,
+    /// How long a conversion is attributed to its click.
+    pub attribution_days: u64,
+    /// Clicks remembered by each instance, Redis shares them when configured.
+    pub capacity: u64,
+    /// How far the signed `ts` of a postback may be from now.
+    pub postback_max_age_secs: u64,
+}
+
+impl Default for Conversions {
+    fn default() -> Self {
+        Self {
+            path: "/_e".to_string(),
+            attribution_days: 30,
+            capacity: 1_000_000,
+            postback_max_age_secs: 300,
+        }
+    }
+}
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Visitors {
+    /// Name of the first-party cookie identifying the visitors of the short domains.
+    pub cookie: String,
+    pub max_age_days: i64,
+    /// Key signing the visitor ids, no visitor cookie is issued without it.
+    pub secret: Option<String>,
+}
+
+impl Default for Visitors {
+    fn default() -> Self {
+        Self {
+            cookie: "_sv".to_string(),
+            max_age_days: 365,
+            secret: None,
+        }
+    }
+}
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Admin {
+    /// Address of the admin listener serving `/metrics`, not started when not set.
+    pub listen: Option<String>,
+    /// Server names of the TLS handshakes labeled apart, the others are counted together.
+    pub max_server_names: usize,
+}
+
+impl Default for Admin {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            max_server_names: 1_000,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HitRegistrarKind {
+    Kafka,
+    Fluvio,
+    /// Rotating JSONL files.
+    File,
+    Stdout,
+    None,
+}
+#[derive(Default, Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Hits {
+    /// Where hits are registered, Fluvio when not set, none for file stores.
+    pub registrar: Option<HitRegistrarKind>,
+    pub file: FileHits,
+}
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeSubscriberKind {
+    Kafka,
+    Fluvio,
+    None,
+}
+#[derive(Default, Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Invalidation {
+    /// Change stream evicting the cached routes, Fluvio when not set.
+    pub subscriber: Option<ChangeSubscriberKind>,
 }
 #[derive(Default, Debug, Deserialize, Clone)]
 #[allow(unused)]
@@ -24,11 +204,38 @@ pub struct Server {
 pub struct Settings {
     pub aws: AWS,
     pub fluvio: Fluvio,
+    pub kafka: Option<Kafka>,
+    /// Routes, user settings and certificates from SQLite instead of DynamoDB.
+    pub sqlite: Option<Sqlite>,
     pub moka: Moka,
+    /// Cache shared by the instances, between the moka caches and the stores.
+    pub redis: Option<Redis>,
     pub uaparser: UAParser,
     pub geo_ip: GeoIP,
     pub server: Server,
+    #[serde(default)]
+    pub admin: Admin,
     pub redirect: Redirect,
+    #[serde(default)]
+    pub qr: Qr,
+    #[serde(default)]
+    pub preview: Preview,
+    #[serde(default)]
+    pub links: Links,
+    #[serde(default)]
+    pub conversions: Conversions,
+    #[serde(default)]
+    pub visitors: Visitors,
+    /// Hits waiting to be registered in batches.
+    #[serde(default)]
+    pub hits: Hits,
+    /// Route changes made by the API.
+    #[serde(default)]
+    pub invalidation: Invalidation,
+    #[serde(default)]
+    pub hit_queue: QueueSettings,
+    /// Hits kept on disk while the hit stream is unavailable.
+    pub hit_spool: Option<Spool>,
 }
 const DEV_RUN_MODE: &'static str = "development";
 
@@ -1,94 +1,438 @@
-use std::{sync::Arc, time::Duration};
+//!
+//! Bounded queue of items processed in batches by background consumers.
+//!
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{Error, Result};
+use serde_derive::Deserialize;
 use tokio::{
     sync::{
-        mpsc::{self, Sender},
-        Mutex, Semaphore,
+        mpsc::{self, error::TrySendError, Receiver, Sender},
+        watch, Semaphore,
     },
+    task::JoinHandle,
     time::Instant,
 };
-use tracing::{error, info};
-
-const IDLE_TIMEOUT: u64 = 500;
+use tracing::error;
 
 #[async_trait::async_trait()]
 pub trait BatchProcess<T>: Send + Sync {
-    async fn process(&mut self, batch: Vec<T>) -> Result<()>;
+    async fn process(&self, batch: Vec<T>) -> Result<()>;
+}
+
+///
+/// Takes the items of a full queue, see `OverflowPolicy::Spill`.
+///
+#[async_trait::async_trait()]
+pub trait Spill<T>: Send + Sync {
+    async fn spill(&self, item: T) -> Result<()>;
+}
+
+///
+/// What happens to the items enqueued while the queue is full.
+///
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OverflowPolicy {
+    /// Drops the item, callers never wait.
+    #[default]
+    Drop,
+    /// Waits for room in the queue.
+    Block,
+    /// Hands the item to the spill of the queue, drops it when there is none.
+    Spill,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct QueueSettings {
+    /// Items waiting to be processed, the overflow policy applies beyond.
+    pub capacity: usize,
+    pub batch_size: usize,
+    /// Batches processed at the same time.
+    pub consumers: usize,
+    /// How long a partial batch waits for more items.
+    pub linger_millis: u64,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            batch_size: 100,
+            consumers: 2,
+            linger_millis: 500,
+            overflow: OverflowPolicy::Drop,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueueMetrics {
+    /// Items waiting in the queue.
+    pub depth: usize,
+    pub enqueued: u64,
+    pub dropped: u64,
+    pub spilled: u64,
+    pub processed: u64,
+    /// Items of the batches that failed.
+    pub failed: u64,
+}
+
+#[derive(Debug, Default)]
+struct QueueCounters {
+    enqueued: AtomicU64,
+    dropped: AtomicU64,
+    spilled: AtomicU64,
+    processed: AtomicU64,
+    failed: AtomicU64,
 }
 
-#[derive(Clone, Debug)]
 pub struct AsyncQueue<T: 'static> {
     tx: Sender<T>,
+    overflow: OverflowPolicy,
+    spill: Option<Arc<dyn Spill<T>>>,
+    counters: Arc<QueueCounters>,
+    shutdown: Arc<watch::Sender<bool>>,
+    consumer: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
-impl<T: Send + Sync> AsyncQueue<T> {
+impl<T: 'static> Clone for AsyncQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            overflow: self.overflow,
+            spill: self.spill.clone(),
+            counters: self.counters.clone(),
+            shutdown: self.shutdown.clone(),
+            consumer: self.consumer.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> AsyncQueue<T> {
     pub fn new(
-        processor: Box<dyn BatchProcess<T> + Send + Sync>,
-        batch_size: usize,
-        consumers: usize,
-        duration: Duration,
+        processor: Box<dyn BatchProcess<T>>,
+        spill: Option<Box<dyn Spill<T>>>,
+        settings: &QueueSettings,
     ) -> Self {
-        let (tx, mut rx) = mpsc::channel((consumers) * batch_size);
-        let processor = Arc::new(Mutex::new(processor));
+        let (tx, rx) = mpsc::channel(settings.capacity.max(1));
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let counters = Arc::new(QueueCounters::default());
+
+        let consumer = tokio::spawn(consume(
+            rx,
+            Arc::from(processor),
+            Batching::from(settings),
+            counters.clone(),
+            shutdown_rx,
+        ));
+
+        Self {
+            tx,
+            overflow: settings.overflow,
+            spill: spill.map(Arc::from),
+            counters,
+            shutdown: Arc::new(shutdown),
+            consumer: Arc::new(Mutex::new(Some(consumer))),
+        }
+    }
+
+    ///
+    /// Enqueues the item, or applies the overflow policy when the queue is full.
+    ///
+    pub async fn enqueue(&self, item: T) -> Result<()> {
+        let item = match self.overflow {
+            OverflowPolicy::Block => {
+                self.tx
+                    .send(item)
+                    .await
+                    .map_err(|_| Error::msg("the queue is closed"))?;
+
+                self.counters.enqueued.fetch_add(1, Ordering::Relaxed);
+
+                return Ok(());
+            }
+            _ => match self.tx.try_send(item) {
+                Ok(()) => {
+                    self.counters.enqueued.fetch_add(1, Ordering::Relaxed);
 
-        let _join_handle = tokio::spawn(async move {
-            let permits = Arc::new(Semaphore::new(consumers));
-            let mut batch = Vec::with_capacity(consumers * batch_size);
-            let sleep = tokio::time::sleep(duration);
+                    return Ok(());
+                }
+                Err(TrySendError::Closed(_)) => return Err(Error::msg("the queue is closed")),
+                Err(TrySendError::Full(item)) => item,
+            },
+        };
+
+        match (self.overflow, &self.spill) {
+            (OverflowPolicy::Spill, Some(spill)) => {
+                self.counters.spilled.fetch_add(1, Ordering::Relaxed);
+
+                spill.spill(item).await
+            }
+            _ => {
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
 
-            tokio::pin!(sleep);
-            sleep.as_mut().reset(Instant::now() + duration);
+                Err(Error::msg("the queue is full, the item was dropped"))
+            }
+        }
+    }
 
-            loop {
-                let recv_res = rx.try_recv();
+    ///
+    /// Stops the consumers once the items enqueued so far are processed.
+    ///
+    pub async fn close(&self) {
+        let _ = self.shutdown.send(true);
 
-                if let Some(message) = recv_res.ok() {
-                    batch.push(message);
-                } else {
-                    if batch.len() > 0 { 
-                        tokio::time::sleep(Duration::from_millis(IDLE_TIMEOUT)).await;
+        let consumer = self.consumer.lock().unwrap().take();
+
+        if let Some(consumer) = consumer {
+            if let Err(error) = consumer.await {
+                error!("queue consumer failed: {}", error);
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        QueueMetrics {
+            depth: self.tx.max_capacity() - self.tx.capacity(),
+            enqueued: self.counters.enqueued.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            spilled: self.counters.spilled.load(Ordering::Relaxed),
+            processed: self.counters.processed.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Batching {
+    size: usize,
+    consumers: usize,
+    linger: Duration,
+}
+
+impl From<&QueueSettings> for Batching {
+    fn from(settings: &QueueSettings) -> Self {
+        Self {
+            size: settings.batch_size.max(1),
+            consumers: settings.consumers.max(1),
+            linger: Duration::from_millis(settings.linger_millis),
+        }
+    }
+}
+
+async fn consume<T: Send + 'static>(
+    mut rx: Receiver<T>,
+    processor: Arc<dyn BatchProcess<T>>,
+    batching: Batching,
+    counters: Arc<QueueCounters>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let permits = Arc::new(Semaphore::new(batching.consumers));
+    let mut batch = Vec::with_capacity(batching.size);
+
+    let linger = tokio::time::sleep(batching.linger);
+    tokio::pin!(linger);
+
+    loop {
+        tokio::select! {
+            item = rx.recv() => match item {
+                Some(item) => {
+                    //a batch waits at most the linger time from its first item
+                    if batch.is_empty() {
+                        linger.as_mut().reset(Instant::now() + batching.linger);
                     }
-                    else  {
-                        let message = rx.recv().await.unwrap();
-                        batch.push(message);
+
+                    batch.push(item);
+
+                    if batch.len() < batching.size {
+                        continue;
                     }
                 }
+                None => break,
+            },
+            () = &mut linger, if !batch.is_empty() => {}
+            _ = shutdown.changed() => break,
+        }
 
-                if batch.len() >= batch_size || (batch.len() > 0 && sleep.is_elapsed()) {
-                    let mut drain_count = batch_size;
+        let items = std::mem::replace(&mut batch, Vec::with_capacity(batching.size));
 
-                    if sleep.is_elapsed() {
-                        drain_count = batch.len();
-                    }
+        dispatch(items, &processor, &permits, &counters).await;
+    }
+
+    //the items enqueued before the shutdown are processed too
+    rx.close();
 
-                    let items = batch.drain(..drain_count).collect();
+    while let Some(item) = rx.recv().await {
+        batch.push(item);
 
-                    let processor = processor.clone();
-                    let permits = permits.clone();
+        if batch.len() >= batching.size {
+            let items = std::mem::replace(&mut batch, Vec::with_capacity(batching.size));
 
-                    let permit = permits.acquire_owned().await.unwrap();
-                    tokio::spawn(async move {
-                        let result = processor.lock().await.process(items).await;
+            dispatch(items, &processor, &permits, &counters).await;
+        }
+    }
 
-                        if let Err(error) = result {
-                            error!("{}", error);
-                        }
+    if !batch.is_empty() {
+        dispatch(batch, &processor, &permits, &counters).await;
+    }
 
-                        drop(permit);
-                    });
+    //waits for the batches in progress
+    let _ = permits.acquire_many(batching.consumers as u32).await;
+}
 
-                    sleep.as_mut().reset(Instant::now() + duration);
-                }
+async fn dispatch<T: Send + 'static>(
+    batch: Vec<T>,
+    processor: &Arc<dyn BatchProcess<T>>,
+    permits: &Arc<Semaphore>,
+    counters: &Arc<QueueCounters>,
+) {
+    let permit = permits.clone().acquire_owned().await.unwrap();
+
+    let processor = processor.clone();
+    let counters = counters.clone();
+
+    tokio::spawn(async move {
+        let size = batch.len() as u64;
+
+        match processor.process(batch).await {
+            Ok(()) => counters.processed.fetch_add(size, Ordering::Relaxed),
+            Err(error) => {
+                error!("batch of {} items failed: {}", size, error);
+
+                counters.failed.fetch_add(size, Ordering::Relaxed)
             }
-        });
+        };
+
+        drop(permit);
+    });
+}
 
-        Self { tx }
+#[cfg(test)]
+mod tests {
+    use tokio::sync::Notify;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Collector {
+        batches: Arc<Mutex<Vec<Vec<u32>>>>,
+        release: Option<Arc<Notify>>,
     }
 
-    pub async fn enqueue(&self, item: T) -> Result<()> {
-        info!("Enqueuing a hit");
+    #[async_trait::async_trait()]
+    impl BatchProcess<u32> for Collector {
+        async fn process(&self, batch: Vec<u32>) -> Result<()> {
+            if let Some(release) = &self.release {
+                release.notified().await;
+            }
+
+            self.batches.lock().unwrap().push(batch);
+
+            Ok(())
+        }
+    }
+
+    struct Overflow(Arc<Mutex<Vec<u32>>>);
+
+    #[async_trait::async_trait()]
+    impl Spill<u32> for Overflow {
+        async fn spill(&self, item: u32) -> Result<()> {
+            self.0.lock().unwrap().push(item);
+
+            Ok(())
+        }
+    }
+
+    fn settings(capacity: usize, batch_size: usize, overflow: OverflowPolicy) -> QueueSettings {
+        QueueSettings {
+            capacity,
+            batch_size,
+            consumers: 1,
+            linger_millis: 3_600_000,
+            overflow,
+        }
+    }
+
+    #[tokio::test]
+    async fn should_process_full_batches_and_the_rest_on_close() {
+        let collector = Collector::default();
+        let queue = AsyncQueue::new(
+            Box::new(collector.clone()),
+            None,
+            &settings(10, 2, OverflowPolicy::Drop),
+        );
+
+        for item in 1..=5 {
+            queue.enqueue(item).await.unwrap();
+        }
+
+        queue.close().await;
+
+        assert_eq!(
+            *collector.batches.lock().unwrap(),
+            vec![vec![1, 2], vec![3, 4], vec![5]]
+        );
+        assert_eq!(queue.metrics().processed, 5);
+        assert!(queue.enqueue(6).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_process_partial_batches_after_the_linger_time() {
+        let collector = Collector::default();
+        let queue = AsyncQueue::new(
+            Box::new(collector.clone()),
+            None,
+            &QueueSettings {
+                linger_millis: 20,
+                ..settings(10, 100, OverflowPolicy::Drop)
+            },
+        );
+
+        queue.enqueue(1).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(*collector.batches.lock().unwrap(), vec![vec![1]]);
+    }
+
+    #[tokio::test]
+    async fn should_drop_or_spill_items_of_a_full_queue() {
+        let collector = Collector {
+            release: Some(Arc::new(Notify::new())),
+            ..Default::default()
+        };
+        let queue = AsyncQueue::new(
+            Box::new(collector.clone()),
+            None,
+            &settings(1, 1, OverflowPolicy::Drop),
+        );
+
+        queue.enqueue(1).await.unwrap();
+        assert!(queue.enqueue(2).await.is_err());
+        assert_eq!(queue.metrics().dropped, 1);
+        assert_eq!(queue.metrics().depth, 1);
+
+        let spilled = Arc::new(Mutex::new(Vec::new()));
+        let queue = AsyncQueue::new(
+            Box::new(collector),
+            Some(Box::new(Overflow(spilled.clone()))),
+            &settings(1, 1, OverflowPolicy::Spill),
+        );
+
+        queue.enqueue(1).await.unwrap();
+        queue.enqueue(2).await.unwrap();
 
-        self.tx.send(item).await.map_err(anyhow::Error::msg)
+        assert_eq!(*spilled.lock().unwrap(), vec![2]);
+        assert_eq!(queue.metrics().spilled, 1);
     }
 }
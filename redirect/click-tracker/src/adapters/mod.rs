@@ -26,7 +26,7 @@ use crate::{
         UserSettingsStore,
         aggs::ClickAggsRegistrar,
         location::LocationDetector,
-        session::{Session, SessionDetector},
+        session::{Session, SessionDetector, SessionKey},
     },
 };
 
@@ -113,12 +113,12 @@ impl SessionDetector for SessionDetectorType {
     async fn detect(
         &self,
         route_id: &str,
-        ip_addr: &IpAddr,
+        key: SessionKey<'_>,
         click_time: &DateTime<Utc>,
     ) -> Result<Session> {
         match self {
             SessionDetectorType::Redis(detector) => {
-                detector.detect(route_id, ip_addr, click_time).await
+                detector.detect(route_id, key, click_time).await
             }
         }
     }
@@ -1,12 +1,10 @@
-use std::net::IpAddr;
-
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use redis::Client;
 use redis::Script;
 use tracing::info;
 
-use crate::core::session::{Session, SessionDetector};
+use crate::core::session::{Session, SessionDetector, SessionKey};
 
 use super::settings::Redis;
 
@@ -36,12 +34,12 @@ impl SessionDetector for RedisSessionDetector {
     async fn detect(
         &self,
         route_id: &str,
-        ip_addr: &IpAddr,
+        key: SessionKey<'_>,
         click_time: &DateTime<Utc>,
     ) -> Result<Session> {
         let click_timestamp = click_time.timestamp_millis();
 
-        let root_key = format!("sessions:{}:{}", route_id, ip_addr);
+        let root_key = format!("sessions:{}:{}", route_id, key);
 
         let script_value = r#"
             local current = redis.call('GET', KEYS[1]) or 'none'
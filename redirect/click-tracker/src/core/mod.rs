@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::IpAddr;
 
 use chrono::DateTime;
@@ -32,14 +32,49 @@ pub struct Country {
     pub iso_code: String,
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, Hash, PartialEq)]
+pub struct Asn {
+    pub number: u32,
+    pub organization: Option<String>,
+}
+
+///
+/// Location detected by the router, see `click-router` `core::location::Location`.
+///
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Location {
     pub country: Option<Country>,
+    #[serde(default)]
+    pub continent: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub coordinates: Option<Coordinates>,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub asn: Option<Asn>,
 }
 
 impl Default for Location {
     fn default() -> Self {
-        Self { country: None }
+        Self {
+            country: None,
+            continent: None,
+            region: None,
+            city: None,
+            coordinates: None,
+            time_zone: None,
+            asn: None,
+        }
     }
 }
 
@@ -158,11 +193,21 @@ pub struct Client {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Click {
     pub dest: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Event {
     pub click: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub value: Option<f64>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub signed: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -179,14 +224,80 @@ pub struct HitRoute {
     pub workspace_id: Option<String>,
 }
 
+///
+/// Request of the hit, see `click-router` `model::hit::HitRequest`.
+///
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HitRequest {
+    #[serde(default)]
+    pub referer: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub domain_id: Option<String>,
+    #[serde(default)]
+    pub scheme: Option<String>,
+    #[serde(default)]
+    pub tls: bool,
+    #[serde(default)]
+    pub alpn: Option<String>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+}
+
+///
+/// Client attributes the router already detected.
+///
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HitClient {
+    #[serde(default)]
+    pub user_agent: Option<UserAgent>,
+    #[serde(default)]
+    pub os: Option<OS>,
+    #[serde(default)]
+    pub device: Option<Device>,
+    #[serde(default)]
+    pub country: Option<Country>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HitRouting {
+    #[serde(default)]
+    pub switch: Option<String>,
+    #[serde(default)]
+    pub condition: Option<String>,
+}
+
+fn first_schema_version() -> u16 {
+    1
+}
+
+///
+/// Hit registered by the router, older routers send version 1 hits, without the optional parts.
+///
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Hit {
+    #[serde(default = "first_schema_version")]
+    pub version: u16,
     pub id: String,
     pub data: HitData,
     pub route: Option<HitRoute>,
     pub user_agent: Option<String>,
     pub ip: Option<IpAddr>,
+    #[serde(default)]
+    pub location: Option<Location>,
     pub utc: DateTime<Utc>,
+    #[serde(default)]
+    pub request: Option<HitRequest>,
+    #[serde(default)]
+    pub client: Option<HitClient>,
+    #[serde(default)]
+    pub routing: Option<HitRouting>,
+    /// Signed first-party id of the visitor, when cookies were allowed.
+    #[serde(default)]
+    pub visitor: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -246,6 +357,7 @@ pub struct TrackingPipeContext {
     pub client_ua: Option<UserAgent>,
     pub client_device: Option<Device>,
     pub client_country: Option<Country>,
+    pub client_location: Option<Location>,
     pub spider: bool,
     pub session: Option<Session>,
     pub state: TrackingState,
@@ -262,6 +374,7 @@ impl TrackingPipeContext {
             client_ua: None,
             client_device: None,
             client_country: None,
+            client_location: None,
             session: None,
             state: TrackingState::Ok,
             spider: false,
@@ -311,6 +424,16 @@ pub struct ClickStreamItem {
     pub workspace_id: Option<String>,
     pub created: DateTime<Utc>,
     pub dest: Option<String>,
+    pub source: Option<String>,
+    pub referer: Option<String>,
+    pub domain: Option<String>,
+    pub language: Option<String>,
+    pub switch: Option<String>,
+    /// Name of the conversion, for the events of a click.
+    pub event: Option<String>,
+    pub click_id: Option<String>,
+    pub value: Option<f64>,
+    pub currency: Option<String>,
     pub ip: Option<String>,
     pub continent: Option<String>,
     pub country: Option<String>,
@@ -34,8 +34,27 @@ impl TrackingModule for AggregateModule {
             stream_item.workspace_id = route.workspace_id.clone();
         }
 
-        if let HitData::Click(click) = &context.hit.data {
-            stream_item.dest = click.dest.clone();
+        match &context.hit.data {
+            HitData::Click(click) => {
+                stream_item.dest = click.dest.clone();
+                stream_item.source = click.source.clone();
+            }
+            HitData::Event(event) => {
+                stream_item.event = Some(event.name.clone().unwrap_or("conversion".to_string()));
+                stream_item.click_id = Some(event.click.clone());
+                stream_item.value = event.value;
+                stream_item.currency = event.currency.clone();
+            }
+        }
+
+        if let Some(request) = &context.hit.request {
+            stream_item.referer = request.referer.clone();
+            stream_item.domain = request.host.clone();
+            stream_item.language = request.languages.first().cloned();
+        }
+
+        if let Some(routing) = &context.hit.routing {
+            stream_item.switch = routing.switch.clone();
         }
 
         if let Some(user_agent) = context.client_ua.clone() {
@@ -60,6 +79,11 @@ impl TrackingModule for AggregateModule {
             stream_item.country = Some(country.iso_code.clone());
         }
 
+        if let Some(location) = &context.client_location {
+            stream_item.continent = location.continent.clone();
+            stream_item.location = location.city.clone().or(location.region.clone());
+        }
+
         if let Some(session) = &context.session {
             stream_item.session_clicks = Some(session.count);
             stream_item.session_first = Some(session.first);
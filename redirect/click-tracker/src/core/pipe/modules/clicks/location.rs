@@ -13,6 +13,25 @@ pub struct EnrichLocationModule {
 #[async_trait::async_trait()]
 impl TrackingModule for EnrichLocationModule {
     async fn execute(&mut self, context: &mut TrackingPipeContext) -> Result<()> {
+        //the router already detected the location
+        if let Some(location) = context.hit.location.clone() {
+            context.client_country = location.country.clone();
+            context.client_location = Some(location);
+
+            return Ok(());
+        }
+
+        if let Some(country) = context
+            .hit
+            .client
+            .as_ref()
+            .and_then(|client| client.country.clone())
+        {
+            context.client_country = Some(country);
+
+            return Ok(());
+        }
+
         if let Some(ip) = context.hit.ip.clone() {
             let country = &self.location_detector.detect_country(&ip);
 
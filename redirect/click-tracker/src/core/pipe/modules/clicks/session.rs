@@ -2,7 +2,11 @@ use anyhow::Result;
 
 use crate::{
     adapters::SessionDetectorType,
-    core::{TrackingPipeContext, session::SessionDetector, tracking_pipe::TrackingModule},
+    core::{
+        HitData, TrackingPipeContext,
+        session::{SessionDetector, SessionKey},
+        tracking_pipe::TrackingModule,
+    },
 };
 
 #[derive(Clone)]
@@ -13,16 +17,29 @@ pub struct EnrichSessionModule {
 #[async_trait::async_trait()]
 impl TrackingModule for EnrichSessionModule {
     async fn execute(&mut self, context: &mut TrackingPipeContext) -> Result<()> {
-        if context.hit.ip.is_none() || context.hit.route.is_none() {
+        //conversions are not clicks of the session
+        if let HitData::Event(_) = context.hit.data {
             return Ok(());
         }
 
-        let ip = context.hit.ip.unwrap();
-        let route = context.hit.route.clone().unwrap();
+        let route_id = match context
+            .hit
+            .route
+            .as_ref()
+            .and_then(|route| route.id.as_ref())
+        {
+            Some(route_id) => route_id,
+            None => return Ok(()),
+        };
+
+        let key = match SessionKey::of(context.hit.visitor.as_deref(), context.hit.ip.as_ref()) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
 
         let session = self
             .session_detector
-            .detect(route.id.unwrap().as_str(), &ip, &context.hit.utc)
+            .detect(route_id, key, &context.hit.utc)
             .await?;
 
         context.session = Some(session);
@@ -15,15 +15,23 @@ pub struct EnrichUserAgentModule {
 impl TrackingModule for EnrichUserAgentModule {
     async fn execute(&mut self, context: &mut TrackingPipeContext) -> Result<()> {
         if let Some(user_agent_string) = context.hit.user_agent.clone() {
-            let user_agent = &self
-                .user_agent_detector
-                .parse_user_agent(&user_agent_string);
-            context.client_ua = Some(user_agent.clone());
-
-            let user_os = &self.user_agent_detector.parse_os(&user_agent_string);
-            context.client_os = Some(user_os.clone());
-
-            let user_device = &self.user_agent_detector.parse_device(&user_agent_string);
+            //the router sends what it already detected, the rest is parsed
+            let client = context.hit.client.clone().unwrap_or_default();
+
+            let user_agent = client.user_agent.unwrap_or_else(|| {
+                self.user_agent_detector
+                    .parse_user_agent(&user_agent_string)
+            });
+            context.client_ua = Some(user_agent);
+
+            let user_os = client
+                .os
+                .unwrap_or_else(|| self.user_agent_detector.parse_os(&user_agent_string));
+            context.client_os = Some(user_os);
+
+            let user_device = client
+                .device
+                .unwrap_or_else(|| self.user_agent_detector.parse_device(&user_agent_string));
             context.client_device = Some(user_device.clone());
 
             if let Some(brand) = &user_device.brand {
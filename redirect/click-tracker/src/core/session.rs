@@ -1,4 +1,7 @@
-use std::net::IpAddr;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    net::IpAddr,
+};
 
 use anyhow::Result;
 use chrono::serde::ts_milliseconds;
@@ -12,12 +15,41 @@ pub struct Session {
     pub count: u128,
 }
 
+///
+/// Who the clicks of a session come from, the visitor cookie when there is one, since an IP is
+/// shared behind carrier NATs and rotated by IPv6 privacy addresses.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionKey<'a> {
+    Visitor(&'a str),
+    Ip(&'a IpAddr),
+}
+
+impl<'a> SessionKey<'a> {
+    pub fn of(visitor: Option<&'a str>, ip_addr: Option<&'a IpAddr>) -> Option<Self> {
+        match (visitor, ip_addr) {
+            (Some(visitor), _) => Some(SessionKey::Visitor(visitor)),
+            (None, Some(ip_addr)) => Some(SessionKey::Ip(ip_addr)),
+            (None, None) => None,
+        }
+    }
+}
+
+impl Display for SessionKey<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            SessionKey::Visitor(visitor) => write!(f, "v:{}", visitor),
+            SessionKey::Ip(ip_addr) => write!(f, "{}", ip_addr),
+        }
+    }
+}
+
 #[async_trait::async_trait]
 pub trait SessionDetector {
     async fn detect(
         &self,
         route_id: &str,
-        ip_addr: &IpAddr,
+        key: SessionKey<'_>,
         click_time: &DateTime<Utc>,
     ) -> Result<Session>;
 }
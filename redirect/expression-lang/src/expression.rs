@@ -0,0 +1,558 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Expression {
+    #[serde(alias = "default_operator", alias = "DEFAULT_OPERATOR")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_operator: Option<DefaultOperator>,
+
+    #[serde(alias = "ua", alias = "UA")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ua: Option<UA>,
+
+    #[serde(alias = "os", alias = "OS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<OS>,
+
+    #[serde(alias = "device", alias = "DEVICE")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<Device>,
+
+    #[serde(alias = "os_version", alias = "OS_VERSION")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_version: Option<Version>,
+
+    #[serde(alias = "ua_version", alias = "UA_VERSION")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ua_version: Option<Version>,
+
+    #[serde(alias = "device_class", alias = "DEVICE_CLASS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_class: Option<DeviceClass>,
+
+    #[serde(alias = "lang", alias = "LANG")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<Lang>,
+
+    #[serde(alias = "country", alias = "COUNTRY")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<Country>,
+
+    #[serde(alias = "continent", alias = "CONTINENT")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continent: Option<Continent>,
+
+    #[serde(alias = "region", alias = "REGION")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+
+    #[serde(alias = "city", alias = "CITY")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<City>,
+
+    #[serde(alias = "time_zone", alias = "TIME_ZONE")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<TimeZone>,
+
+    #[serde(alias = "asn", alias = "ASN")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<Asn>,
+
+    #[serde(alias = "org", alias = "ORG")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org: Option<Org>,
+
+    #[serde(alias = "location", alias = "LOCATION")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+
+    #[serde(alias = "date", alias = "DATE")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<Date>,
+    
+    #[serde(alias = "rnd", alias = "RND")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rnd: Option<RND>,
+
+    #[serde(alias = "day_of_week", alias = "DAY_OF_WEEK")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day_of_week: Option<DayOfWeek>,
+
+    #[serde(alias = "day_of_month", alias = "DAY_OF_MONTH")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day_of_month: Option<DayOfMonth>,
+
+    #[serde(alias = "month", alias = "MONTH")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub month: Option<Month>,
+
+    #[serde(alias = "hour", alias = "HOUR")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hour: Option<Hour>,
+
+    #[serde(alias = "datetime", alias = "DATETIME")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datetime: Option<DateTime>,
+
+    #[serde(alias = "and", alias = "AND")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub and: Option<Vec<Box<Expression>>>,
+
+    #[serde(alias = "or", alias = "OR")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub or: Option<Vec<Box<Expression>>>,
+
+    #[serde(alias = "not", alias = "NOT")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not: Option<Box<Expression>>,
+}
+
+impl Expression {
+    ///
+    /// Checks if current expression or subsequential expressions need device to be preloaded.
+    ///
+    pub fn needs_device(&self) -> bool {
+        let curent = self.device.is_some();
+        let and = self.and.is_some()
+            && self
+                .and
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_device());
+        let or = self.or.is_some()
+            && self
+                .or
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_device());
+        let not = self.not.as_ref().is_some_and(|item| item.needs_device());
+
+        curent || and || or || not
+    }
+
+    ///
+    /// Checks if current expression or subsequential expressions need os to be preloaded.
+    ///
+    pub fn needs_os(&self) -> bool {
+        let curent = self.os.is_some() || self.os_version.is_some();
+        let and = self.and.is_some()
+            && self
+                .and
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_os());
+        let or = self.or.is_some()
+            && self
+                .or
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_os());
+        let not = self.not.as_ref().is_some_and(|item| item.needs_os());
+
+        curent || and || or || not
+    }
+
+    ///
+    /// Checks if current expression or subsequential expressions need browser to be preloaded.
+    ///
+    pub fn needs_ua(&self) -> bool {
+        let curent = self.ua.is_some() || self.ua_version.is_some();
+        let and = self.and.is_some()
+            && self
+                .and
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_ua());
+        let or = self.or.is_some()
+            && self
+                .or
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_ua());
+        let not = self.not.as_ref().is_some_and(|item| item.needs_ua());
+
+        curent || and || or || not
+    }
+
+    ///
+    /// Checks if current expression or subsequential expressions need country to be preloaded.
+    ///
+    pub fn needs_country(&self) -> bool {
+        let curent = self.country.is_some();
+        let and = self.and.is_some()
+            && self
+                .and
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_country());
+        let or = self.or.is_some()
+            && self
+                .or
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_country());
+        let not = self.not.as_ref().is_some_and(|item| item.needs_country());
+
+        curent || and || or || not
+    }
+
+    ///
+    /// Checks if current expression or subsequential expressions need the device class to be detected.
+    ///
+    pub fn needs_device_class(&self) -> bool {
+        let curent = self.device_class.is_some();
+        let and = self.and.is_some()
+            && self
+                .and
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_device_class());
+        let or = self.or.is_some()
+            && self
+                .or
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_device_class());
+        let not = self.not.as_ref().is_some_and(|item| item.needs_device_class());
+
+        curent || and || or || not
+    }
+
+    ///
+    /// Checks if current expression or subsequential expressions depend on the date or time of the request.
+    ///
+    pub fn needs_time(&self) -> bool {
+        let curent = self.date.is_some()
+            || self.day_of_week.is_some()
+            || self.day_of_month.is_some()
+            || self.month.is_some()
+            || self.hour.is_some()
+            || self.datetime.is_some();
+        let and = self.and.is_some()
+            && self
+                .and
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_time());
+        let or = self.or.is_some()
+            && self
+                .or
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_time());
+        let not = self.not.as_ref().is_some_and(|item| item.needs_time());
+
+        curent || and || or || not
+    }
+
+    ///
+    /// Checks if current expression or subsequential expressions need location (region, city, asn...) to be preloaded.
+    ///
+    pub fn needs_location(&self) -> bool {
+        let curent = self.continent.is_some()
+            || self.region.is_some()
+            || self.city.is_some()
+            || self.time_zone.is_some()
+            || self.asn.is_some()
+            || self.org.is_some()
+            || self.location.is_some();
+        let and = self.and.is_some()
+            && self
+                .and
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_location());
+        let or = self.or.is_some()
+            && self
+                .or
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|item| item.needs_location());
+        let not = self.not.as_ref().is_some_and(|item| item.needs_location());
+
+        curent || and || or || not
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DefaultOperator {
+    #[serde(alias = "and", alias = "AND")]
+    And,
+    #[serde(alias = "or", alias = "OR")]
+    Or,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Lang {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum UA {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum OS {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Device {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Country {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DeviceClass {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+///
+/// Semver-style comparison of a dotted version, compared up to the given precision:
+/// `GE("17")` matches `17.2`, `LT("5")` matches `4.4`.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Version {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "gt", alias = "GT")]
+    GT(String),
+    #[serde(alias = "ge", alias = "GE")]
+    GE(String),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(String),
+    #[serde(alias = "le", alias = "LE")]
+    LE(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Continent {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Region {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum City {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TimeZone {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Org {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "starts", alias = "STARTS")]
+    Starts(String),
+    #[serde(alias = "ends", alias = "ENDS")]
+    Ends(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Asn {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(u32),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<u32>),
+}
+
+///
+/// A circle on the map, `radius_km` around the given coordinates.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GeoCircle {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_km: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Location {
+    #[serde(alias = "within", alias = "WITHIN")]
+    Within(GeoCircle),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Date {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(String),
+    #[serde(alias = "gt", alias = "GT")]
+    GT(String),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(String),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DayOfMonth {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(u32),
+    #[serde(alias = "gt", alias = "GT")]
+    GT(u32),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(u32),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<u32>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DayOfWeek {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(u32),
+    #[serde(alias = "gt", alias = "GT")]
+    GT(u32),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(u32),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<u32>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Month {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(u32),
+    #[serde(alias = "gt", alias = "GT")]
+    GT(u32),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(u32),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<u32>),
+}
+
+///
+/// Hour of the day, 0 to 23. `Between(9, 17)` matches from 9:00 to 16:59,
+/// `Between(22, 6)` wraps around midnight. Unlike the older ordered fields, which read
+/// "value OP request", `GT(17)` matches the hours after 17.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Hour {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(u32),
+    #[serde(alias = "gt", alias = "GT")]
+    GT(u32),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(u32),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<u32>),
+    #[serde(alias = "between", alias = "BETWEEN")]
+    Between(u32, u32),
+}
+
+///
+/// Local date and time as `YYYY-MM-DDTHH:MM`, in the time zone of the route.
+/// `Between` includes its start and excludes its end.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DateTime {
+    #[serde(alias = "gt", alias = "GT")]
+    GT(String),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(String),
+    #[serde(alias = "between", alias = "BETWEEN")]
+    Between(String, String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RND {
+    #[serde(alias = "eq", alias = "EQ")]
+    EQ(u32),
+    #[serde(alias = "gt", alias = "GT")]
+    GT(u32),
+    #[serde(alias = "lt", alias = "LT")]
+    LT(u32),
+    #[serde(alias = "in", alias = "IN")]
+    IN(Vec<u32>),
+}
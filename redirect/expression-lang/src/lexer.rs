@@ -0,0 +1,159 @@
+use super::{ParseError, Span};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    Word(String),
+    Str(String),
+    LParen,
+    RParen,
+    Comma,
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+///
+/// Characters allowed in an unquoted word (field names, keywords and plain values).
+///
+pub fn is_word_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' || ch == '.' || ch == ':'
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let single = match ch {
+            '(' => Some(TokenKind::LParen),
+            ')' => Some(TokenKind::RParen),
+            ',' => Some(TokenKind::Comma),
+            _ => None,
+        };
+
+        if let Some(kind) = single {
+            chars.next();
+            tokens.push(Token {
+                kind,
+                span: Span::new(start, start + 1),
+            });
+            continue;
+        }
+
+        if ch == '=' {
+            chars.next();
+            let mut end = start + 1;
+
+            //both "=" and "==" are accepted
+            if let Some(&(next, '=')) = chars.peek() {
+                chars.next();
+                end = next + 1;
+            }
+
+            tokens.push(Token {
+                kind: TokenKind::Eq,
+                span: Span::new(start, end),
+            });
+            continue;
+        }
+
+        if ch == '>' || ch == '<' {
+            chars.next();
+            let mut end = start + 1;
+            let mut or_equal = false;
+
+            if let Some(&(next, '=')) = chars.peek() {
+                chars.next();
+                end = next + 1;
+                or_equal = true;
+            }
+
+            let kind = match (ch, or_equal) {
+                ('>', false) => TokenKind::Gt,
+                ('>', true) => TokenKind::Ge,
+                ('<', false) => TokenKind::Lt,
+                _ => TokenKind::Le,
+            };
+
+            tokens.push(Token {
+                kind,
+                span: Span::new(start, end),
+            });
+            continue;
+        }
+
+        if ch == '"' || ch == '\'' {
+            let quote = ch;
+            chars.next();
+
+            let mut value = String::new();
+            let mut end = None;
+
+            while let Some((index, ch)) = chars.next() {
+                if ch == quote {
+                    end = Some(index + 1);
+                    break;
+                }
+
+                if ch == '\\' {
+                    match chars.next() {
+                        Some((_, escaped)) => value.push(escaped),
+                        None => break,
+                    }
+                    continue;
+                }
+
+                value.push(ch);
+            }
+
+            let end = end.ok_or_else(|| {
+                ParseError::new("unterminated string", Span::new(start, input.len()))
+            })?;
+
+            tokens.push(Token {
+                kind: TokenKind::Str(value),
+                span: Span::new(start, end),
+            });
+            continue;
+        }
+
+        if is_word_char(ch) {
+            let mut end = start;
+
+            while let Some(&(index, ch)) = chars.peek() {
+                if !is_word_char(ch) {
+                    break;
+                }
+
+                end = index + ch.len_utf8();
+                chars.next();
+            }
+
+            tokens.push(Token {
+                kind: TokenKind::Word(input[start..end].to_string()),
+                span: Span::new(start, end),
+            });
+            continue;
+        }
+
+        return Err(ParseError::new(
+            format!("unexpected character '{}'", ch),
+            Span::new(start, start + ch.len_utf8()),
+        ));
+    }
+
+    Ok(tokens)
+}
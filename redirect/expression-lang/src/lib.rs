@@ -0,0 +1,498 @@
+//!
+//! A small textual language for route conditions, compiled to `Expression`:
+//!
+//! `country in (us, ca) and (os = "iOS" or device starts iPad) and not day_of_week in (0, 6)`
+//!
+//! `hour between (9, 17) and datetime < 2025-12-24T12:00`
+//!
+//! Operators are `=`, `>`, `>=`, `<`, `<=`, `in`, `between`, `starts` and `ends`; conditions
+//! are combined with `and`, `or`, `not` and parentheses.
+//!
+//! The router evaluates the expressions, the API prints them; both check them with `validate`.
+//!
+//the `&'static str` consts are spelled out like in the other crates of the workspace
+#![allow(clippy::redundant_static_lifetimes)]
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{de::Error as DeError, Deserialize, Deserializer};
+
+use crate::expression::Expression;
+
+pub mod expression;
+mod lexer;
+mod parser;
+mod printer;
+mod validator;
+
+pub use parser::{parse, DEVICE_CLASSES};
+pub use printer::print;
+pub use validator::{validate, ValidationError};
+
+pub const DATE_FORMAT: &'static str = "%Y%m%d";
+pub const DATETIME_FORMAT: &'static str = "%Y-%m-%dT%H:%M";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    ///
+    /// Renders the error under the offending line of the input, e.g.
+    ///
+    /// ```text
+    /// country = us and os ~ ios
+    ///                     ^ expected an operator: =, >, >=, <, <=, in, between, starts or ends
+    /// ```
+    ///
+    pub fn render(&self, input: &str) -> String {
+        let start = self.span.start.min(input.len());
+        let end = self.span.end.clamp(start, input.len());
+
+        let line_start = input[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[start..].find('\n').map_or(input.len(), |i| start + i);
+
+        let column = input[line_start..start].chars().count();
+        let width = input[start..end.min(line_end)].chars().count().max(1);
+
+        format!(
+            "{}\n{}{} {}",
+            &input[line_start..line_end],
+            " ".repeat(column),
+            "^".repeat(width),
+            self.message
+        )
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", print(self))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExpressionSource {
+    Text(String),
+    Model(Box<Expression>),
+}
+
+///
+/// Deserializes a condition given either as an `Expression` object or as condition language text,
+/// checking its values with `validate`.
+///
+pub fn deserialize_expression<'de, D>(deserializer: D) -> Result<Expression, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let expression = match ExpressionSource::deserialize(deserializer)? {
+        ExpressionSource::Model(expression) => *expression,
+        ExpressionSource::Text(text) => {
+            parse(&text).map_err(|error| D::Error::custom(error.render(&text)))?
+        }
+    };
+
+    validate(&expression).map_err(D::Error::custom)?;
+
+    Ok(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::{
+        Continent, Country, DateTime, DayOfWeek, DefaultOperator, Device, DeviceClass, GeoCircle,
+        Hour, Lang, Location, Version, OS, RND,
+    };
+
+    use super::*;
+
+    fn canonical(input: &str) -> String {
+        print(&parse(input).unwrap())
+    }
+
+    #[test]
+    fn should_parse_nested_condition() {
+        let expression = parse(
+            r#"country in (us, ca) and (os = "iOS" or device starts "iPad") and not day_of_week in (0,6)"#,
+        )
+        .unwrap();
+
+        assert_eq!(expression.default_operator, Some(DefaultOperator::And));
+        assert_eq!(
+            expression.country,
+            Some(Country::IN(vec!["us".to_string(), "ca".to_string()]))
+        );
+
+        let and = expression.and.unwrap();
+        assert_eq!(and.len(), 2);
+
+        assert_eq!(and[0].default_operator, None);
+        assert_eq!(and[0].os, Some(OS::EQ("iOS".to_string())));
+        assert_eq!(and[0].device, Some(Device::Starts("iPad".to_string())));
+
+        let not = and[1].not.as_ref().unwrap();
+        assert_eq!(not.day_of_week, Some(DayOfWeek::IN(vec![0, 6])));
+    }
+
+    #[test]
+    fn should_accept_case_insensitive_keywords() {
+        let expression = parse("LANG IN (en, de) OR rnd < 30").unwrap();
+
+        assert_eq!(
+            expression.lang,
+            Some(Lang::IN(vec!["en".to_string(), "de".to_string()]))
+        );
+        //stored as "value OP request"
+        assert_eq!(expression.rnd, Some(RND::GT(30)));
+    }
+
+    #[test]
+    fn should_keep_repeated_fields_as_children() {
+        let expression = parse("country = us or country = ca").unwrap();
+
+        assert_eq!(expression.country, Some(Country::EQ("us".to_string())));
+        assert_eq!(
+            expression.or.unwrap()[0].country,
+            Some(Country::EQ("ca".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_print_canonical_form() {
+        assert_eq!(
+            canonical(r#"country in (us,ca) and (os = "iOS" or device starts "iPad") and not day_of_week in (0,6)"#),
+            "country in (us, ca) and (os = iOS or device starts iPad) and not day_of_week in (0, 6)"
+        );
+
+        assert_eq!(
+            canonical("not (ua = Chrome or ua = Edge)"),
+            "not (ua = Chrome or ua = Edge)"
+        );
+        assert_eq!(
+            canonical(r#"ua = "in" and os = "Mac OS X""#),
+            r#"ua = "in" and os = "Mac OS X""#
+        );
+    }
+
+    #[test]
+    fn should_round_trip_canonical_form() {
+        let inputs = [
+            "country = us",
+            "country = us and os = ios or lang = en",
+            "(country = us or country = ca) and not (rnd > 50 and date <= 20250101)",
+            "day_of_month in (1, 15) or month = 12 and not not ua ends bot",
+            "region in (us-ca, us-nv) and location in (37.77, -122.41, 25) or asn = 15169",
+            "os = Android and os_version < 9 or device_class in (tv, bot) and ua_version >= 120.0.1",
+            "hour between (9, 17) and day_of_week in (1, 2, 3, 4, 5) or datetime > 2025-12-24T12:00",
+        ];
+
+        for input in inputs {
+            let printed = canonical(input);
+
+            assert_eq!(canonical(&printed), printed);
+        }
+    }
+
+    #[test]
+    fn should_round_trip_serde_model() {
+        let json = r#"{
+            "DEFAULT_OPERATOR": "AND",
+            "country": { "IN": ["us", "ca"] },
+            "AND": [{ "os": { "EQ": "iOS" }, "device": { "STARTS": "iPad" } }],
+            "not": { "day_of_week": { "in": [0, 6] } }
+        }"#;
+
+        let expression: Expression = serde_json::from_str(json).unwrap();
+
+        let printed = print(&expression);
+        let reparsed = parse(&printed).unwrap();
+
+        assert_eq!(print(&reparsed), printed);
+        assert_eq!(reparsed.country, expression.country);
+        assert_eq!(
+            reparsed.and.as_ref().unwrap()[0].os,
+            expression.and.unwrap()[0].os
+        );
+    }
+
+    #[test]
+    fn should_parse_location_fields() {
+        let expression = parse(
+            r#"continent = eu and city = "Frankfurt am Main" and location in (50.11, 8.68, 30)"#,
+        )
+        .unwrap();
+
+        assert_eq!(expression.continent, Some(Continent::EQ("eu".to_string())));
+        assert_eq!(
+            expression.location,
+            Some(Location::Within(GeoCircle {
+                latitude: 50.11,
+                longitude: 8.68,
+                radius_km: 30.0
+            }))
+        );
+
+        let error = parse("location in (50.11, 8.68)").unwrap_err();
+
+        assert_eq!(error.message, "expected (latitude, longitude, radius_km)");
+    }
+
+    #[test]
+    fn should_parse_version_and_device_class() {
+        let expression = parse("os = iOS and os_version >= 17 and device_class = Mobile").unwrap();
+
+        assert_eq!(expression.os_version, Some(Version::GE("17".to_string())));
+        assert_eq!(
+            expression.device_class,
+            Some(DeviceClass::EQ("mobile".to_string()))
+        );
+
+        let error = parse("os_version > 17.x").unwrap_err();
+
+        assert_eq!(error.span, Span::new(13, 17));
+
+        let error = parse("device_class = fridge").unwrap_err();
+
+        assert!(error.message.starts_with("expected a device class"));
+
+        let error = parse("rnd >= 10").unwrap_err();
+
+        assert_eq!(
+            error.message,
+            "operator '>=' is not supported for field 'rnd'"
+        );
+
+        let error = parse("date > 20250101").unwrap_err();
+
+        assert_eq!(
+            error.message,
+            "operator '>' is not supported for field 'date'"
+        );
+    }
+
+    #[test]
+    fn should_parse_schedule_conditions() {
+        let expression = parse(
+            r#"hour between (22, 6) and datetime between ("2025-11-28T00:00", 2025-12-01T00:00)"#,
+        )
+        .unwrap();
+
+        assert_eq!(expression.hour, Some(Hour::Between(22, 6)));
+        assert_eq!(
+            expression.datetime,
+            Some(DateTime::Between(
+                "2025-11-28T00:00".to_string(),
+                "2025-12-01T00:00".to_string()
+            ))
+        );
+        assert_eq!(
+            print(&expression),
+            "hour between (22, 6) and datetime between (2025-11-28T00:00, 2025-12-01T00:00)"
+        );
+
+        let error = parse("hour between (9)").unwrap_err();
+
+        assert_eq!(error.message, "expected (from, to)");
+
+        let error = parse("datetime > 2025-11-28").unwrap_err();
+
+        assert_eq!(error.span, Span::new(11, 21));
+
+        let error = parse("hour = 24").unwrap_err();
+
+        assert_eq!(error.message, "expected a number between 0 and 23");
+    }
+
+    #[test]
+    fn should_report_unknown_field_span() {
+        let error = parse("country = us and planet = mars").unwrap_err();
+
+        assert_eq!(error.span, Span::new(17, 23));
+        assert!(error.message.starts_with("unknown field 'planet'"));
+    }
+
+    #[test]
+    fn should_report_bad_operator_span() {
+        let error = parse("lang starts en").unwrap_err();
+
+        assert_eq!(error.span, Span::new(5, 11));
+        assert_eq!(
+            error.render("lang starts en"),
+            "lang starts en\n     ^^^^^^ operator 'starts' is not supported for field 'lang'"
+        );
+    }
+
+    #[test]
+    fn should_report_out_of_range_values() {
+        let error = parse("day_of_week in (1, 7)").unwrap_err();
+
+        assert_eq!(error.span, Span::new(19, 20));
+
+        let error = parse("date = 2024-13-01").unwrap_err();
+
+        assert_eq!(error.span, Span::new(7, 17));
+    }
+
+    #[test]
+    fn should_report_unclosed_groups() {
+        let error = parse("(country = us or os = ios").unwrap_err();
+
+        assert_eq!(error.span, Span::new(0, 25));
+        assert_eq!(error.message, "unclosed '('");
+
+        let error = parse(r#"os = "ios"#).unwrap_err();
+
+        assert_eq!(error.message, "unterminated string");
+    }
+
+    #[test]
+    fn should_report_trailing_tokens() {
+        let error = parse("country = us os = ios").unwrap_err();
+
+        assert_eq!(error.span, Span::new(13, 15));
+    }
+
+    #[test]
+    fn should_deserialize_text_conditions() {
+        #[derive(Deserialize)]
+        struct Holder {
+            #[serde(deserialize_with = "deserialize_expression")]
+            condition: Expression,
+        }
+
+        let holder: Holder =
+            serde_json::from_str(r#"{ "condition": "country = us and rnd < 10" }"#).unwrap();
+
+        assert_eq!(
+            holder.condition.country,
+            Some(Country::EQ("us".to_string()))
+        );
+        assert_eq!(holder.condition.rnd, Some(RND::GT(10)));
+
+        let result: Result<Holder, _> = serde_json::from_str(r#"{ "condition": "country ~ us" }"#);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_validate_model_values() {
+        let invalid = [
+            r#"{ "hour": { "EQ": 24 } }"#,
+            r#"{ "hour": { "BETWEEN": [9, 25] } }"#,
+            r#"{ "rnd": { "LT": 101 } }"#,
+            r#"{ "AND": [{ "day_of_week": { "IN": [1, 7] } }] }"#,
+            r#"{ "month": { "IN": [] } }"#,
+            r#"{ "date": { "EQ": "2024-05-15" } }"#,
+            r#"{ "datetime": { "BETWEEN": ["2025-12-01T00:00", "2025-11-28T00:00"] } }"#,
+            r#"{ "time_zone": { "EQ": "Europe/Atlantis" } }"#,
+            r#"{ "os_version": { "GE": "17.x" } }"#,
+            r#"{ "device_class": { "EQ": "fridge" } }"#,
+            r#"{ "country": { "IN": [] } }"#,
+            r#"{ "location": { "WITHIN": { "latitude": 91, "longitude": 0, "radius_km": 1 } } }"#,
+        ];
+
+        for json in invalid {
+            let expression: Expression = serde_json::from_str(json).unwrap();
+
+            assert!(validate(&expression).is_err(), "{}", json);
+        }
+
+        let expression: Expression = serde_json::from_str(
+            r#"{ "time_zone": { "IN": ["europe/berlin", "America/New_York"] }, "hour": { "BETWEEN": [22, 24] } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(validate(&expression), Ok(()));
+        assert_eq!(
+            validate(&parse(r#"time_zone = "Mars/Olympus""#).unwrap())
+                .unwrap_err()
+                .message,
+            "unknown time zone 'Mars/Olympus'"
+        );
+    }
+
+    #[test]
+    fn should_reject_empty_groups() {
+        #[derive(Deserialize, Debug)]
+        struct Holder {
+            #[serde(deserialize_with = "deserialize_expression")]
+            condition: Expression,
+        }
+
+        //an empty "or" never matches, but would print as "country = us"
+        let result: Result<Holder, _> = serde_json::from_str(
+            r#"{ "condition": { "country": { "EQ": "us" }, "OR": [], "DEFAULT_OPERATOR": "AND" } }"#,
+        );
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("empty 'or' group"));
+
+        let holder: Holder = serde_json::from_str(
+            r#"{ "condition": { "country": { "EQ": "us" }, "OR": [{ "os": { "EQ": "iOS" } }], "DEFAULT_OPERATOR": "AND" } }"#,
+        )
+        .unwrap();
+
+        let printed = print(&holder.condition);
+
+        assert_eq!(printed, "country = us and os = iOS");
+        let reprinted = canonical(&printed);
+
+        assert_eq!(canonical(&reprinted), reprinted);
+
+        let not: Expression =
+            serde_json::from_str(r#"{ "country": { "EQ": "us" }, "not": {} }"#).unwrap();
+
+        assert_eq!(validate(&not).unwrap_err().message, "empty 'not' group");
+        assert_eq!(
+            validate(&Expression::default()).unwrap_err().message,
+            "empty condition"
+        );
+    }
+}
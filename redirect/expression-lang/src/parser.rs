@@ -0,0 +1,736 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::expression::{
+    Asn, City, Continent, Country, Date, DateTime, DayOfMonth, DayOfWeek, DefaultOperator, Device,
+    DeviceClass, Expression, GeoCircle, Hour, Lang, Location, Month, Org, Region, TimeZone,
+    Version, OS, RND, UA,
+};
+
+use super::{
+    lexer::{tokenize, Token, TokenKind},
+    ParseError, Span, DATETIME_FORMAT, DATE_FORMAT,
+};
+
+const AND: &'static str = "and";
+const OR: &'static str = "or";
+const NOT: &'static str = "not";
+const IN: &'static str = "in";
+const STARTS: &'static str = "starts";
+const ENDS: &'static str = "ends";
+const BETWEEN: &'static str = "between";
+
+pub const KEYWORDS: [&'static str; 7] = [AND, OR, NOT, IN, STARTS, ENDS, BETWEEN];
+
+pub const FIELDS: [&'static str; 22] = [
+    "ua",
+    "os",
+    "device",
+    "os_version",
+    "ua_version",
+    "device_class",
+    "lang",
+    "country",
+    "continent",
+    "region",
+    "city",
+    "time_zone",
+    "asn",
+    "org",
+    "location",
+    "date",
+    "rnd",
+    "day_of_week",
+    "day_of_month",
+    "month",
+    "hour",
+    "datetime",
+];
+
+pub const DEVICE_CLASSES: [&'static str; 6] = ["mobile", "tablet", "desktop", "tv", "bot", "other"];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Not(Box<Node>),
+    /// A single comparison, already built as a one-field expression.
+    Test(Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Starts,
+    Ends,
+    In,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Between,
+}
+
+impl Op {
+    fn name(&self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Starts => STARTS,
+            Op::Ends => ENDS,
+            Op::In => IN,
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Between => BETWEEN,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Literal {
+    text: String,
+    span: Span,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    input_len: usize,
+}
+
+pub fn parse(input: &str) -> Result<Expression, ParseError> {
+    let tokens = tokenize(input)?;
+
+    if tokens.is_empty() {
+        return Err(ParseError::new(
+            "empty condition",
+            Span::new(0, input.len()),
+        ));
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        input_len: input.len(),
+    };
+
+    let node = parser.parse_or()?;
+
+    if let Some(token) = parser.peek() {
+        return Err(ParseError::new(
+            "expected 'and', 'or' or end of condition",
+            token.span.clone(),
+        ));
+    }
+
+    Ok(lower(node))
+}
+
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    if let TokenKind::Word(word) = &token.kind {
+        return word.eq_ignore_ascii_case(keyword);
+    }
+
+    false
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+
+        if token.is_some() {
+            self.position += 1;
+        }
+
+        token
+    }
+
+    fn end_span(&self) -> Span {
+        Span::new(self.input_len, self.input_len)
+    }
+
+    fn next_or_eof(&mut self, expected: &str) -> Result<Token, ParseError> {
+        let end = self.end_span();
+
+        self.next()
+            .ok_or_else(|| ParseError::new(format!("expected {}", expected), end))
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.peek()
+            .is_some_and(|token| is_keyword(token, keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Node, ParseError> {
+        let mut items = vec![self.parse_and()?];
+
+        while self.peek_keyword(OR) {
+            self.next();
+            items.push(self.parse_and()?);
+        }
+
+        if items.len() == 1 {
+            return Ok(items.remove(0));
+        }
+
+        Ok(Node::Or(items))
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ParseError> {
+        let mut items = vec![self.parse_unary()?];
+
+        while self.peek_keyword(AND) {
+            self.next();
+            items.push(self.parse_unary()?);
+        }
+
+        if items.len() == 1 {
+            return Ok(items.remove(0));
+        }
+
+        Ok(Node::And(items))
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, ParseError> {
+        if self.peek_keyword(NOT) {
+            self.next();
+            return Ok(Node::Not(Box::new(self.parse_unary()?)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, ParseError> {
+        if let Some(Token {
+            kind: TokenKind::LParen,
+            span,
+        }) = self.peek().cloned()
+        {
+            self.next();
+
+            let node = self.parse_or()?;
+
+            match self.next() {
+                Some(Token {
+                    kind: TokenKind::RParen,
+                    ..
+                }) => return Ok(node),
+                Some(token) => {
+                    return Err(ParseError::new("expected ')'", token.span));
+                }
+                None => {
+                    return Err(ParseError::new(
+                        "unclosed '('",
+                        Span::new(span.start, self.input_len),
+                    ));
+                }
+            }
+        }
+
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Node, ParseError> {
+        let token = self.next_or_eof("a condition")?;
+
+        let field = match &token.kind {
+            TokenKind::Word(word) if !KEYWORDS.iter().any(|k| word.eq_ignore_ascii_case(k)) => {
+                word.to_ascii_lowercase()
+            }
+            _ => return Err(ParseError::new("expected a condition field", token.span)),
+        };
+
+        if !FIELDS.contains(&field.as_str()) {
+            return Err(ParseError::new(
+                format!(
+                    "unknown field '{}', expected one of: {}",
+                    field,
+                    FIELDS.join(", ")
+                ),
+                token.span,
+            ));
+        }
+
+        let op_token = self.next_or_eof("an operator")?;
+
+        let op = match &op_token.kind {
+            TokenKind::Eq => Op::Eq,
+            TokenKind::Gt => Op::Gt,
+            TokenKind::Ge => Op::Ge,
+            TokenKind::Lt => Op::Lt,
+            TokenKind::Le => Op::Le,
+            _ if is_keyword(&op_token, IN) => Op::In,
+            _ if is_keyword(&op_token, STARTS) => Op::Starts,
+            _ if is_keyword(&op_token, ENDS) => Op::Ends,
+            _ if is_keyword(&op_token, BETWEEN) => Op::Between,
+            _ => {
+                return Err(ParseError::new(
+                    "expected an operator: =, >, >=, <, <=, in, between, starts or ends",
+                    op_token.span,
+                ))
+            }
+        };
+
+        let values = if op == Op::In || op == Op::Between {
+            self.parse_list()?
+        } else {
+            vec![self.parse_literal()?]
+        };
+
+        let expression = build(&field, op, &op_token.span, values)?;
+
+        Ok(Node::Test(Box::new(expression)))
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, ParseError> {
+        let token = self.next_or_eof("a value")?;
+
+        match token.kind {
+            TokenKind::Word(text) | TokenKind::Str(text) => Ok(Literal {
+                text,
+                span: token.span,
+            }),
+            _ => Err(ParseError::new("expected a value", token.span)),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<Literal>, ParseError> {
+        let open = self.next_or_eof("'('")?;
+
+        if open.kind != TokenKind::LParen {
+            return Err(ParseError::new(
+                "expected '(' after the operator",
+                open.span,
+            ));
+        }
+
+        let mut values = vec![self.parse_literal()?];
+
+        loop {
+            let token = self.next().ok_or_else(|| {
+                ParseError::new("unclosed '('", Span::new(open.span.start, self.input_len))
+            })?;
+
+            match token.kind {
+                TokenKind::Comma => values.push(self.parse_literal()?),
+                TokenKind::RParen => return Ok(values),
+                _ => return Err(ParseError::new("expected ',' or ')'", token.span)),
+            }
+        }
+    }
+}
+
+fn unsupported(field: &str, op: Op, span: &Span) -> ParseError {
+    ParseError::new(
+        format!(
+            "operator '{}' is not supported for field '{}'",
+            op.name(),
+            field
+        ),
+        span.clone(),
+    )
+}
+
+fn first(values: Vec<Literal>) -> String {
+    values
+        .into_iter()
+        .next()
+        .map(|l| l.text)
+        .unwrap_or_default()
+}
+
+fn texts(values: Vec<Literal>) -> Vec<String> {
+    values.into_iter().map(|l| l.text).collect()
+}
+
+fn dates(values: Vec<Literal>) -> Result<Vec<String>, ParseError> {
+    values
+        .into_iter()
+        .map(|literal| {
+            NaiveDate::parse_from_str(&literal.text, DATE_FORMAT)
+                .map(|_| literal.text.clone())
+                .map_err(|_| ParseError::new("expected a date as YYYYMMDD", literal.span))
+        })
+        .collect()
+}
+
+fn datetimes(values: Vec<Literal>) -> Result<Vec<String>, ParseError> {
+    values
+        .into_iter()
+        .map(|literal| {
+            NaiveDateTime::parse_from_str(&literal.text, DATETIME_FORMAT)
+                .map(|_| literal.text.clone())
+                .map_err(|_| {
+                    ParseError::new("expected a date and time as YYYY-MM-DDTHH:MM", literal.span)
+                })
+        })
+        .collect()
+}
+
+///
+/// Checks the `(from, to)` bounds of a `between` condition.
+///
+fn bounds<T>(mut values: Vec<T>, span: &Span) -> Result<(T, T), ParseError> {
+    if values.len() != 2 {
+        return Err(ParseError::new("expected (from, to)", span.clone()));
+    }
+
+    let to = values.pop().unwrap();
+    let from = values.pop().unwrap();
+
+    Ok((from, to))
+}
+
+pub fn is_version(text: &str) -> bool {
+    let parts: Vec<&str> = text.split('.').collect();
+
+    parts.len() <= 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|ch| ch.is_ascii_digit()))
+}
+
+fn versions(values: Vec<Literal>) -> Result<Vec<String>, ParseError> {
+    values
+        .into_iter()
+        .map(|literal| {
+            if is_version(&literal.text) {
+                return Ok(literal.text);
+            }
+
+            Err(ParseError::new(
+                "expected a version as major[.minor[.patch]]",
+                literal.span,
+            ))
+        })
+        .collect()
+}
+
+fn device_classes(values: Vec<Literal>) -> Result<Vec<String>, ParseError> {
+    values
+        .into_iter()
+        .map(|literal| {
+            let text = literal.text.to_ascii_lowercase();
+
+            if DEVICE_CLASSES.contains(&text.as_str()) {
+                return Ok(text);
+            }
+
+            Err(ParseError::new(
+                format!("expected a device class: {}", DEVICE_CLASSES.join(", ")),
+                literal.span,
+            ))
+        })
+        .collect()
+}
+
+fn numbers(values: Vec<Literal>, min: u32, max: u32) -> Result<Vec<u32>, ParseError> {
+    values
+        .into_iter()
+        .map(|literal| match literal.text.parse::<u32>() {
+            Ok(num) if num >= min && num <= max => Ok(num),
+            _ => Err(ParseError::new(
+                format!("expected a number between {} and {}", min, max),
+                literal.span,
+            )),
+        })
+        .collect()
+}
+
+///
+/// Parses `(latitude, longitude, radius_km)` of a `location in (..)` condition.
+///
+fn geo_circle(values: Vec<Literal>, span: &Span) -> Result<GeoCircle, ParseError> {
+    if values.len() != 3 {
+        return Err(ParseError::new(
+            "expected (latitude, longitude, radius_km)",
+            span.clone(),
+        ));
+    }
+
+    let mut nums = Vec::new();
+
+    for (literal, (min, max)) in
+        values
+            .into_iter()
+            .zip([(-90.0, 90.0), (-180.0, 180.0), (0.0, f64::MAX)])
+    {
+        match literal.text.parse::<f64>() {
+            Ok(num) if num >= min && num <= max => nums.push(num),
+            _ => {
+                return Err(ParseError::new(
+                    format!("expected a number between {} and {}", min, max),
+                    literal.span,
+                ))
+            }
+        }
+    }
+
+    Ok(GeoCircle {
+        latitude: nums[0],
+        longitude: nums[1],
+        radius_km: nums[2],
+    })
+}
+
+macro_rules! text_condition {
+    ($enum:ident, $field:expr, $op:expr, $span:expr, $values:expr) => {
+        match $op {
+            Op::Eq => $enum::EQ(first($values)),
+            Op::Starts => $enum::Starts(first($values)),
+            Op::Ends => $enum::Ends(first($values)),
+            Op::In => $enum::IN(texts($values)),
+            _ => return Err(unsupported($field, $op, $span)),
+        }
+    };
+}
+
+//the stored ordered fields read "value OP request", so `rnd > 30` is `RND::LT(30)`
+macro_rules! number_condition {
+    ($enum:ident, $field:expr, $op:expr, $span:expr, $values:expr, $min:expr, $max:expr) => {{
+        let mut nums = numbers($values, $min, $max)?;
+
+        match $op {
+            Op::Eq => $enum::EQ(nums.remove(0)),
+            Op::Gt => $enum::LT(nums.remove(0)),
+            Op::Lt => $enum::GT(nums.remove(0)),
+            Op::In => $enum::IN(nums),
+            _ => return Err(unsupported($field, $op, $span)),
+        }
+    }};
+}
+
+macro_rules! version_condition {
+    ($field:expr, $op:expr, $span:expr, $values:expr) => {{
+        let mut values = versions($values)?;
+
+        match $op {
+            Op::Eq => Version::EQ(values.remove(0)),
+            Op::Gt => Version::GT(values.remove(0)),
+            Op::Ge => Version::GE(values.remove(0)),
+            Op::Lt => Version::LT(values.remove(0)),
+            Op::Le => Version::LE(values.remove(0)),
+            Op::In => Version::IN(values),
+            _ => return Err(unsupported($field, $op, $span)),
+        }
+    }};
+}
+
+fn build(field: &str, op: Op, span: &Span, values: Vec<Literal>) -> Result<Expression, ParseError> {
+    let mut expression = Expression::default();
+
+    match field {
+        "ua" => expression.ua = Some(text_condition!(UA, field, op, span, values)),
+        "os" => expression.os = Some(text_condition!(OS, field, op, span, values)),
+        "device" => expression.device = Some(text_condition!(Device, field, op, span, values)),
+        "country" => expression.country = Some(text_condition!(Country, field, op, span, values)),
+        "os_version" => expression.os_version = Some(version_condition!(field, op, span, values)),
+        "ua_version" => expression.ua_version = Some(version_condition!(field, op, span, values)),
+        "device_class" => {
+            let mut values = device_classes(values)?;
+
+            expression.device_class = Some(match op {
+                Op::Eq => DeviceClass::EQ(values.remove(0)),
+                Op::In => DeviceClass::IN(values),
+                _ => return Err(unsupported(field, op, span)),
+            })
+        }
+        "continent" => {
+            expression.continent = Some(text_condition!(Continent, field, op, span, values))
+        }
+        "region" => expression.region = Some(text_condition!(Region, field, op, span, values)),
+        "city" => expression.city = Some(text_condition!(City, field, op, span, values)),
+        "time_zone" => {
+            expression.time_zone = Some(text_condition!(TimeZone, field, op, span, values))
+        }
+        "org" => expression.org = Some(text_condition!(Org, field, op, span, values)),
+        "asn" => {
+            let mut nums = numbers(values, 0, u32::MAX)?;
+
+            expression.asn = Some(match op {
+                Op::Eq => Asn::EQ(nums.remove(0)),
+                Op::In => Asn::IN(nums),
+                _ => return Err(unsupported(field, op, span)),
+            })
+        }
+        "location" => {
+            expression.location = Some(match op {
+                Op::In => Location::Within(geo_circle(values, span)?),
+                _ => return Err(unsupported(field, op, span)),
+            })
+        }
+        "lang" => {
+            expression.lang = Some(match op {
+                Op::Eq => Lang::EQ(first(values)),
+                Op::In => Lang::IN(texts(values)),
+                _ => return Err(unsupported(field, op, span)),
+            })
+        }
+        "date" => {
+            let mut values = dates(values)?;
+
+            expression.date = Some(match op {
+                //stored dates are inclusive and read "value OP request" too
+                Op::Eq => Date::EQ(values.remove(0)),
+                Op::Ge => Date::LT(values.remove(0)),
+                Op::Le => Date::GT(values.remove(0)),
+                Op::In => Date::IN(values),
+                _ => return Err(unsupported(field, op, span)),
+            })
+        }
+        "rnd" => expression.rnd = Some(number_condition!(RND, field, op, span, values, 0, 100)),
+        "day_of_week" => {
+            expression.day_of_week =
+                Some(number_condition!(DayOfWeek, field, op, span, values, 0, 6))
+        }
+        "day_of_month" => {
+            expression.day_of_month = Some(number_condition!(
+                DayOfMonth, field, op, span, values, 1, 31
+            ))
+        }
+        "month" => {
+            expression.month = Some(number_condition!(Month, field, op, span, values, 1, 12))
+        }
+        "hour" => {
+            expression.hour = Some(match op {
+                Op::Between => {
+                    //the end of a range is exclusive, so "between (9, 24)" is allowed
+                    let (from, to) = bounds(numbers(values, 0, 24)?, span)?;
+
+                    Hour::Between(from, to)
+                }
+                _ => {
+                    let mut nums = numbers(values, 0, 23)?;
+
+                    match op {
+                        Op::Eq => Hour::EQ(nums.remove(0)),
+                        Op::Gt => Hour::GT(nums.remove(0)),
+                        Op::Lt => Hour::LT(nums.remove(0)),
+                        Op::In => Hour::IN(nums),
+                        _ => return Err(unsupported(field, op, span)),
+                    }
+                }
+            })
+        }
+        "datetime" => {
+            let mut values = datetimes(values)?;
+
+            expression.datetime = Some(match op {
+                Op::Gt => DateTime::GT(values.remove(0)),
+                Op::Lt => DateTime::LT(values.remove(0)),
+                Op::Between => {
+                    let (from, to) = bounds(values, span)?;
+
+                    DateTime::Between(from, to)
+                }
+                _ => return Err(unsupported(field, op, span)),
+            })
+        }
+        _ => unreachable!("field names are checked against FIELDS"),
+    }
+
+    Ok(expression)
+}
+
+macro_rules! merge_fields {
+    ($target:expr, $leaf:expr, $($field:ident),*) => {{
+        $(
+            if $leaf.$field.is_some() {
+                if $target.$field.is_some() {
+                    return false;
+                }
+
+                $target.$field = $leaf.$field.take();
+                return true;
+            }
+        )*
+
+        false
+    }};
+}
+
+///
+/// Moves the single field of `leaf` into `target` unless `target` already has it.
+///
+fn merge_leaf(target: &mut Expression, leaf: &mut Expression) -> bool {
+    merge_fields!(
+        target,
+        leaf,
+        ua,
+        os,
+        device,
+        os_version,
+        ua_version,
+        device_class,
+        lang,
+        country,
+        continent,
+        region,
+        city,
+        time_zone,
+        asn,
+        org,
+        location,
+        date,
+        rnd,
+        day_of_week,
+        day_of_month,
+        month,
+        hour,
+        datetime
+    )
+}
+
+fn lower(node: Node) -> Expression {
+    match node {
+        Node::Test(expression) => *expression,
+        Node::Not(inner) => Expression {
+            not: Some(Box::new(lower(*inner))),
+            ..Default::default()
+        },
+        Node::And(items) => lower_group(items, DefaultOperator::And),
+        Node::Or(items) => lower_group(items, DefaultOperator::Or),
+    }
+}
+
+///
+/// Flattens comparisons into the fields of a single expression and keeps
+/// everything else (nested groups, negations, repeated fields) as children.
+///
+fn lower_group(items: Vec<Node>, operator: DefaultOperator) -> Expression {
+    let mut result = Expression::default();
+    let mut rest = Vec::new();
+
+    for item in items {
+        match item {
+            Node::Test(mut leaf) => {
+                if !merge_leaf(&mut result, &mut leaf) {
+                    rest.push(leaf);
+                }
+            }
+            other => rest.push(Box::new(lower(other))),
+        }
+    }
+
+    match operator {
+        DefaultOperator::And => {
+            result.default_operator = Some(DefaultOperator::And);
+
+            if !rest.is_empty() {
+                result.and = Some(rest);
+            }
+        }
+        //or is the default operator
+        DefaultOperator::Or => {
+            if !rest.is_empty() {
+                result.or = Some(rest);
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,294 @@
+use crate::expression::{
+    Asn, City, Continent, Country, Date, DateTime, DayOfMonth, DayOfWeek, DefaultOperator, Device,
+    DeviceClass, Expression, Hour, Lang, Location, Month, Org, Region, TimeZone, Version, OS, RND,
+    UA,
+};
+
+use super::{lexer::is_word_char, parser::KEYWORDS};
+
+enum Doc {
+    And(Vec<Doc>),
+    Or(Vec<Doc>),
+    Not(Box<Doc>),
+    Test(String),
+}
+
+const PREC_OR: u8 = 1;
+const PREC_AND: u8 = 2;
+const PREC_NOT: u8 = 3;
+const PREC_TEST: u8 = 4;
+
+impl Doc {
+    fn precedence(&self) -> u8 {
+        match self {
+            Doc::Or(_) => PREC_OR,
+            Doc::And(_) => PREC_AND,
+            Doc::Not(_) => PREC_NOT,
+            Doc::Test(_) => PREC_TEST,
+        }
+    }
+}
+
+///
+/// Prints an expression in the condition language.
+/// Empty expressions have no textual form and are printed as an empty string.
+///
+pub fn print(expression: &Expression) -> String {
+    match to_doc(expression) {
+        Some(doc) => render(&doc, PREC_OR),
+        None => String::new(),
+    }
+}
+
+fn render(doc: &Doc, min_precedence: u8) -> String {
+    let text = match doc {
+        Doc::Test(text) => text.clone(),
+        Doc::Not(inner) => format!("not {}", render(inner, PREC_NOT)),
+        Doc::And(items) => items
+            .iter()
+            .map(|item| render(item, PREC_AND))
+            .collect::<Vec<String>>()
+            .join(" and "),
+        Doc::Or(items) => items
+            .iter()
+            .map(|item| render(item, PREC_OR))
+            .collect::<Vec<String>>()
+            .join(" or "),
+    };
+
+    if doc.precedence() < min_precedence {
+        return format!("({})", text);
+    }
+
+    text
+}
+
+fn group(items: Vec<Doc>, operator: &DefaultOperator) -> Option<Doc> {
+    let mut flat = Vec::new();
+
+    for item in items {
+        match (item, operator) {
+            (Doc::And(children), DefaultOperator::And) => flat.extend(children),
+            (Doc::Or(children), DefaultOperator::Or) => flat.extend(children),
+            (item, _) => flat.push(item),
+        }
+    }
+
+    if flat.len() <= 1 {
+        return flat.pop();
+    }
+
+    match operator {
+        DefaultOperator::And => Some(Doc::And(flat)),
+        DefaultOperator::Or => Some(Doc::Or(flat)),
+    }
+}
+
+fn to_doc(expression: &Expression) -> Option<Doc> {
+    let mut terms: Vec<Doc> = field_terms(expression)
+        .into_iter()
+        .map(Doc::Test)
+        .collect();
+
+    if let Some(and) = &expression.and {
+        let children = and.iter().filter_map(|child| to_doc(child)).collect();
+
+        if let Some(doc) = group(children, &DefaultOperator::And) {
+            terms.push(doc);
+        }
+    }
+
+    if let Some(or) = &expression.or {
+        let children = or.iter().filter_map(|child| to_doc(child)).collect();
+
+        if let Some(doc) = group(children, &DefaultOperator::Or) {
+            terms.push(doc);
+        }
+    }
+
+    if let Some(not) = &expression.not {
+        if let Some(doc) = to_doc(not) {
+            terms.push(Doc::Not(Box::new(doc)));
+        }
+    }
+
+    //or by default
+    let operator = expression
+        .default_operator
+        .as_ref()
+        .unwrap_or(&DefaultOperator::Or);
+
+    group(terms, operator)
+}
+
+fn is_keyword(value: &str) -> bool {
+    KEYWORDS.iter().any(|k| value.eq_ignore_ascii_case(k))
+}
+
+fn quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(is_word_char) && !is_keyword(value) {
+        return value.to_string();
+    }
+
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+    format!("\"{}\"", escaped)
+}
+
+fn list<T: ToString>(values: &[T]) -> String {
+    let items: Vec<String> = values.iter().map(|v| quote(&v.to_string())).collect();
+
+    format!("({})", items.join(", "))
+}
+
+macro_rules! text_term {
+    ($enum:ident, $name:expr, $value:expr) => {
+        match $value {
+            $enum::EQ(v) => format!("{} = {}", $name, quote(v)),
+            $enum::Starts(v) => format!("{} starts {}", $name, quote(v)),
+            $enum::Ends(v) => format!("{} ends {}", $name, quote(v)),
+            $enum::IN(values) => format!("{} in {}", $name, list(values)),
+        }
+    };
+}
+
+//stored as "value OP request", printed as "request OP value" with the operators of `GT` and `LT`
+macro_rules! ordered_term {
+    ($enum:ident, $name:expr, $value:expr, $gt:expr, $lt:expr) => {
+        match $value {
+            $enum::EQ(v) => format!("{} = {}", $name, quote(&v.to_string())),
+            $enum::GT(v) => format!("{} {} {}", $name, $gt, quote(&v.to_string())),
+            $enum::LT(v) => format!("{} {} {}", $name, $lt, quote(&v.to_string())),
+            $enum::IN(values) => format!("{} in {}", $name, list(values)),
+        }
+    };
+}
+
+fn version_term(name: &str, version: &Version) -> String {
+    match version {
+        Version::EQ(v) => format!("{} = {}", name, quote(v)),
+        Version::GT(v) => format!("{} > {}", name, quote(v)),
+        Version::GE(v) => format!("{} >= {}", name, quote(v)),
+        Version::LT(v) => format!("{} < {}", name, quote(v)),
+        Version::LE(v) => format!("{} <= {}", name, quote(v)),
+        Version::IN(values) => format!("{} in {}", name, list(values)),
+    }
+}
+
+fn field_terms(expression: &Expression) -> Vec<String> {
+    let mut terms = Vec::new();
+
+    if let Some(ua) = &expression.ua {
+        terms.push(text_term!(UA, "ua", ua));
+    }
+
+    if let Some(os) = &expression.os {
+        terms.push(text_term!(OS, "os", os));
+    }
+
+    if let Some(device) = &expression.device {
+        terms.push(text_term!(Device, "device", device));
+    }
+
+    if let Some(version) = &expression.os_version {
+        terms.push(version_term("os_version", version));
+    }
+
+    if let Some(version) = &expression.ua_version {
+        terms.push(version_term("ua_version", version));
+    }
+
+    if let Some(device_class) = &expression.device_class {
+        terms.push(match device_class {
+            DeviceClass::EQ(v) => format!("device_class = {}", quote(v)),
+            DeviceClass::IN(values) => format!("device_class in {}", list(values)),
+        });
+    }
+
+    if let Some(lang) = &expression.lang {
+        terms.push(match lang {
+            Lang::EQ(v) => format!("lang = {}", quote(v)),
+            Lang::IN(values) => format!("lang in {}", list(values)),
+        });
+    }
+
+    if let Some(country) = &expression.country {
+        terms.push(text_term!(Country, "country", country));
+    }
+
+    if let Some(continent) = &expression.continent {
+        terms.push(text_term!(Continent, "continent", continent));
+    }
+
+    if let Some(region) = &expression.region {
+        terms.push(text_term!(Region, "region", region));
+    }
+
+    if let Some(city) = &expression.city {
+        terms.push(text_term!(City, "city", city));
+    }
+
+    if let Some(time_zone) = &expression.time_zone {
+        terms.push(text_term!(TimeZone, "time_zone", time_zone));
+    }
+
+    if let Some(asn) = &expression.asn {
+        terms.push(match asn {
+            Asn::EQ(v) => format!("asn = {}", v),
+            Asn::IN(values) => format!("asn in {}", list(values)),
+        });
+    }
+
+    if let Some(org) = &expression.org {
+        terms.push(text_term!(Org, "org", org));
+    }
+
+    if let Some(location) = &expression.location {
+        terms.push(match location {
+            Location::Within(circle) => format!(
+                "location in {}",
+                list(&[circle.latitude, circle.longitude, circle.radius_km])
+            ),
+        });
+    }
+
+    if let Some(date) = &expression.date {
+        terms.push(ordered_term!(Date, "date", date, "<=", ">="));
+    }
+
+    if let Some(rnd) = &expression.rnd {
+        terms.push(ordered_term!(RND, "rnd", rnd, "<", ">"));
+    }
+
+    if let Some(day) = &expression.day_of_week {
+        terms.push(ordered_term!(DayOfWeek, "day_of_week", day, "<", ">"));
+    }
+
+    if let Some(day) = &expression.day_of_month {
+        terms.push(ordered_term!(DayOfMonth, "day_of_month", day, "<", ">"));
+    }
+
+    if let Some(month) = &expression.month {
+        terms.push(ordered_term!(Month, "month", month, "<", ">"));
+    }
+
+    if let Some(hour) = &expression.hour {
+        terms.push(match hour {
+            Hour::Between(from, to) => format!("hour between {}", list(&[from, to])),
+            Hour::EQ(v) => format!("hour = {}", v),
+            Hour::GT(v) => format!("hour > {}", v),
+            Hour::LT(v) => format!("hour < {}", v),
+            Hour::IN(values) => format!("hour in {}", list(values)),
+        });
+    }
+
+    if let Some(datetime) = &expression.datetime {
+        terms.push(match datetime {
+            DateTime::GT(v) => format!("datetime > {}", quote(v)),
+            DateTime::LT(v) => format!("datetime < {}", quote(v)),
+            DateTime::Between(from, to) => format!("datetime between {}", list(&[from, to])),
+        });
+    }
+
+    terms
+}
@@ -0,0 +1,348 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+    slice,
+};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use chrono_tz::TZ_VARIANTS;
+
+use crate::expression::{
+    Asn, City, Continent, Country, Date, DateTime, DayOfMonth, DayOfWeek, Device, DeviceClass,
+    Expression, Hour, Lang, Location, Month, Org, Region, TimeZone, Version, OS, RND, UA,
+};
+
+use super::{
+    parser::{is_version, DEVICE_CLASSES},
+    DATETIME_FORMAT, DATE_FORMAT,
+};
+
+const RND_RANGE: RangeInclusive<u32> = 0..=100;
+const DAY_OF_WEEK_RANGE: RangeInclusive<u32> = 0..=6;
+const DAY_OF_MONTH_RANGE: RangeInclusive<u32> = 1..=31;
+const MONTH_RANGE: RangeInclusive<u32> = 1..=12;
+const HOUR_RANGE: RangeInclusive<u32> = 0..=23;
+//the end of an hour range is exclusive
+const HOUR_END_RANGE: RangeInclusive<u32> = 0..=24;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+///
+/// Checks the values of an expression, shared by the API and the router so that a condition
+/// the API accepts is one the router can compile: numbers in range, known device classes and
+/// time zones, well formed versions, dates and datetimes, no empty lists.
+///
+/// Empty `and`, `or` and `not` groups are rejected as well: the evaluator reads them as
+/// constants (an empty `or` is false, an empty `and` is true) that have no textual form.
+///
+pub fn validate(expression: &Expression) -> Result<(), ValidationError> {
+    if is_empty(expression) {
+        return Err(ValidationError::new("empty condition"));
+    }
+
+    validate_fields(expression)?;
+
+    validate_children("and", expression.and.as_deref())?;
+    validate_children("or", expression.or.as_deref())?;
+
+    if let Some(not) = &expression.not {
+        if is_empty(not) {
+            return Err(ValidationError::new("empty 'not' group"));
+        }
+
+        validate(not)?;
+    }
+
+    Ok(())
+}
+
+fn validate_children(
+    name: &str,
+    children: Option<&[Box<Expression>]>,
+) -> Result<(), ValidationError> {
+    if let Some(children) = children {
+        if children.is_empty() || children.iter().any(|child| is_empty(child)) {
+            return Err(ValidationError::new(format!("empty '{}' group", name)));
+        }
+
+        for child in children {
+            validate(child)?;
+        }
+    }
+
+    Ok(())
+}
+
+macro_rules! text_values {
+    ($enum:ident, $value:expr) => {
+        match $value {
+            $enum::EQ(text) | $enum::Starts(text) | $enum::Ends(text) => slice::from_ref(text),
+            $enum::IN(texts) => texts.as_slice(),
+        }
+    };
+}
+
+macro_rules! number_values {
+    ($enum:ident, $value:expr) => {
+        match $value {
+            $enum::EQ(num) | $enum::GT(num) | $enum::LT(num) => slice::from_ref(num),
+            $enum::IN(nums) => nums.as_slice(),
+        }
+    };
+}
+
+fn validate_fields(expression: &Expression) -> Result<(), ValidationError> {
+    if let Some(ua) = &expression.ua {
+        non_empty("ua", text_values!(UA, ua))?;
+    }
+
+    if let Some(os) = &expression.os {
+        non_empty("os", text_values!(OS, os))?;
+    }
+
+    if let Some(device) = &expression.device {
+        non_empty("device", text_values!(Device, device))?;
+    }
+
+    if let Some(country) = &expression.country {
+        non_empty("country", text_values!(Country, country))?;
+    }
+
+    if let Some(continent) = &expression.continent {
+        non_empty("continent", text_values!(Continent, continent))?;
+    }
+
+    if let Some(region) = &expression.region {
+        non_empty("region", text_values!(Region, region))?;
+    }
+
+    if let Some(city) = &expression.city {
+        non_empty("city", text_values!(City, city))?;
+    }
+
+    if let Some(org) = &expression.org {
+        non_empty("org", text_values!(Org, org))?;
+    }
+
+    if let Some(time_zone) = &expression.time_zone {
+        let names = non_empty("time_zone", text_values!(TimeZone, time_zone))?;
+
+        //only whole names are known zones, "starts Europe/" is fine
+        if let TimeZone::EQ(_) | TimeZone::IN(_) = time_zone {
+            for name in names {
+                if !TZ_VARIANTS
+                    .iter()
+                    .any(|tz| tz.name().eq_ignore_ascii_case(name))
+                {
+                    return Err(ValidationError::new(format!(
+                        "unknown time zone '{}'",
+                        name
+                    )));
+                }
+            }
+        }
+    }
+
+    if let Some(Lang::IN(langs)) = &expression.lang {
+        non_empty("lang", langs)?;
+    }
+
+    if let Some(version) = &expression.os_version {
+        versions("os_version", version)?;
+    }
+
+    if let Some(version) = &expression.ua_version {
+        versions("ua_version", version)?;
+    }
+
+    if let Some(device_class) = &expression.device_class {
+        let names = match device_class {
+            DeviceClass::EQ(name) => slice::from_ref(name),
+            DeviceClass::IN(names) => non_empty("device_class", names)?,
+        };
+
+        for name in names {
+            if !DEVICE_CLASSES.contains(&name.to_ascii_lowercase().as_str()) {
+                return Err(ValidationError::new(format!(
+                    "unknown device class '{}'",
+                    name
+                )));
+            }
+        }
+    }
+
+    if let Some(Asn::IN(nums)) = &expression.asn {
+        non_empty("asn", nums)?;
+    }
+
+    if let Some(Location::Within(circle)) = &expression.location {
+        if !(-90.0..=90.0).contains(&circle.latitude)
+            || !(-180.0..=180.0).contains(&circle.longitude)
+            || !(0.0..).contains(&circle.radius_km)
+        {
+            return Err(ValidationError::new(format!(
+                "invalid location ({}, {}, {})",
+                circle.latitude, circle.longitude, circle.radius_km
+            )));
+        }
+    }
+
+    if let Some(rnd) = &expression.rnd {
+        numbers("rnd", &RND_RANGE, number_values!(RND, rnd))?;
+    }
+
+    if let Some(day) = &expression.day_of_week {
+        numbers(
+            "day_of_week",
+            &DAY_OF_WEEK_RANGE,
+            number_values!(DayOfWeek, day),
+        )?;
+    }
+
+    if let Some(day) = &expression.day_of_month {
+        numbers(
+            "day_of_month",
+            &DAY_OF_MONTH_RANGE,
+            number_values!(DayOfMonth, day),
+        )?;
+    }
+
+    if let Some(month) = &expression.month {
+        numbers("month", &MONTH_RANGE, number_values!(Month, month))?;
+    }
+
+    if let Some(hour) = &expression.hour {
+        match hour {
+            Hour::EQ(num) | Hour::GT(num) | Hour::LT(num) => {
+                numbers("hour", &HOUR_RANGE, slice::from_ref(num))?
+            }
+            Hour::IN(nums) => numbers("hour", &HOUR_RANGE, nums)?,
+            Hour::Between(from, to) => {
+                numbers("hour", &HOUR_RANGE, slice::from_ref(from))?;
+                numbers("hour", &HOUR_END_RANGE, slice::from_ref(to))?;
+            }
+        }
+    }
+
+    if let Some(date) = &expression.date {
+        let values = match date {
+            Date::EQ(value) | Date::GT(value) | Date::LT(value) => slice::from_ref(value),
+            Date::IN(values) => non_empty("date", values)?,
+        };
+
+        for value in values {
+            parse_date(value)?;
+        }
+    }
+
+    if let Some(datetime) = &expression.datetime {
+        match datetime {
+            DateTime::GT(value) | DateTime::LT(value) => {
+                parse_datetime(value)?;
+            }
+            DateTime::Between(from, to) => {
+                let (from, to) = (parse_datetime(from)?, parse_datetime(to)?);
+
+                if from >= to {
+                    return Err(ValidationError::new(format!(
+                        "empty datetime range {} - {}",
+                        from, to
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn versions(name: &str, version: &Version) -> Result<(), ValidationError> {
+    let values = match version {
+        Version::EQ(value)
+        | Version::GT(value)
+        | Version::GE(value)
+        | Version::LT(value)
+        | Version::LE(value) => slice::from_ref(value),
+        Version::IN(values) => non_empty(name, values)?,
+    };
+
+    for value in values {
+        if !is_version(value.trim()) {
+            return Err(ValidationError::new(format!(
+                "invalid version '{}' of field '{}'",
+                value, name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn numbers(name: &str, range: &RangeInclusive<u32>, values: &[u32]) -> Result<(), ValidationError> {
+    for value in non_empty(name, values)? {
+        if !range.contains(value) {
+            return Err(ValidationError::new(format!(
+                "value {} of field '{}' is out of range {}..={}",
+                value,
+                name,
+                range.start(),
+                range.end()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn non_empty<'a, T>(name: &str, values: &'a [T]) -> Result<&'a [T], ValidationError> {
+    if values.is_empty() {
+        return Err(ValidationError::new(format!(
+            "empty list for field '{}'",
+            name
+        )));
+    }
+
+    Ok(values)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, ValidationError> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .map_err(|_| ValidationError::new(format!("invalid date '{}', expected YYYYMMDD", value)))
+}
+
+fn parse_datetime(value: &str) -> Result<NaiveDateTime, ValidationError> {
+    NaiveDateTime::parse_from_str(value, DATETIME_FORMAT).map_err(|_| {
+        ValidationError::new(format!(
+            "invalid datetime '{}', expected YYYY-MM-DDTHH:MM",
+            value
+        ))
+    })
+}
+
+//an expression without any field or group, only the default operator
+fn is_empty(expression: &Expression) -> bool {
+    *expression
+        == Expression {
+            default_operator: expression.default_operator.clone(),
+            ..Default::default()
+        }
+}
@@ -14,6 +14,18 @@ resource "aws_dynamodb_table" "core-routes-table" {
     type = "S"
   }
 
+  attribute {
+    name = "pattern.domain"
+    type = "S"
+  }
+
+  global_secondary_index {
+    name            = "patterns"
+    hash_key        = "pattern.domain"
+    range_key       = "switch"
+    projection_type = "ALL"
+  }
+
   ttl {
     attribute_name = "TimeToExist"
     enabled        = false
@@ -0,0 +1,197 @@
+//!
+//! Keys of the links, `{domain}%2f{path}`. Domains are case insensitive, paths too unless their
+//! domain has case sensitive links, e.g. for dense base62 codes.
+//!
+//! The router looks the links up and the API stores them with the same keys.
+//!
+//the `&'static str` consts are spelled out like in the other crates of the workspace
+#![allow(clippy::redundant_static_lifetimes)]
+
+use std::collections::HashMap;
+
+mod settings;
+
+pub use settings::{CaseSensitiveLinks, Links};
+
+pub const DOMAIN_SEPARATOR: &'static str = "%2f";
+
+///
+/// Domain and path of a `{domain}%2f{path}` link, whatever the case of its separator.
+///
+pub fn split_link(link: &str) -> Option<(&str, &str)> {
+    let index = link.to_ascii_lowercase().find(DOMAIN_SEPARATOR)?;
+
+    Some((&link[..index], &link[index + DOMAIN_SEPARATOR.len()..]))
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LinkKeys {
+    //lowercase fallback of the case sensitive domains
+    case_sensitive: HashMap<String, bool>,
+}
+
+impl LinkKeys {
+    pub fn new(case_sensitive: impl IntoIterator<Item = (String, bool)>) -> Self {
+        Self {
+            case_sensitive: case_sensitive
+                .into_iter()
+                .map(|(domain, fallback)| (domain.to_ascii_lowercase(), fallback))
+                .collect(),
+        }
+    }
+
+    pub fn from_settings(settings: &Links) -> Self {
+        Self::new(
+            settings
+                .case_sensitive
+                .iter()
+                .map(|links| (links.domain.clone(), links.lowercase_fallback)),
+        )
+    }
+
+    pub fn is_case_sensitive(&self, domain: &str) -> bool {
+        self.case_sensitive
+            .contains_key(&domain.to_ascii_lowercase())
+    }
+
+    ///
+    /// Path as stored in the links of the domain.
+    ///
+    pub fn path(&self, domain: &str, path: &str) -> String {
+        match self.is_case_sensitive(domain) {
+            true => path.to_string(),
+            false => path.to_ascii_lowercase(),
+        }
+    }
+
+    ///
+    /// Domain and path as stored in the links.
+    ///
+    pub fn normalize(&self, domain: &str, path: &str) -> (String, String) {
+        (domain.to_ascii_lowercase(), self.path(domain, path))
+    }
+
+    ///
+    /// Domain and path of a `{domain}%2f{path}` link, as stored.
+    ///
+    pub fn split(&self, link: &str) -> Option<(String, String)> {
+        let (domain, path) = split_link(link)?;
+
+        Some(self.normalize(domain, path))
+    }
+
+    pub fn key(&self, domain: &str, path: &str) -> String {
+        format!(
+            "{}{}{}",
+            domain.to_ascii_lowercase(),
+            DOMAIN_SEPARATOR,
+            self.path(domain, path)
+        )
+    }
+
+    ///
+    /// Key of a `{domain}%2f{path}` link, whatever the case of its separator.
+    ///
+    pub fn link(&self, link: &str) -> String {
+        match split_link(link) {
+            Some((domain, path)) => self.key(domain, path),
+            None => link.to_ascii_lowercase(),
+        }
+    }
+
+    ///
+    /// Lowercase path of a link stored before its domain became case sensitive, when the domain
+    /// falls back to them and the path is not lowercase already.
+    ///
+    pub fn lowercase_path(&self, domain: &str, path: &str) -> Option<String> {
+        let fallback = self.case_sensitive.get(&domain.to_ascii_lowercase())?;
+
+        let lowercase = path.to_ascii_lowercase();
+
+        (*fallback && lowercase != path).then_some(lowercase)
+    }
+
+    ///
+    /// Key of the lowercase link, see `lowercase_path`.
+    ///
+    pub fn lowercase_key(&self, domain: &str, path: &str) -> Option<String> {
+        self.lowercase_path(domain, path).map(|lowercase| {
+            format!(
+                "{}{}{}",
+                domain.to_ascii_lowercase(),
+                DOMAIN_SEPARATOR,
+                lowercase
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link_keys() -> LinkKeys {
+        LinkKeys::new([
+            ("Go.Brand.com".to_string(), true),
+            ("b62.brand.com".to_string(), false),
+        ])
+    }
+
+    #[test]
+    fn should_split_links() {
+        assert_eq!(
+            split_link("docs.brand.com%2Fdocs/{*rest}"),
+            Some(("docs.brand.com", "docs/{*rest}"))
+        );
+        assert_eq!(split_link("docs.brand.com"), None);
+
+        assert_eq!(
+            link_keys().split("GO.brand.com%2FAbC"),
+            Some(("go.brand.com".to_string(), "AbC".to_string()))
+        );
+        assert_eq!(
+            link_keys().split("Docs.brand.com%2FAbC"),
+            Some(("docs.brand.com".to_string(), "abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_lowercase_links_of_other_domains() {
+        let link_keys = link_keys();
+
+        assert_eq!(
+            link_keys.key("Docs.Brand.com", "AbC"),
+            "docs.brand.com%2fabc"
+        );
+        assert_eq!(
+            link_keys.link("Docs.Brand.com%2FAbC"),
+            "docs.brand.com%2fabc"
+        );
+        assert_eq!(link_keys.lowercase_key("docs.brand.com", "AbC"), None);
+    }
+
+    #[test]
+    fn should_keep_the_case_of_case_sensitive_paths() {
+        let link_keys = link_keys();
+
+        assert_eq!(link_keys.key("GO.brand.com", "AbC"), "go.brand.com%2fAbC");
+        assert_eq!(link_keys.link("go.brand.com%2FAbC"), "go.brand.com%2fAbC");
+        assert_eq!(link_keys.key("b62.brand.com", "AbC"), "b62.brand.com%2fAbC");
+    }
+
+    #[test]
+    fn should_fall_back_to_lowercase_links() {
+        let link_keys = link_keys();
+
+        assert_eq!(
+            link_keys.lowercase_key("go.brand.com", "AbC").as_deref(),
+            Some("go.brand.com%2fabc")
+        );
+        assert_eq!(
+            link_keys.lowercase_path("Go.Brand.com", "AbC").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(link_keys.lowercase_key("go.brand.com", "abc"), None);
+        assert_eq!(link_keys.lowercase_key("b62.brand.com", "AbC"), None);
+    }
+}
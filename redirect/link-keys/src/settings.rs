@@ -0,0 +1,22 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(unused)]
+pub struct CaseSensitiveLinks {
+    pub domain: String,
+    /// Also looks up the lowercase path, for the links created before the switch.
+    #[serde(default = "default_lowercase_fallback")]
+    pub lowercase_fallback: bool,
+}
+
+fn default_lowercase_fallback() -> bool {
+    true
+}
+
+#[derive(Default, Debug, Deserialize, Clone)]
+#[serde(default)]
+#[allow(unused)]
+pub struct Links {
+    /// Domains whose link paths are case sensitive, other paths are lowercase.
+    pub case_sensitive: Vec<CaseSensitiveLinks>,
+}
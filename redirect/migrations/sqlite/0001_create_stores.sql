@@ -0,0 +1,42 @@
+-- Routes, user settings and certificates of the SQLite stores, shared by the router and the API.
+
+CREATE TABLE IF NOT EXISTS routes (
+    switch TEXT NOT NULL,
+    -- '{domain}%2f{path}', lowercase
+    link TEXT NOT NULL,
+    dest TEXT,
+    -- 'http' or 'native'
+    dest_format TEXT NOT NULL DEFAULT 'http',
+    code INTEGER,
+    ttl INTEGER,
+    blocked INTEGER NOT NULL DEFAULT 0,
+    blocked_reason TEXT,
+    -- 'external', 'internal' or 'middleware'
+    terminal TEXT NOT NULL DEFAULT 'external',
+    -- JSON of the routing policy, e.g. '"Basic"' or '{"Conditional":[...]}'
+    policy TEXT NOT NULL DEFAULT '"Basic"',
+    -- JSON of the route properties
+    properties TEXT NOT NULL DEFAULT '{}',
+    PRIMARY KEY (switch, link)
+);
+
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id TEXT NOT NULL PRIMARY KEY,
+    user_email TEXT NOT NULL,
+    api_key TEXT,
+    -- 'active' or 'blocked'
+    status TEXT NOT NULL DEFAULT 'active',
+    debug INTEGER NOT NULL DEFAULT 0,
+    overflow INTEGER NOT NULL DEFAULT 0,
+    -- JSON arrays of strings
+    skip TEXT NOT NULL DEFAULT '[]',
+    request_params TEXT NOT NULL DEFAULT '[]',
+    destination_params TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE TABLE IF NOT EXISTS certificates (
+    -- lowercase server name
+    hostname TEXT NOT NULL PRIMARY KEY,
+    key BLOB NOT NULL,
+    cert BLOB NOT NULL
+);